@@ -0,0 +1,198 @@
+//! Calendar billing periods: when the next payment of a plan is due, computed from blocktime
+//! in UTC.
+
+/// Named key holding the interval type of the plan, an `IntervalType` as a `u8`.
+pub const INTERVAL_TYPE: &str = "interval_type";
+
+/// Named key holding the day payments are due on: the weekday of a weekly plan (1 for Monday to
+/// 7 for Sunday), or the day of the month of a monthly or yearly plan (1 to 31).
+pub const BILLING_DAY: &str = "billing_day";
+
+/// Milliseconds in a day, blocktime having no leap seconds.
+pub const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Enum for IntervalType, how the plan counts the time between two payments.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalType {
+    /// 0 for a fixed number of `period_seconds`
+    Seconds = 0,
+    /// 1 for every day at midnight UTC
+    Daily = 1,
+    /// 2 for every week, on the billing weekday
+    Weekly = 2,
+    /// 3 for every month, on the billing day of the month
+    Monthly = 3,
+    /// 4 for every year, on the billing day of the month the subscription started in
+    Yearly = 4,
+}
+
+impl IntervalType {
+    /// Read an interval type stored in the `interval_type` named key, `None` for an unknown value.
+    pub fn from_u8(interval_type: u8) -> Option<IntervalType> {
+        match interval_type {
+            0 => Some(IntervalType::Seconds),
+            1 => Some(IntervalType::Daily),
+            2 => Some(IntervalType::Weekly),
+            3 => Some(IntervalType::Monthly),
+            4 => Some(IntervalType::Yearly),
+            _ => None,
+        }
+    }
+}
+
+/// The billing period of a plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BillingInterval {
+    pub interval_type: IntervalType,
+    /// Seconds between two payments, only used by `IntervalType::Seconds`.
+    pub period_seconds: u64,
+    /// See `BILLING_DAY`, ignored by `IntervalType::Seconds` and `IntervalType::Daily`.
+    pub billing_day: u8,
+}
+
+impl BillingInterval {
+    /// Whether the billing day is in range for the interval type.
+    pub fn is_valid(&self) -> bool {
+        match self.interval_type {
+            IntervalType::Seconds | IntervalType::Daily => true,
+            IntervalType::Weekly => (1..=7).contains(&self.billing_day),
+            IntervalType::Monthly | IntervalType::Yearly => (1..=31).contains(&self.billing_day),
+        }
+    }
+
+    /// When the payment after the one due at `timestamp` is due, both in milliseconds.
+    ///
+    /// A fixed period adds `period_seconds`. Calendar periods are due at midnight UTC of the
+    /// first billing day strictly after `timestamp`, so payments do not drift against the
+    /// calendar and the first period of a new subscription may be short. A billing day past the
+    /// end of a month falls on its last day, without moving the following payments.
+    pub fn next_timestamp(&self, timestamp: u64) -> u64 {
+        let day = timestamp / MILLIS_PER_DAY;
+        let next_day = match self.interval_type {
+            IntervalType::Seconds => return timestamp.saturating_add(self.period_seconds.saturating_mul(1000)),
+            IntervalType::Daily => day + 1,
+            IntervalType::Weekly => day + days_until_weekday(weekday(day), self.billing_day),
+            IntervalType::Monthly => {
+                let (year, month, day_of_month) = civil_from_days(day);
+                let (year, month) = if clamp_day(year, month, self.billing_day) > day_of_month {
+                    (year, month)
+                } else {
+                    next_month(year, month)
+                };
+                days_from_civil(year, month, clamp_day(year, month, self.billing_day))
+            }
+            IntervalType::Yearly => {
+                let (year, month, day_of_month) = civil_from_days(day);
+                let year = if clamp_day(year, month, self.billing_day) > day_of_month { year } else { year + 1 };
+                days_from_civil(year, month, clamp_day(year, month, self.billing_day))
+            }
+        };
+        next_day.saturating_mul(MILLIS_PER_DAY)
+    }
+
+    /// When the payment before the one due at `timestamp` was due, the start of the period ending
+    /// at `timestamp`, a due date as `next_timestamp` returns them.
+    pub fn previous_timestamp(&self, timestamp: u64) -> u64 {
+        let day = timestamp / MILLIS_PER_DAY;
+        let previous_day = match self.interval_type {
+            IntervalType::Seconds => return timestamp.saturating_sub(self.period_seconds.saturating_mul(1000)),
+            IntervalType::Daily => day.saturating_sub(1),
+            IntervalType::Weekly => day.saturating_sub(7),
+            IntervalType::Monthly => {
+                let (year, month, _) = civil_from_days(day);
+                let (year, month) = previous_month(year, month);
+                days_from_civil(year, month, clamp_day(year, month, self.billing_day))
+            }
+            IntervalType::Yearly => {
+                let (year, month, _) = civil_from_days(day);
+                days_from_civil(year - 1, month, clamp_day(year - 1, month, self.billing_day))
+            }
+        };
+        previous_day.saturating_mul(MILLIS_PER_DAY)
+    }
+
+    /// The earliest a price change announced at `timestamp` can take effect: the end of the first
+    /// whole period after it, so every subscriber has a billing cycle to cancel in.
+    pub fn notice_end(&self, timestamp: u64) -> u64 {
+        match self.interval_type {
+            IntervalType::Seconds => self.next_timestamp(timestamp),
+            _ => self.next_timestamp(self.next_timestamp(timestamp)),
+        }
+    }
+}
+
+/// Whether `year` has a February 29th in the Gregorian calendar.
+pub fn is_leap_year(year: u64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Number of days of `month` (1 to 12) in `year`.
+pub fn days_in_month(year: u64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The date `(year, month, day)` of a number of days since January 1st, 1970.
+pub fn civil_from_days(days: u64) -> (u64, u32, u32) {
+    // Howard Hinnant's algorithm, with eras of 400 years starting on March 1st
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// The number of days since January 1st, 1970 of a date, 0 for a date before 1970.
+pub fn days_from_civil(year: u64, month: u32, day: u32) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let month_from_march = u64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * month_from_march + 2) / 5 + u64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    (era * 146_097 + day_of_era).saturating_sub(719_468)
+}
+
+/// The ISO weekday of a number of days since January 1st, 1970, 1 for Monday to 7 for Sunday.
+pub fn weekday(days: u64) -> u8 {
+    // January 1st, 1970 was a Thursday
+    ((days + 3) % 7 + 1) as u8
+}
+
+/// Days from `weekday` to the next `billing_weekday`, 1 to 7.
+fn days_until_weekday(weekday: u8, billing_weekday: u8) -> u64 {
+    u64::from((billing_weekday + 6 - weekday) % 7 + 1)
+}
+
+/// The billing day within `month`, the last day of the month for a day past its end.
+fn clamp_day(year: u64, month: u32, billing_day: u8) -> u32 {
+    u32::from(billing_day).min(days_in_month(year, month))
+}
+
+fn next_month(year: u64, month: u32) -> (u64, u32) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+fn previous_month(year: u64, month: u32) -> (u64, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
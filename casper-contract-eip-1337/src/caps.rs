@@ -0,0 +1,111 @@
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{ApiError, URef, U256};
+
+use crate::errors::ContractError;
+use crate::utils;
+
+pub const MAX_AMOUNTS_PER_PERIOD_DICT: &str = "max_amounts_per_period";
+pub const LIFETIME_CAPS_DICT: &str = "lifetime_caps";
+pub const AMOUNTS_PAID_DICT: &str = "amounts_paid";
+
+/// Spending caps the subscriber signed and what each subscription has paid, keyed by
+/// subscription id (decimal string). The caps are part of the subscription terms, so they hold
+/// whatever the publisher does to prices and regardless of the erc20 allowance: a payment past
+/// either reverts rather than charging less than the plan's price.
+pub struct Caps {
+    max_amounts_per_period_dict_uref: URef,
+    lifetime_caps_dict_uref: URef,
+    amounts_paid_dict_uref: URef,
+}
+
+impl Caps {
+    pub fn new() -> Caps {
+        Caps {
+            max_amounts_per_period_dict_uref: utils::get_dictionary_uref(MAX_AMOUNTS_PER_PERIOD_DICT),
+            lifetime_caps_dict_uref: utils::get_dictionary_uref(LIFETIME_CAPS_DICT),
+            amounts_paid_dict_uref: utils::get_dictionary_uref(AMOUNTS_PAID_DICT),
+        }
+    }
+
+    /// Record the caps of a new subscription, reverting with `InvalidMaxAmount` for a cap below
+    /// `token_amount`, which could never pay for a period.
+    pub fn set(&self, subscription_id: u64, token_amount: U256, max_amount_per_period: Option<U256>, lifetime_cap: Option<U256>) {
+        let key = &subscription_id.to_string();
+
+        require_caps_cover(max_amount_per_period, lifetime_cap, token_amount);
+
+        if let Some(max_amount_per_period) = max_amount_per_period {
+            storage::dictionary_put(self.max_amounts_per_period_dict_uref, key, max_amount_per_period);
+        }
+        if let Some(lifetime_cap) = lifetime_cap {
+            storage::dictionary_put(self.lifetime_caps_dict_uref, key, lifetime_cap);
+        }
+    }
+
+    /// Revert with `InvalidMaxAmount` if a cap of the subscription is below `amount`, the price of
+    /// a period for all its seats, e.g. before a seat is added.
+    pub fn require_cover(&self, subscription_id: u64, amount: U256) {
+        let (max_amount_per_period, lifetime_cap) = self.get(subscription_id);
+
+        require_caps_cover(max_amount_per_period, lifetime_cap, amount);
+    }
+
+    /// The caps the subscriber signed, each `None` when left out.
+    pub fn get(&self, subscription_id: u64) -> (Option<U256>, Option<U256>) {
+        (self.get_max_amount_per_period(subscription_id), self.get_lifetime_cap(subscription_id))
+    }
+
+    /// The most a payment can charge, `None` without a cap.
+    pub fn get_max_amount_per_period(&self, subscription_id: u64) -> Option<U256> {
+        storage::dictionary_get(self.max_amounts_per_period_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    pub fn get_lifetime_cap(&self, subscription_id: u64) -> Option<U256> {
+        storage::dictionary_get(self.lifetime_caps_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// Everything the subscription has paid, less the payments refunded or charged back.
+    pub fn get_amount_paid(&self, subscription_id: u64) -> U256 {
+        storage::dictionary_get(self.amounts_paid_dict_uref, &subscription_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default()
+    }
+
+    /// Revert with `PeriodCapReached` if `amount` is above the max amount per period, and with
+    /// `SpendingCapReached` if paying it would take the subscription past its lifetime cap.
+    pub fn check(&self, subscription_id: u64, amount: U256) {
+        if self.get_max_amount_per_period(subscription_id).map_or(false, |max_amount_per_period| amount > max_amount_per_period) {
+            runtime::revert(ApiError::User(ContractError::PeriodCapReached as u16));
+        }
+        if let Some(lifetime_cap) = self.get_lifetime_cap(subscription_id) {
+            let within_cap = self.get_amount_paid(subscription_id).checked_add(amount).map_or(false, |total| total <= lifetime_cap);
+            if !within_cap {
+                runtime::revert(ApiError::User(ContractError::SpendingCapReached as u16));
+            }
+        }
+    }
+
+    /// Count a payment towards the lifetime cap, reverting like `check` past a cap.
+    pub fn add_payment(&self, subscription_id: u64, amount: U256) {
+        self.check(subscription_id, amount);
+
+        let total = self.get_amount_paid(subscription_id).saturating_add(amount);
+        storage::dictionary_put(self.amounts_paid_dict_uref, &subscription_id.to_string(), total);
+    }
+
+    /// Take a refunded or charged back payment off what the subscription has paid.
+    pub fn sub_payment(&self, subscription_id: u64, amount: U256) {
+        let total = self.get_amount_paid(subscription_id).saturating_sub(amount);
+        storage::dictionary_put(self.amounts_paid_dict_uref, &subscription_id.to_string(), total);
+    }
+}
+
+/// Revert with `InvalidMaxAmount` if either cap is below `amount`, which it could never pay.
+fn require_caps_cover(max_amount_per_period: Option<U256>, lifetime_cap: Option<U256>, amount: U256) {
+    if max_amount_per_period.into_iter().chain(lifetime_cap).any(|cap| cap < amount) {
+        runtime::revert(ApiError::User(ContractError::InvalidMaxAmount as u16));
+    }
+}
@@ -16,5 +16,53 @@ pub const PUBLIC: &str= "public";
 pub const SIGNATURE: &str= "signature";
 /// Key For erc20 Contract Hash
 pub const ERC20_CONTRACT_HASH: &str= "erc20_contract_hash";
-///Key For the nextvalidtimestamp
+///Field For the nextvalidtimestamp
 pub const NEXT_VALID_TIMESTAMP: &str= "next_valid_timestamp"; 
+///Key For the contract package hash
+pub const PACKAGE_HASH: &str= "package_hash";
+///Field For the subscription status
+pub const STATUS: &str= "status";
+///Key For the first index position of a page
+pub const OFFSET: &str= "offset";
+///Key For the number of index positions in a page
+pub const LIMIT: &str= "limit";
+///Key For the subscription id
+pub const SUBSCRIPTION_ID: &str= "subscription_id";
+///Key For the account paid a share of each payment
+pub const RECIPIENT: &str= "recipient";
+///Key For the share of each payment in basis points
+pub const BASIS_POINTS: &str= "basis_points";
+///Key For the id of a payment held in escrow
+pub const PAYMENT_ID: &str= "payment_id";
+///Key For the account of an escrow balance
+pub const ACCOUNT: &str= "account";
+///Key For a coupon code
+pub const COUPON_CODE: &str= "coupon_code";
+///Key For how a coupon takes its discount off, a `DiscountType` as a u8
+pub const DISCOUNT_TYPE: &str= "discount_type";
+///Key For the discount of a coupon, basis points or a token amount
+pub const DISCOUNT: &str= "discount";
+///Key For the number of payments a coupon discounts
+pub const PERIODS: &str= "periods";
+///Key For the number of times a coupon can be redeemed
+pub const MAX_REDEMPTIONS: &str= "max_redemptions";
+///Key For the blocktime in milliseconds a coupon expires
+pub const EXPIRES_AT: &str= "expires_at";
+///Key For the most a payment of a subscription can charge
+pub const MAX_AMOUNT_PER_PERIOD: &str= "max_amount_per_period";
+///Key For the most a subscription can charge over its lifetime
+pub const LIFETIME_CAP: &str= "lifetime_cap";
+///Key For whether the usage reported for a subscription is charged on top of its price
+pub const METERED: &str= "metered";
+///Key For the price of a unit of usage
+pub const UNIT_PRICE: &str= "unit_price";
+///Key For units of usage reported for a subscription
+pub const USAGE: &str= "usage";
+///Key For the account allowed to report usage besides the publisher
+pub const ORACLE: &str= "oracle";
+///Key For the blocktime in milliseconds a price change takes effect
+pub const EFFECTIVE_AT: &str= "effective_at";
+///Key For the account given access to a subscription another account pays for
+pub const BENEFICIARY: &str= "beneficiary";
+///Key For an account holding a seat of a subscription
+pub const MEMBER: &str= "member";
@@ -0,0 +1,133 @@
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{bytesrepr::FromBytes, ApiError, CLTyped, ContractHash, URef, U256};
+
+use crate::errors::ContractError;
+use crate::pricing::{self, DiscountType};
+use crate::utils;
+
+pub const COUPON_TYPES_DICT: &str = "coupon_types";
+pub const COUPON_DISCOUNTS_DICT: &str = "coupon_discounts";
+pub const COUPON_TOKENS_DICT: &str = "coupon_tokens";
+pub const COUPON_PERIODS_DICT: &str = "coupon_periods";
+pub const COUPON_MAX_REDEMPTIONS_DICT: &str = "coupon_max_redemptions";
+pub const COUPON_EXPIRATIONS_DICT: &str = "coupon_expirations";
+pub const COUPON_REDEMPTIONS_DICT: &str = "coupon_redemptions";
+
+/// Longest coupon code, the longest dictionary item key.
+pub const MAX_CODE_LENGTH: usize = 64;
+
+/// A coupon as applied to a subscription.
+pub struct Discount {
+    pub discount_type: DiscountType,
+    pub discount: U256,
+    /// Payments the discount applies to, 0 for every payment
+    pub periods: u64,
+}
+
+/// Publisher-managed coupons, keyed by coupon code.
+/// For `periods`, `max_redemptions` and `expires_at`, 0 means no limit.
+pub struct Coupons {
+    types_dict_uref: URef,
+    discounts_dict_uref: URef,
+    tokens_dict_uref: URef,
+    periods_dict_uref: URef,
+    max_redemptions_dict_uref: URef,
+    expirations_dict_uref: URef,
+    redemptions_dict_uref: URef,
+}
+
+impl Coupons {
+    pub fn new() -> Coupons {
+        Coupons {
+            types_dict_uref: utils::get_dictionary_uref(COUPON_TYPES_DICT),
+            discounts_dict_uref: utils::get_dictionary_uref(COUPON_DISCOUNTS_DICT),
+            tokens_dict_uref: utils::get_dictionary_uref(COUPON_TOKENS_DICT),
+            periods_dict_uref: utils::get_dictionary_uref(COUPON_PERIODS_DICT),
+            max_redemptions_dict_uref: utils::get_dictionary_uref(COUPON_MAX_REDEMPTIONS_DICT),
+            expirations_dict_uref: utils::get_dictionary_uref(COUPON_EXPIRATIONS_DICT),
+            redemptions_dict_uref: utils::get_dictionary_uref(COUPON_REDEMPTIONS_DICT),
+        }
+    }
+
+    /// Create a coupon, reverting with `CouponExists` if the code is taken and with
+    /// `InvalidCoupon` for a code that is empty or too long or a percentage over 100%.
+    /// A fixed amount is taken off in `erc20_contract_hash`, the coupon only applies to it.
+    pub fn add(&self, code: &str, discount: Discount, erc20_contract_hash: ContractHash, max_redemptions: u64, expires_at: u64) {
+        if code.is_empty() || code.len() > MAX_CODE_LENGTH || !pricing::is_valid_discount(discount.discount_type, discount.discount) {
+            runtime::revert(ApiError::User(ContractError::InvalidCoupon as u16));
+        }
+        if self.get_type(code).is_some() {
+            runtime::revert(ApiError::User(ContractError::CouponExists as u16));
+        }
+
+        storage::dictionary_put(self.types_dict_uref, code, discount.discount_type as u8);
+        storage::dictionary_put(self.discounts_dict_uref, code, discount.discount);
+        if discount.discount_type == DiscountType::FixedAmount {
+            storage::dictionary_put(self.tokens_dict_uref, code, erc20_contract_hash);
+        }
+        storage::dictionary_put(self.periods_dict_uref, code, discount.periods);
+        storage::dictionary_put(self.max_redemptions_dict_uref, code, max_redemptions);
+        storage::dictionary_put(self.expirations_dict_uref, code, expires_at);
+        storage::dictionary_put(self.redemptions_dict_uref, code, 0u64);
+    }
+
+    fn get_type(&self, code: &str) -> Option<u8> {
+        if code.is_empty() || code.len() > MAX_CODE_LENGTH {
+            return None;
+        }
+        storage::dictionary_get(self.types_dict_uref, code).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    fn get<T: FromBytes + CLTyped>(&self, dict_uref: URef, code: &str) -> T {
+        storage::dictionary_get(dict_uref, code)
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::UnknownCoupon)
+    }
+
+    /// Redeem a coupon for a subscription paying in `erc20_contract_hash` at `blocktime`.
+    /// Reverts with `UnknownCoupon`, `CouponExpired` once `expires_at` is reached,
+    /// `CouponExhausted` once redeemed `max_redemptions` times and `InvalidCoupon` for a
+    /// fixed amount in another token.
+    pub fn redeem(&self, code: &str, erc20_contract_hash: ContractHash, blocktime: u64) -> Discount {
+        let discount_type = self.get_type(code)
+            .and_then(DiscountType::from_u8)
+            .unwrap_or_revert_with(ContractError::UnknownCoupon);
+
+        let expires_at: u64 = self.get(self.expirations_dict_uref, code);
+        if expires_at != 0 && blocktime >= expires_at {
+            runtime::revert(ApiError::User(ContractError::CouponExpired as u16));
+        }
+
+        let max_redemptions: u64 = self.get(self.max_redemptions_dict_uref, code);
+        let redemptions: u64 = self.get(self.redemptions_dict_uref, code);
+        if max_redemptions != 0 && redemptions >= max_redemptions {
+            runtime::revert(ApiError::User(ContractError::CouponExhausted as u16));
+        }
+
+        if discount_type == DiscountType::FixedAmount {
+            let coupon_token: ContractHash = self.get(self.tokens_dict_uref, code);
+            if coupon_token != erc20_contract_hash {
+                runtime::revert(ApiError::User(ContractError::InvalidCoupon as u16));
+            }
+        }
+
+        storage::dictionary_put(self.redemptions_dict_uref, code, redemptions + 1);
+
+        Discount {
+            discount_type,
+            discount: self.get(self.discounts_dict_uref, code),
+            periods: self.get(self.periods_dict_uref, code),
+        }
+    }
+
+    /// Stop a coupon from being redeemed from `blocktime` on. Subscriptions that redeemed it keep their discount.
+    pub fn expire(&self, code: &str, blocktime: u64) {
+        if self.get_type(code).is_none() {
+            runtime::revert(ApiError::User(ContractError::UnknownCoupon as u16));
+        }
+        storage::dictionary_put(self.expirations_dict_uref, code, blocktime.max(1));
+    }
+}
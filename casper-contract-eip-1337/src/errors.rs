@@ -0,0 +1,237 @@
+use types::ApiError;
+
+/// Enum for ContractError, It represents codes for different smart contract errors.
+///
+/// The contract reverts with `ApiError::User(code)`, which a deploy result reports as
+/// `User error: <code>`.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractError {
+    /// 17 for signature verification error
+    SignatureVerificationFailed = 17,
+    /// 18 for invalid signature
+    InvalidSignature = 18,
+    /// 19, retired: an account can register any number of subscriptions, each under its own id.
+    /// Kept so the code is not reused and older deploy results still decode.
+    HashExists = 19,
+    /// 20, retired: signer verification failures revert with `SignatureVerificationFailed`. Kept so
+    /// the code is not reused and older deploy results still decode.
+    SignerFailed = 20,
+    /// 21 for somehow missing public_key
+    MissingPublicKey = 21,
+    /// 22 or blocktime less than nextvalidtimestamp
+    InvalidBlockTime = 22,
+    /// 23 for allowance is less than token_amount
+    InsufficientAllowance = 23,
+    /// 24 for subcription not active
+    SubscriptionNotActive = 24,
+    /// 25 for the caller of the entry point could not be read
+    ReadingCallerError = 25,
+    /// 26 for a signature that is not valid hex
+    MalformedSignatureHex = 26,
+    /// 27 for a signature that does not decode to 64 bytes
+    InvalidSignatureLength = 27,
+    /// 28 for a registered public key that is not Ed25519
+    UnsupportedKeyType = 28,
+    /// 29 for a named key the contract relies on is missing
+    MissingKey = 29,
+    /// 30 for a named key that does not hold a value of the expected type
+    InvalidKey = 30,
+    /// 31 for a dictionary the contract relies on is missing
+    MissingDictionary = 31,
+    /// 32 for a dictionary item that could not be read
+    DictionaryError = 32,
+    /// 33 for an `erc20_contract_hash` install argument that is not a hash key
+    InvalidContractHash = 33,
+    /// 34 for erc20 balance is less than token_amount
+    InsufficientBalance = 34,
+    /// 35 for no subscription registered under the subscription hash or id
+    UnknownSubscription = 35,
+    /// 36 for a runtime argument that does not deserialize to the expected type
+    InvalidArgument = 36,
+    /// 37 for an erc20 token the plan does not accept
+    UnsupportedToken = 37,
+    /// 38 for an erc20 token the plan already accepts
+    TokenExists = 38,
+    /// 39 for a caller that is not the publisher of the plan
+    NotPublisher = 39,
+    /// 40 for a payee that would take the payouts past 10000 basis points, or is the publisher
+    InvalidPayout = 40,
+    /// 41 for a payment whose dispute window has ended
+    DisputeWindowClosed = 41,
+    /// 42 for no payment held in escrow under the payment id
+    UnknownPayment = 42,
+    /// 43 for a payment already refunded or charged back
+    PaymentReversed = 43,
+    /// 44 for a caller that is not the subscriber of the payment, or paying for the subscription
+    NotSubscriber = 44,
+    /// 45 for no balance to withdraw in the erc20 token
+    NothingToWithdraw = 45,
+    /// 46 for no coupon created under the coupon code
+    UnknownCoupon = 46,
+    /// 47 for a coupon code already taken
+    CouponExists = 47,
+    /// 48 for a coupon that is malformed or does not apply to the chosen erc20 token
+    InvalidCoupon = 48,
+    /// 49 for a coupon past its expiry
+    CouponExpired = 49,
+    /// 50 for a coupon redeemed as many times as it allows
+    CouponExhausted = 50,
+    /// 51 for a caller that is neither the publisher nor the usage oracle
+    NotUsageReporter = 51,
+    /// 52 for a subscription that did not sign up for metered billing, so usage cannot be billed
+    NotMetered = 52,
+    /// 53 for a spending cap below the price of a period for every seat, or a metered subscription
+    /// without a max amount per period
+    InvalidMaxAmount = 53,
+    /// 54 for a payment that would take the subscription past its lifetime cap
+    SpendingCapReached = 54,
+    /// 55 for an unknown interval type, or a billing day out of range for it
+    InvalidInterval = 55,
+    /// 56 for a price change taking effect before a whole billing period of notice
+    PriceNoticeTooShort = 56,
+    /// 57 for an account the subscription does not give access to
+    NotBeneficiary = 57,
+    /// 58 for managing the members of a subscription to a plan that is not seat-based
+    NotSeatBased = 58,
+    /// 59 for an account that already holds a seat of the subscription
+    MemberExists = 59,
+    /// 60 for an account that holds no seat of the subscription
+    UnknownMember = 60,
+    /// 61 for removing the last member, a subscription keeps at least one seat
+    LastSeat = 61,
+    /// 62 for a payment above the max amount per period the subscriber signed
+    PeriodCapReached = 62,
+    /// 63 for a dispute window too long to hold payments for, past `u64::MAX` milliseconds
+    InvalidDisputeWindow = 63,
+}
+
+impl ContractError {
+    /// Every error the contract can revert with, in code order.
+    pub const ALL: [ContractError; 47] = [
+        ContractError::SignatureVerificationFailed,
+        ContractError::InvalidSignature,
+        ContractError::HashExists,
+        ContractError::SignerFailed,
+        ContractError::MissingPublicKey,
+        ContractError::InvalidBlockTime,
+        ContractError::InsufficientAllowance,
+        ContractError::SubscriptionNotActive,
+        ContractError::ReadingCallerError,
+        ContractError::MalformedSignatureHex,
+        ContractError::InvalidSignatureLength,
+        ContractError::UnsupportedKeyType,
+        ContractError::MissingKey,
+        ContractError::InvalidKey,
+        ContractError::MissingDictionary,
+        ContractError::DictionaryError,
+        ContractError::InvalidContractHash,
+        ContractError::InsufficientBalance,
+        ContractError::UnknownSubscription,
+        ContractError::InvalidArgument,
+        ContractError::UnsupportedToken,
+        ContractError::TokenExists,
+        ContractError::NotPublisher,
+        ContractError::InvalidPayout,
+        ContractError::DisputeWindowClosed,
+        ContractError::UnknownPayment,
+        ContractError::PaymentReversed,
+        ContractError::NotSubscriber,
+        ContractError::NothingToWithdraw,
+        ContractError::UnknownCoupon,
+        ContractError::CouponExists,
+        ContractError::InvalidCoupon,
+        ContractError::CouponExpired,
+        ContractError::CouponExhausted,
+        ContractError::NotUsageReporter,
+        ContractError::NotMetered,
+        ContractError::InvalidMaxAmount,
+        ContractError::SpendingCapReached,
+        ContractError::InvalidInterval,
+        ContractError::PriceNoticeTooShort,
+        ContractError::NotBeneficiary,
+        ContractError::NotSeatBased,
+        ContractError::MemberExists,
+        ContractError::UnknownMember,
+        ContractError::LastSeat,
+        ContractError::PeriodCapReached,
+        ContractError::InvalidDisputeWindow,
+    ];
+
+    /// The user error code carried by `ApiError::User`.
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// Looks up the error for a user error code.
+    pub fn from_code(code: u16) -> Option<ContractError> {
+        ContractError::ALL.iter().copied().find(|error| error.code() == code)
+    }
+
+    /// Looks up the error for an `ApiError` returned by the execution engine.
+    pub fn from_api_error(error: ApiError) -> Option<ContractError> {
+        match error {
+            ApiError::User(code) => ContractError::from_code(code),
+            _ => None,
+        }
+    }
+
+    /// A readable explanation of the error for clients.
+    pub fn description(self) -> &'static str {
+        match self {
+            ContractError::SignatureVerificationFailed => "the signature does not match the subscription hash and registered public key",
+            ContractError::InvalidSignature => "the signature bytes are not a valid Ed25519 signature",
+            ContractError::HashExists => "retired, the contract no longer reverts with this code",
+            ContractError::SignerFailed => "retired, the contract no longer reverts with this code",
+            ContractError::MissingPublicKey => "no public key is registered for this subscriber",
+            ContractError::InvalidBlockTime => "the next payment is not due yet",
+            ContractError::InsufficientAllowance => "the erc20 allowance granted to the contract package is less than the token amount",
+            ContractError::SubscriptionNotActive => "the subscription is not active",
+            ContractError::ReadingCallerError => "the caller of the entry point could not be read",
+            ContractError::MalformedSignatureHex => "the signature is not valid hex",
+            ContractError::InvalidSignatureLength => "the signature does not decode to 64 bytes",
+            ContractError::UnsupportedKeyType => "the registered public key is not an Ed25519 key",
+            ContractError::MissingKey => "a named key the contract relies on is missing",
+            ContractError::InvalidKey => "a named key does not hold a value of the expected type",
+            ContractError::MissingDictionary => "a dictionary the contract relies on is missing",
+            ContractError::DictionaryError => "a dictionary item could not be read",
+            ContractError::InvalidContractHash => "the erc20 contract hash is not a hash key",
+            ContractError::InsufficientBalance => "the subscriber's erc20 balance is less than the token amount",
+            ContractError::UnknownSubscription => "no subscription is registered under this subscription hash or id",
+            ContractError::InvalidArgument => "a runtime argument does not have the expected type",
+            ContractError::UnsupportedToken => "the plan does not accept this erc20 token",
+            ContractError::TokenExists => "the plan already accepts this erc20 token, its price changes with schedule_price_change",
+            ContractError::NotPublisher => "only the publisher of the plan (`to`) can call this entry point",
+            ContractError::InvalidPayout => "the payees would get more than 10000 basis points of a payment, or the payee is the publisher",
+            ContractError::DisputeWindowClosed => "the dispute window of the payment has ended",
+            ContractError::UnknownPayment => "no payment is held in escrow under this payment id",
+            ContractError::PaymentReversed => "the payment was already refunded or charged back",
+            ContractError::NotSubscriber => "only the subscriber who made the payment can charge it back, or who pays for the subscription can manage its members",
+            ContractError::NothingToWithdraw => "the caller has no escrow balance available in this erc20 token",
+            ContractError::UnknownCoupon => "no coupon is created under this coupon code",
+            ContractError::CouponExists => "a coupon is already created under this coupon code",
+            ContractError::InvalidCoupon => "the coupon code is empty or longer than 64 bytes, the discount is over 100%, or a fixed amount coupon is for another erc20 token",
+            ContractError::CouponExpired => "the coupon has expired",
+            ContractError::CouponExhausted => "the coupon was redeemed as many times as it allows",
+            ContractError::NotUsageReporter => "only the publisher of the plan (`to`) or the usage oracle can report usage",
+            ContractError::NotMetered => "the subscription did not sign up for metered billing, its usage cannot be billed",
+            ContractError::InvalidMaxAmount => "a spending cap is below the price of a period for every seat in the chosen erc20 token, or a metered subscription has no max amount per period",
+            ContractError::SpendingCapReached => "the payment would take the subscription past its signed lifetime cap",
+            ContractError::InvalidInterval => "the interval type is unknown, or the billing day is not a weekday (1 to 7) of a weekly plan or a day of the month (1 to 31) of a monthly or yearly plan",
+            ContractError::PriceNoticeTooShort => "the price change would take effect before the end of the next whole billing period",
+            ContractError::NotBeneficiary => "the subscription does not give this account access",
+            ContractError::NotSeatBased => "the plan is not seat-based, its subscriptions have no members to manage",
+            ContractError::MemberExists => "the account already holds a seat of this subscription",
+            ContractError::UnknownMember => "the account holds no seat of this subscription",
+            ContractError::LastSeat => "the last member cannot be removed, a subscription keeps at least one seat",
+            ContractError::PeriodCapReached => "the payment is above the max amount per period the subscriber signed",
+            ContractError::InvalidDisputeWindow => "the dispute window is too long, its milliseconds do not fit in a u64",
+        }
+    }
+}
+
+impl From<ContractError> for ApiError {
+    fn from(err: ContractError) -> ApiError {
+        ApiError::User(err as u16)
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{
+    account::AccountHash,
+    ApiError, ContractHash, URef, U256,
+};
+
+use crate::errors::ContractError;
+use crate::keys::account_key;
+use crate::status::PaymentState;
+use crate::utils;
+
+pub const DISPUTE_WINDOW_SECONDS: &str = "dispute_window_seconds";
+pub const ESCROW_HELD: &str = "escrow_held";
+pub const ESCROW_PAYMENT_COUNT: &str = "escrow_payment_count";
+pub const ESCROW_SETTLED_COUNT: &str = "escrow_settled_count";
+pub const ESCROW_SUBSCRIPTIONS_DICT: &str = "escrow_subscriptions";
+pub const ESCROW_TOKENS_DICT: &str = "escrow_tokens";
+pub const ESCROW_SHARES_DICT: &str = "escrow_shares";
+pub const ESCROW_RELEASE_TIMESTAMPS_DICT: &str = "escrow_release_timestamps";
+pub const ESCROW_STATES_DICT: &str = "escrow_states";
+pub const ESCROW_PENDING_DICT: &str = "escrow_pending";
+pub const ESCROW_AVAILABLE_DICT: &str = "escrow_available";
+
+/// Most payments one call of `Escrow::settle` releases, so a backlog of payments whose dispute
+/// window ended cannot run a deploy out of gas. Every `execute_subscription` held in escrow and
+/// every `withdraw` releases the next batch.
+pub const MAX_SETTLE_BATCH: u64 = 50;
+
+/// Seconds a payment stays in escrow, set at install. 0 pays the publisher and payees directly.
+pub fn get_dispute_window_seconds() -> u64 {
+    utils::get_key(DISPUTE_WINDOW_SECONDS).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// Payments held by the contract package in escrow mode, keyed by payment id (decimal string).
+/// Ids are handed out sequentially from `escrow_payment_count`. Balances are keyed by account
+/// (see `keys::account_key`) and map each erc20 token to an amount: `escrow_pending` while the
+/// dispute window runs, `escrow_available` once it ended and the account can withdraw.
+///
+/// The dispute window is fixed at install, so release timestamps only grow with the payment id
+/// and payments are released in order, from `escrow_settled_count` on.
+pub struct Escrow {
+    subscriptions_dict_uref: URef,
+    tokens_dict_uref: URef,
+    shares_dict_uref: URef,
+    release_timestamps_dict_uref: URef,
+    states_dict_uref: URef,
+    pending_dict_uref: URef,
+    available_dict_uref: URef,
+}
+
+impl Escrow {
+    pub fn new() -> Escrow {
+        Escrow {
+            subscriptions_dict_uref: utils::get_dictionary_uref(ESCROW_SUBSCRIPTIONS_DICT),
+            tokens_dict_uref: utils::get_dictionary_uref(ESCROW_TOKENS_DICT),
+            shares_dict_uref: utils::get_dictionary_uref(ESCROW_SHARES_DICT),
+            release_timestamps_dict_uref: utils::get_dictionary_uref(ESCROW_RELEASE_TIMESTAMPS_DICT),
+            states_dict_uref: utils::get_dictionary_uref(ESCROW_STATES_DICT),
+            pending_dict_uref: utils::get_dictionary_uref(ESCROW_PENDING_DICT),
+            available_dict_uref: utils::get_dictionary_uref(ESCROW_AVAILABLE_DICT),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        utils::get_key(ESCROW_PAYMENT_COUNT).unwrap_or_revert_with(ContractError::MissingKey)
+    }
+
+    /// The id of the first payment not released yet by `settle`.
+    fn settled_count(&self) -> u64 {
+        utils::get_key(ESCROW_SETTLED_COUNT).unwrap_or_revert_with(ContractError::MissingKey)
+    }
+
+    /// Every erc20 token held in escrow, with the amount held, whatever the state of the payments.
+    pub fn get_held(&self) -> BTreeMap<ContractHash, U256> {
+        utils::get_key(ESCROW_HELD).unwrap_or_revert_with(ContractError::MissingKey)
+    }
+
+    fn add_held(&self, erc20_contract_hash: ContractHash, amount: U256) {
+        let mut held = self.get_held();
+        *held.entry(erc20_contract_hash).or_insert_with(U256::zero) += amount;
+        utils::set_key(ESCROW_HELD, held);
+    }
+
+    fn sub_held(&self, erc20_contract_hash: ContractHash, amount: U256) {
+        let mut held = self.get_held();
+        *held.entry(erc20_contract_hash).or_insert_with(U256::zero) -= amount;
+        utils::set_key(ESCROW_HELD, held);
+    }
+
+    /// Hold a payment received by the contract package until `release_timestamp`, crediting the
+    /// shares of its split as pending. Returns the id of the payment.
+    pub fn add(&self, subscription_id: u64, erc20_contract_hash: ContractHash, shares: Vec<(AccountHash, U256)>, release_timestamp: u64) -> u64 {
+        let payment_id = self.count();
+        let key = &payment_id.to_string();
+
+        let token_amount = shares.iter().fold(U256::zero(), |total, (_, amount)| total + *amount);
+        for (account, amount) in &shares {
+            self.credit(self.pending_dict_uref, *account, erc20_contract_hash, *amount);
+        }
+        self.add_held(erc20_contract_hash, token_amount);
+
+        storage::dictionary_put(self.subscriptions_dict_uref, key, subscription_id);
+        storage::dictionary_put(self.tokens_dict_uref, key, erc20_contract_hash);
+        storage::dictionary_put(self.shares_dict_uref, key, shares);
+        storage::dictionary_put(self.release_timestamps_dict_uref, key, release_timestamp);
+        storage::dictionary_put(self.states_dict_uref, key, PaymentState::Held as u8);
+        utils::set_key(ESCROW_PAYMENT_COUNT, payment_id + 1);
+
+        payment_id
+    }
+
+    /// The subscription a payment was made for.
+    pub fn get_subscription_id(&self, payment_id: u64) -> Option<u64> {
+        storage::dictionary_get(self.subscriptions_dict_uref, &payment_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The erc20 token a payment was made in.
+    pub fn get_token(&self, payment_id: u64) -> ContractHash {
+        storage::dictionary_get(self.tokens_dict_uref, &payment_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::UnknownPayment)
+    }
+
+    /// The split of a payment, the publisher first.
+    pub fn get_shares(&self, payment_id: u64) -> Vec<(AccountHash, U256)> {
+        storage::dictionary_get(self.shares_dict_uref, &payment_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::UnknownPayment)
+    }
+
+    /// The timestamp in milliseconds the dispute window of a payment ends.
+    pub fn get_release_timestamp(&self, payment_id: u64) -> u64 {
+        storage::dictionary_get(self.release_timestamps_dict_uref, &payment_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::UnknownPayment)
+    }
+
+    pub fn get_state(&self, payment_id: u64) -> PaymentState {
+        let state: u8 = storage::dictionary_get(self.states_dict_uref, &payment_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::UnknownPayment);
+
+        PaymentState::from_u8(state).unwrap_or_revert_with(ContractError::InvalidKey)
+    }
+
+    fn set_state(&self, payment_id: u64, state: PaymentState) {
+        storage::dictionary_put(self.states_dict_uref, &payment_id.to_string(), state as u8);
+    }
+
+    /// The pending balances of an account, by erc20 token.
+    pub fn get_pending(&self, account: AccountHash) -> BTreeMap<ContractHash, U256> {
+        self.get_balances(self.pending_dict_uref, account)
+    }
+
+    /// The balances an account can withdraw, by erc20 token.
+    pub fn get_available(&self, account: AccountHash) -> BTreeMap<ContractHash, U256> {
+        self.get_balances(self.available_dict_uref, account)
+    }
+
+    fn get_balances(&self, dict_uref: URef, account: AccountHash) -> BTreeMap<ContractHash, U256> {
+        storage::dictionary_get(dict_uref, &account_key(account))
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default()
+    }
+
+    fn credit(&self, dict_uref: URef, account: AccountHash, erc20_contract_hash: ContractHash, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+
+        let mut balances = self.get_balances(dict_uref, account);
+        *balances.entry(erc20_contract_hash).or_insert_with(U256::zero) += amount;
+        storage::dictionary_put(dict_uref, &account_key(account), balances);
+    }
+
+    fn debit(&self, dict_uref: URef, account: AccountHash, erc20_contract_hash: ContractHash, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+
+        let mut balances = self.get_balances(dict_uref, account);
+        let balance = balances.get(&erc20_contract_hash).copied().unwrap_or_default();
+
+        if balance == amount {
+            balances.remove(&erc20_contract_hash);
+        } else {
+            balances.insert(erc20_contract_hash, balance - amount);
+        }
+        storage::dictionary_put(dict_uref, &account_key(account), balances);
+    }
+
+    /// The pending and available balances of an account as of `blocktime`, counting as available
+    /// the shares of the payments the next `settle` would release, the same batch of at most
+    /// `MAX_SETTLE_BATCH`, so it shows what a `withdraw` pays. Writes nothing, for read-only views.
+    pub fn get_balances_at(&self, account: AccountHash, blocktime: u64) -> (BTreeMap<ContractHash, U256>, BTreeMap<ContractHash, U256>) {
+        let mut pending = self.get_pending(account);
+        let mut available = self.get_available(account);
+        let mut payment_id = self.settled_count();
+        let end = self.count().min(payment_id + MAX_SETTLE_BATCH);
+
+        while payment_id < end && self.get_release_timestamp(payment_id) <= blocktime {
+            if self.get_state(payment_id) == PaymentState::Held {
+                let erc20_contract_hash = self.get_token(payment_id);
+
+                for (_, amount) in self.get_shares(payment_id).into_iter().filter(|(share_account, amount)| *share_account == account && !amount.is_zero()) {
+                    let balance = pending.get(&erc20_contract_hash).copied().unwrap_or_default();
+                    if balance == amount {
+                        pending.remove(&erc20_contract_hash);
+                    } else {
+                        pending.insert(erc20_contract_hash, balance - amount);
+                    }
+                    *available.entry(erc20_contract_hash).or_insert_with(U256::zero) += amount;
+                }
+            }
+            payment_id += 1;
+        }
+
+        (pending, available)
+    }
+
+    /// Release the payments whose dispute window ended by `blocktime`, in order, moving their
+    /// shares from pending to available, at most `MAX_SETTLE_BATCH` of them. Refunded and charged
+    /// back payments are skipped.
+    pub fn settle(&self, blocktime: u64) {
+        let mut settled = self.settled_count();
+        let end = self.count().min(settled + MAX_SETTLE_BATCH);
+
+        while settled < end && self.get_release_timestamp(settled) <= blocktime {
+            if self.get_state(settled) == PaymentState::Held {
+                let erc20_contract_hash = self.get_token(settled);
+
+                for (account, amount) in self.get_shares(settled) {
+                    self.debit(self.pending_dict_uref, account, erc20_contract_hash, amount);
+                    self.credit(self.available_dict_uref, account, erc20_contract_hash, amount);
+                }
+                self.set_state(settled, PaymentState::Released);
+            }
+            settled += 1;
+        }
+
+        utils::set_key(ESCROW_SETTLED_COUNT, settled);
+    }
+
+    /// Give a held payment back, reverting with `DisputeWindowClosed` once its dispute window
+    /// ended and with `PaymentReversed` if it was already given back. The caller transfers the
+    /// returned amount of the payment's token back to the subscriber.
+    pub fn reverse(&self, payment_id: u64, blocktime: u64, state: PaymentState) -> U256 {
+        match self.get_state(payment_id) {
+            PaymentState::Held => {}
+            PaymentState::Released => runtime::revert(ApiError::User(ContractError::DisputeWindowClosed as u16)),
+            PaymentState::Refunded | PaymentState::ChargedBack => {
+                runtime::revert(ApiError::User(ContractError::PaymentReversed as u16))
+            }
+        }
+        if blocktime >= self.get_release_timestamp(payment_id) {
+            runtime::revert(ApiError::User(ContractError::DisputeWindowClosed as u16));
+        }
+
+        let erc20_contract_hash = self.get_token(payment_id);
+        let mut token_amount = U256::zero();
+        for (account, amount) in self.get_shares(payment_id) {
+            self.debit(self.pending_dict_uref, account, erc20_contract_hash, amount);
+            token_amount += amount;
+        }
+        self.sub_held(erc20_contract_hash, token_amount);
+        self.set_state(payment_id, state);
+
+        token_amount
+    }
+
+    /// Take the whole available balance of an account in a token, reverting with
+    /// `NothingToWithdraw` if there is none. The caller transfers it to the account.
+    pub fn withdraw(&self, account: AccountHash, erc20_contract_hash: ContractHash) -> U256 {
+        let amount = self.get_available(account).get(&erc20_contract_hash).copied().unwrap_or_default();
+
+        if amount.is_zero() {
+            runtime::revert(ApiError::User(ContractError::NothingToWithdraw as u16));
+        }
+
+        self.debit(self.available_dict_uref, account, erc20_contract_hash, amount);
+        self.sub_held(erc20_contract_hash, amount);
+
+        amount
+    }
+}
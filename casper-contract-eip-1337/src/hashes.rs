@@ -1,62 +1,183 @@
 use contract::{
-    contract_api::{runtime, storage},
+    contract_api::{storage},
     unwrap_or_revert::UnwrapOrRevert,
 };
 use types::{
     account::AccountHash,
     crypto::{PublicKey},
-    Key, URef,
+    ContractHash, URef, U256,
 };
 
+use crate::errors::ContractError;
+use crate::utils;
+
 pub const HASHES_DICT: &str = "hashes";
 pub const PUBKEYS_DICT: &str = "pubkeys";
-pub const PREFIX: &str = "account-hash-";
+pub const ACCOUNTS_DICT: &str = "accounts";
+pub const NEXT_VALID_TIMESTAMPS_DICT: &str = "next_valid_timestamps";
+pub const SUBSCRIPTION_IDS_DICT: &str = "subscription_ids";
+pub const SUBSCRIPTION_TOKENS_DICT: &str = "subscription_tokens";
+pub const SIGNED_PRICES_DICT: &str = "signed_prices";
+pub const BENEFICIARIES_DICT: &str = "beneficiaries";
+pub const SUBSCRIPTION_COUPONS_DICT: &str = "subscription_coupons";
+pub const DISCOUNTED_AMOUNTS_DICT: &str = "discounted_amounts";
+pub const DISCOUNTED_PERIODS_DICT: &str = "discounted_periods";
+pub const SUBSCRIPTION_COUNT: &str = "subscription_count";
+
+/// Remaining discounted periods of a coupon applying to every payment.
+pub const EVERY_PERIOD: u64 = u64::MAX;
 
+/// Subscription records, keyed by subscription id (decimal string).
+/// Ids are handed out sequentially from `subscription_count`, and the
+/// `subscription_ids` dictionary maps a subscription hash back to its id.
 pub struct Hashes {
     dict_uref: URef,
     pubkeys_dict_uref: URef,
+    accounts_dict_uref: URef,
+    timestamps_dict_uref: URef,
+    ids_dict_uref: URef,
+    tokens_dict_uref: URef,
+    signed_prices_dict_uref: URef,
+    beneficiaries_dict_uref: URef,
+    coupons_dict_uref: URef,
+    discounted_amounts_dict_uref: URef,
+    discounted_periods_dict_uref: URef,
 }
 
 impl Hashes {
     pub fn new() -> Hashes {
-        let dict_key: Key = runtime::get_key(HASHES_DICT).unwrap_or_revert();
-        let dict_uref: &URef = dict_key.as_uref().unwrap_or_revert();
-
-        let pubkeys_dict_key: Key = runtime::get_key(PUBKEYS_DICT).unwrap_or_revert();
-        let pubkeys_dict_uref: &URef = pubkeys_dict_key.as_uref().unwrap_or_revert();
-
         Hashes {
-            dict_uref: *dict_uref,
-            pubkeys_dict_uref: *pubkeys_dict_uref,
+            dict_uref: utils::get_dictionary_uref(HASHES_DICT),
+            pubkeys_dict_uref: utils::get_dictionary_uref(PUBKEYS_DICT),
+            accounts_dict_uref: utils::get_dictionary_uref(ACCOUNTS_DICT),
+            timestamps_dict_uref: utils::get_dictionary_uref(NEXT_VALID_TIMESTAMPS_DICT),
+            ids_dict_uref: utils::get_dictionary_uref(SUBSCRIPTION_IDS_DICT),
+            tokens_dict_uref: utils::get_dictionary_uref(SUBSCRIPTION_TOKENS_DICT),
+            signed_prices_dict_uref: utils::get_dictionary_uref(SIGNED_PRICES_DICT),
+            beneficiaries_dict_uref: utils::get_dictionary_uref(BENEFICIARIES_DICT),
+            coupons_dict_uref: utils::get_dictionary_uref(SUBSCRIPTION_COUPONS_DICT),
+            discounted_amounts_dict_uref: utils::get_dictionary_uref(DISCOUNTED_AMOUNTS_DICT),
+            discounted_periods_dict_uref: utils::get_dictionary_uref(DISCOUNTED_PERIODS_DICT),
         }
     }
 
-    pub fn set(&self, account: AccountHash, hash: &str, public_key: PublicKey) {
-        let key = &account.to_formatted_string().replace(PREFIX, "");
+    pub fn count(&self) -> u64 {
+        utils::get_key(SUBSCRIPTION_COUNT).unwrap_or_revert_with(ContractError::MissingKey)
+    }
+
+    /// Register a new subscription for an account, paid in the given erc20 token at `token_amount`
+    /// a period, and return its id. The hash is set separately as it commits to the id.
+    pub fn add(&self, account: AccountHash, public_key: PublicKey, erc20_contract_hash: ContractHash, token_amount: U256) -> u64 {
+        let subscription_id = self.count();
+        let key = &subscription_id.to_string();
 
-        storage::dictionary_put(self.dict_uref, key, hash);
+        storage::dictionary_put(self.accounts_dict_uref, key, account);
         storage::dictionary_put(self.pubkeys_dict_uref, key, public_key);
-    }
+        storage::dictionary_put(self.tokens_dict_uref, key, erc20_contract_hash);
+        storage::dictionary_put(self.signed_prices_dict_uref, key, token_amount);
+        utils::set_key(SUBSCRIPTION_COUNT, subscription_id + 1);
 
-    pub fn delete(&self, account: AccountHash) {
-        let key = &account.to_formatted_string().replace(PREFIX, "");
+        subscription_id
+    }
 
-        storage::dictionary_put(self.dict_uref, key, "");
-        storage::dictionary_put(self.pubkeys_dict_uref, key, "");
+    pub fn set(&self, subscription_id: u64, hash: &str) {
+        storage::dictionary_put(self.dict_uref, &subscription_id.to_string(), hash);
+        storage::dictionary_put(self.ids_dict_uref, hash, subscription_id);
     }
 
-    pub fn get(&self, account: AccountHash) -> (Option<String>, Option<PublicKey>) {
-        let key = &account.to_formatted_string().replace(PREFIX, "");
+    pub fn get(&self, subscription_id: u64) -> (Option<String>, Option<PublicKey>) {
+        let key = &subscription_id.to_string();
 
-        let hash: Option<String> = storage::dictionary_get(self.dict_uref, key).unwrap_or_revert();
-        let public_key: Option<PublicKey> = storage::dictionary_get(self.pubkeys_dict_uref, key).unwrap_or_revert();
+        let hash: Option<String> = storage::dictionary_get(self.dict_uref, key).unwrap_or_revert_with(ContractError::DictionaryError);
+        let public_key: Option<PublicKey> = storage::dictionary_get(self.pubkeys_dict_uref, key).unwrap_or_revert_with(ContractError::DictionaryError);
 
         (hash, public_key)
     }
 
-    pub fn get_public_key(&self, account: AccountHash) -> Option<PublicKey> {
-        let key = &account.to_formatted_string().replace(PREFIX, "");
-        
-        storage::dictionary_get(self.pubkeys_dict_uref, key).unwrap_or_revert()
+    pub fn get_public_key(&self, subscription_id: u64) -> Option<PublicKey> {
+        storage::dictionary_get(self.pubkeys_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The subscriber account that registered the subscription.
+    pub fn get_account(&self, subscription_id: u64) -> Option<AccountHash> {
+        storage::dictionary_get(self.accounts_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// Give another account than the subscriber access to the subscription, the subscriber paying for it.
+    pub fn set_beneficiary(&self, subscription_id: u64, beneficiary: AccountHash) {
+        storage::dictionary_put(self.beneficiaries_dict_uref, &subscription_id.to_string(), beneficiary);
+    }
+
+    /// The account given access when the subscriber pays for another one, `None` for the
+    /// subscriber itself.
+    pub fn get_beneficiary(&self, subscription_id: u64) -> Option<AccountHash> {
+        storage::dictionary_get(self.beneficiaries_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The erc20 token the subscriber chose to pay with.
+    pub fn get_token(&self, subscription_id: u64) -> Option<ContractHash> {
+        storage::dictionary_get(self.tokens_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The price of a period the subscription hash commits to, the price of the token when the
+    /// subscription was registered.
+    pub fn get_signed_price(&self, subscription_id: u64) -> Option<U256> {
+        storage::dictionary_get(self.signed_prices_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// Record the coupon a subscription was created with: `discounted_amount` is paid for the
+    /// next `periods` payments, `EVERY_PERIOD` for all of them.
+    pub fn set_discount(&self, subscription_id: u64, code: &str, discounted_amount: U256, periods: u64) {
+        let key = &subscription_id.to_string();
+
+        storage::dictionary_put(self.coupons_dict_uref, key, code);
+        storage::dictionary_put(self.discounted_amounts_dict_uref, key, discounted_amount);
+        storage::dictionary_put(self.discounted_periods_dict_uref, key, periods);
+    }
+
+    /// The coupon code a subscription was created with.
+    pub fn get_coupon(&self, subscription_id: u64) -> Option<String> {
+        storage::dictionary_get(self.coupons_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The discounted amount of the next payment and the discounted periods left,
+    /// or `None` once the discount has run out or without a coupon.
+    pub fn get_discount(&self, subscription_id: u64) -> Option<(U256, u64)> {
+        let key = &subscription_id.to_string();
+
+        let periods: u64 = storage::dictionary_get(self.discounted_periods_dict_uref, key)
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default();
+        if periods == 0 {
+            return None;
+        }
+        let discounted_amount: U256 = storage::dictionary_get(self.discounted_amounts_dict_uref, key)
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_revert_with(ContractError::DictionaryError);
+
+        Some((discounted_amount, periods))
     }
-}
\ No newline at end of file
+
+    /// Count a discounted payment against the discounted periods left.
+    pub fn use_discount(&self, subscription_id: u64) {
+        if let Some((_, periods)) = self.get_discount(subscription_id) {
+            if periods != EVERY_PERIOD {
+                storage::dictionary_put(self.discounted_periods_dict_uref, &subscription_id.to_string(), periods - 1);
+            }
+        }
+    }
+
+    /// The subscription id registered under a subscription hash.
+    pub fn get_id(&self, hash: &str) -> Option<u64> {
+        storage::dictionary_get(self.ids_dict_uref, hash).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// The next valid timestamp in milliseconds, keyed by the subscription id.
+    pub fn get_next_valid_timestamp(&self, subscription_id: u64) -> Option<u64> {
+        storage::dictionary_get(self.timestamps_dict_uref, &subscription_id.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    pub fn set_next_valid_timestamp(&self, subscription_id: u64, next_valid_timestamp: u64) {
+        storage::dictionary_put(self.timestamps_dict_uref, &subscription_id.to_string(), next_valid_timestamp);
+    }
+}
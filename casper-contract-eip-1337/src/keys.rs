@@ -0,0 +1,11 @@
+//! Dictionary item keys for accounts.
+
+use types::account::AccountHash;
+
+pub const PREFIX: &str = "account-hash-";
+
+/// Dictionary item key for an account: the account hash as lowercase hex,
+/// i.e. the formatted string with the `account-hash-` prefix stripped.
+pub fn account_key(account: AccountHash) -> String {
+    account.to_formatted_string().replace(PREFIX, "")
+}
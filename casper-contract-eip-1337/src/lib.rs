@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use contract::{
     contract_api::{runtime, storage},
     unwrap_or_revert::UnwrapOrRevert 
@@ -7,44 +9,42 @@ use types::{
     account::AccountHash,
     contracts::{ContractPackageHash, EntryPoint, EntryPointAccess, EntryPointType, EntryPoints, NamedKeys}, 
     crypto::{PublicKey, Signature},
+    bytesrepr::ToBytes,
     CLValue, CLTyped, CLType, Parameter, RuntimeArgs, runtime_args, ContractHash, U256, ApiError, Key,
 };
 
 mod utils;
 mod constants;
+mod calendar;
+mod caps;
+mod coupons;
+mod escrow;
 mod hashes;
+mod keys;
+mod metering;
+mod paging;
+mod payouts;
+mod pricing;
+mod seats;
+mod subscribers;
+mod terms;
+mod tokens;
+pub mod errors;
+pub mod status;
 
-use hashes::Hashes;
+use calendar::{BillingInterval, IntervalType};
+use caps::Caps;
+use coupons::{Coupons, Discount};
+use escrow::Escrow;
+use hashes::{Hashes, EVERY_PERIOD};
+use metering::Metering;
+use pricing::DiscountType;
+use seats::Seats;
+use subscribers::Subscribers;
+use terms::SubscriptionTerms;
+pub use errors::ContractError;
+use status::{PaymentState, SubscriptionStatus, CANCELLED_TIMESTAMP};
   
-/// Enum for ContractError, It represents codes for different smart contract errors.
-#[derive(Debug)]
-pub enum ContractError {
-    /// 17 for signature verification error
-    SignatureVerificationFailed = 17,
-    /// 18 for invalid signature
-    InvalidSignature = 18,
-    /// 19 for hash exists
-    HashExists = 19,
-    /// 20 for signer verification failed
-    SignerFailed = 20,
-    /// 21 for somehow missing public_key
-    MissingPublicKey = 21,
-    /// 22 or blocktime less than nextvalidtimestamp
-    InvalidBlockTime = 22, 
-    /// 23 for allowance is less than token_amount
-    InsufficientAllowance = 23, 
-    /// 24 for subcription not active
-    SubscriptionNotActive = 24,      
-
-    ReadingCallerError = 25,
-}
-
-impl From<ContractError> for ApiError {
-    fn from(err: ContractError) -> ApiError {
-        ApiError::User(err as u16)
-    }
-}
-
 /// This function is used by external smart contracts to verify on-chain that a
 /// particular subscription is "paid" and "active"
 /// there must be a small grace period added to allow the publisher
@@ -52,26 +52,43 @@ impl From<ContractError> for ApiError {
 /// 
 /// # Parameters
 ///
-/// * `subcription_hash_` - A string slice that holds the eip-191 standard's subcription_hash of the meta transaction
+/// * `subcription_hash` - A string slice that holds the eip-191 standard's subcription_hash of the meta transaction
 ///
 /// * `grace_period_seconds` - An u64 that holds the grace seconds to see if transaction gets active by adding some grace seconds
 /// 
+/// * `beneficiary` - An optional Accounthash that must be the account given access, a member of a subscription to a seat-based plan, the beneficiary of a subscription paid by another account or the subscriber otherwise
+/// 
 #[no_mangle]
 pub fn is_subscription_active()
 {
     let subscription_hash:String=runtime::get_named_arg(constants::SUBSCRIPTION_HASH);
     let grace_period_seconds:u64=runtime::get_named_arg(constants::GRACE_PERIOD_SECONDS);
+    let beneficiary:Option<AccountHash>=utils::get_optional_named_arg(constants::BENEFICIARY);
 
     let blocktime:u64 =runtime::get_blocktime().into();
-    let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,subscription_hash);
-    let next_valid_timestamp:u64=utils::get_key(&next_valid_timestamp_key).unwrap_or_revert();
+    let hashes = Hashes::new();
+    let subscription_id:Option<u64>=hashes.get_id(&subscription_hash);
+    let next_valid_timestamp:Option<u64>=subscription_id.and_then(|subscription_id| hashes.get_next_valid_timestamp(subscription_id));
 
-    if blocktime < next_valid_timestamp+(grace_period_seconds*1000)
+    // the subscription must give the account access, whoever pays for it
+    if let (Some(subscription_id), Some(beneficiary)) = (subscription_id, beneficiary)
     {
-         // subscription not active
-        runtime::revert(ApiError::User(ContractError::SubscriptionNotActive as u16));
+        if !gives_access(&hashes, subscription_id, beneficiary)
+        {
+            runtime::revert(ApiError::User(ContractError::NotBeneficiary as u16));
+        }
     }
 
+    match SubscriptionStatus::from_timestamps(next_valid_timestamp, blocktime, grace_period_seconds) {
+        SubscriptionStatus::Active => {},
+        SubscriptionStatus::NotFound => {
+            runtime::revert(ApiError::User(ContractError::UnknownSubscription as u16));
+        },
+        _ => {
+            // subscription not active
+            runtime::revert(ApiError::User(ContractError::SubscriptionNotActive as u16));
+        }
+    }
 }
 
 /// Given the subscription details, generate a message string
@@ -79,13 +96,204 @@ pub fn is_subscription_active()
 ///
 /// * `from` - An Accounthash that holds the account address of the subscriber/signer
 ///
-pub fn get_subscription_data(from:AccountHash) -> String
+/// * `subscription_id` - An u64 that holds the id of the subscription, so every subscription of an account signs a different hash
+///
+/// * `erc20_contract_hash` - A ContractHash that holds the erc20 token the subscriber pays with
+///
+/// * `token_amount` - An U256 that holds the price of a period in that token
+///
+/// * `max_amount_per_period` - An optional U256 that holds the most a payment can charge
+///
+/// * `lifetime_cap` - An optional U256 that holds the most the subscription can charge over its lifetime
+///
+/// * `beneficiary` - An optional Accounthash that holds the account given access when `from` pays for another account
+///
+/// * `metered` - A bool that holds whether the usage reported is charged on top of the price
+///
+pub fn get_subscription_data(from:AccountHash, subscription_id:u64, erc20_contract_hash:ContractHash, token_amount:U256, max_amount_per_period:Option<U256>, lifetime_cap:Option<U256>, beneficiary:Option<AccountHash>, metered:bool) -> String
+{
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
+    let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert_with(ContractError::MissingKey);
+
+    SubscriptionTerms{to,from,token_amount,period_seconds,subscription_id,erc20_contract_hash,max_amount_per_period,lifetime_cap,beneficiary,metered}.data()
+}
+
+/// The account a subscription gives access to: its beneficiary when the subscriber pays for
+/// another account, the subscriber otherwise.
+fn get_beneficiary(hashes: &Hashes, subscription_id: u64) -> AccountHash
+{
+    match hashes.get_beneficiary(subscription_id) {
+        Some(beneficiary) => beneficiary,
+        None => hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription),
+    }
+}
+
+/// The erc20 token a subscription pays with and the price of a period it signed for.
+/// # Parameters
+///
+/// * `hashes` - The subscription dictionaries
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+fn get_subscription_token(hashes: &Hashes, subscription_id: u64) -> (ContractHash, U256)
+{
+    let erc20_contract_hash: ContractHash = hashes.get_token(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let token_amount: U256 = hashes.get_signed_price(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    (erc20_contract_hash, token_amount)
+}
+
+/// The billing period of the plan, see `calendar`.
+fn get_billing_interval() -> BillingInterval
+{
+    let interval_type: u8 = utils::get_key(calendar::INTERVAL_TYPE).unwrap_or_revert_with(ContractError::MissingKey);
+
+    BillingInterval {
+        interval_type: IntervalType::from_u8(interval_type).unwrap_or_revert_with(ContractError::InvalidInterval),
+        period_seconds: utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert_with(ContractError::MissingKey),
+        billing_day: utils::get_key(calendar::BILLING_DAY).unwrap_or_revert_with(ContractError::MissingKey),
+    }
+}
+
+/// The amount of the payment of a subscription due at `due_at`: the discounted amount while its
+/// coupon applies, the price of a period in its token at `due_at` otherwise, so a scheduled price
+/// change applies to the periods falling due once it takes effect, however late they are paid. A seat-based plan charges that amount for every
+/// seat, prorating the seats added or removed since the last payment. A metered subscription adds
+/// the usage reported since the last payment at the unit price of its token. The spending caps
+/// are not applied here, a payment above them reverts (see `Caps::check`).
+fn get_payment_amount(hashes: &Hashes, subscription_id: u64, erc20_contract_hash: ContractHash, due_at: u64) -> U256
+{
+    let seat_price: U256 = get_seat_price(hashes, subscription_id, erc20_contract_hash, due_at);
+    let base: U256 = if seats::is_seat_based() {
+        let seats = Seats::new();
+        let (charges, credits) = seats.get_proration(subscription_id);
+        pricing::seats_price(seat_price, seats.get_seats(subscription_id), charges, credits)
+    } else {
+        seat_price
+    };
+
+    let metering = Metering::new();
+    if metering.is_metered(subscription_id)
+    {
+        pricing::metered_price(base, metering.get_usage(subscription_id), metering::get_unit_price(erc20_contract_hash))
+    }
+    else
+    {
+        base
+    }
+}
+
+/// The price of a period of a subscription before usage, for a single seat of a seat-based plan:
+/// the discounted amount while its coupon applies, the price in its token at `due_at` otherwise.
+fn get_seat_price(hashes: &Hashes, subscription_id: u64, erc20_contract_hash: ContractHash, due_at: u64) -> U256
+{
+    let token_amount: U256 = tokens::get_price_at(erc20_contract_hash, due_at).unwrap_or_revert_with(ContractError::UnsupportedToken);
+
+    hashes.get_discount(subscription_id).map_or(token_amount, |(discounted_amount, _periods)| discounted_amount)
+}
+
+/// The time the next payment of a subscription falls due: its next valid timestamp, or the current
+/// blocktime before its first payment. Its price is the one in effect at that time.
+fn get_due_timestamp(hashes: &Hashes, subscription_id: u64) -> u64
+{
+    let next_valid_timestamp: u64 = hashes.get_next_valid_timestamp(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    if next_valid_timestamp == 0 { runtime::get_blocktime().into() } else { next_valid_timestamp }
+}
+
+/// The price of a seat for the part of the current period already gone, the period ending at the
+/// subscription's next valid timestamp. The next payment counts the seats held at that time as
+/// whole seats, so a seat added now is credited this amount and a seat removed now is charged it.
+fn get_elapsed_seat_price(hashes: &Hashes, subscription_id: u64) -> U256
+{
+    let erc20_contract_hash: ContractHash = hashes.get_token(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let next_valid_timestamp: u64 = hashes.get_next_valid_timestamp(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let period_start: u64 = get_billing_interval().previous_timestamp(next_valid_timestamp);
+    let blocktime: u64 = runtime::get_blocktime().into();
+
+    pricing::prorated_price(
+        get_seat_price(hashes, subscription_id, erc20_contract_hash, get_due_timestamp(hashes, subscription_id)),
+        blocktime.saturating_sub(period_start),
+        next_valid_timestamp.saturating_sub(period_start),
+    )
+}
+
+/// Whether a subscription gives an account access: any member of a subscription to a seat-based
+/// plan, its beneficiary when the subscriber pays for another account, the subscriber otherwise.
+fn gives_access(hashes: &Hashes, subscription_id: u64, account: AccountHash) -> bool
+{
+    if seats::is_seat_based()
+    {
+        Seats::new().is_member(subscription_id, account)
+    }
+    else
+    {
+        get_beneficiary(hashes, subscription_id) == account
+    }
+}
+
+/// Revert with `NotSeatBased` unless the plan charges per seat, with `NotSubscriber` unless the
+/// subscriber paying for the subscription is the caller, and with `SubscriptionNotActive` once
+/// it is cancelled.
+fn require_seat_owner(hashes: &Hashes, subscription_id: u64)
+{
+    if !seats::is_seat_based()
+    {
+        runtime::revert(ApiError::User(ContractError::NotSeatBased as u16));
+    }
+
+    let from: AccountHash = hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    if runtime::get_caller() != from
+    {
+        runtime::revert(ApiError::User(ContractError::NotSubscriber as u16));
+    }
+
+    if hashes.get_next_valid_timestamp(subscription_id) == Some(CANCELLED_TIMESTAMP)
+    {
+        runtime::revert(ApiError::User(ContractError::SubscriptionNotActive as u16));
+    }
+}
+
+/// Read an erc20 contract hash passed as a hash key, reverting with `InvalidContractHash` otherwise.
+fn get_erc20_contract_hash(key: Key) -> ContractHash
 {
-    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert();
-    let token_amount:U256=utils::get_key(constants::TOKEN_AMOUNT).unwrap_or_revert();
-    let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert();
+    ContractHash::from(key.into_hash().unwrap_or_revert_with(ContractError::InvalidContractHash))
+}
+
+/// Revert with `NotPublisher` unless the publisher of the plan (`to`) is the caller.
+fn require_publisher()
+{
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
+
+    if runtime::get_caller() != to
+    {
+        runtime::revert(ApiError::User(ContractError::NotPublisher as u16));
+    }
+}
+
+/// The payees of the plan, each with its share of a payment in basis points.
+fn get_payee_shares() -> BTreeMap<AccountHash, u32>
+{
+    utils::get_key(payouts::PAYEES).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// The contract package as a Key, the spender subscribers approve and the holder of escrowed payments.
+fn get_package_key() -> Key
+{
+    Key::Hash(utils::get_key(constants::PACKAGE_HASH).unwrap_or_revert_with(ContractError::MissingKey))
+}
 
-    format!("{}_{}_{}_{}",to,from,token_amount,period_seconds)
+/// Transfer erc20 tokens held by the contract package to an account.
+fn transfer(erc20_contract_hash: ContractHash, recipient: AccountHash, amount: U256)
+{
+    let _: () = runtime::call_contract(
+        erc20_contract_hash,
+        "transfer",
+        runtime_args!{
+            "recipient" => Key::Account(recipient),
+            "amount" => amount
+        }
+    );
 }
 
 /// Given the subscription details, generate blake2b standard hash, internal implementation.
@@ -111,96 +319,156 @@ pub fn _get_subscription_hash(data:String) -> String
     blake2b_standard_hash_string
 }
 
-/// Given the subscription details, generate eip-191 standard hash, external interface.
+/// Register a new subscription for the subscriber, storing its hash, public key, token
+/// and first next valid timestamp.
+/// Returns the id and hash of the new subscription.
 /// # Parameters
-/// 
-/// * `public_key` - A string slice that holds the public key of the meta transaction signer,  Subscriber have to get it from running cryptoxide project externally.
 ///
 /// * `from` - An Accounthash that holds the account address of the subscriber/signer
 ///
-#[no_mangle]
-pub fn get_subscription_hash()
+/// * `public_key` - The public key the subscriber signs the subscription hash with
+///
+/// * `erc20_contract_hash` - A ContractHash that holds the erc20 token the subscriber pays with, one the plan accepts
+///
+/// * `coupon_code` - An optional coupon to redeem, its discounted price is stored with the subscription
+///
+/// * `caps` - The optional max amount per period and lifetime cap the subscriber signs
+///
+/// * `beneficiary` - An optional Accounthash that holds the account given access when the subscriber pays for another account
+///
+/// * `metered` - A bool that holds whether the usage reported is charged on top of the price, within the max amount per period
+///
+fn add_subscription(from: AccountHash, public_key: PublicKey, erc20_contract_hash: ContractHash, coupon_code: Option<String>, caps: (Option<U256>, Option<U256>), beneficiary: Option<AccountHash>, metered: bool) -> (u64, String)
 {
-    let public_key: PublicKey= runtime::get_named_arg(constants::PUBLIC);
-    let from: AccountHash = runtime::get_named_arg(constants::FROM);
+    let token_amount: U256 = tokens::get_price(erc20_contract_hash).unwrap_or_revert_with(ContractError::UnsupportedToken);
+    let hashes = Hashes::new();
+    let (max_amount_per_period, lifetime_cap) = caps;
+    // a subscriber paying for itself is not a gift, and signs the same hash as without beneficiary
+    let beneficiary: Option<AccountHash> = beneficiary.filter(|beneficiary| *beneficiary != from);
 
-    let data: String = get_subscription_data(from);
+    // usage is only charged within a max amount per period the subscriber signed
+    if metered && max_amount_per_period.is_none()
+    {
+        runtime::revert(ApiError::User(ContractError::InvalidMaxAmount as u16));
+    }
 
-    let hashes = Hashes::new();
+    let subscription_id: u64 = hashes.add(from, public_key, erc20_contract_hash, token_amount);
+    Caps::new().set(subscription_id, token_amount, max_amount_per_period, lifetime_cap);
+    if metered
+    {
+        Metering::new().set_metered(subscription_id);
+    }
+    let hash: String = _get_subscription_hash(get_subscription_data(from, subscription_id, erc20_contract_hash, token_amount, max_amount_per_period, lifetime_cap, beneficiary, metered));
+    hashes.set(subscription_id, &hash);
 
-    let (opt_hash, opt_public_key) = hashes.get(from);
-
-    match opt_hash {
-        Some(hash) => {
-            let blocktime:u64 =runtime::get_blocktime().into();
-            let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert();
-            let next_valid_timestamp:u64=blocktime + 1000 * period_seconds;
-            let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,hash);
-            utils::set_key(&next_valid_timestamp_key,next_valid_timestamp); 
-        
-            match opt_public_key {
-                Some(_public_key) => {
-                    runtime::ret(CLValue::from_t(hash).unwrap_or_revert());
-                },
-                // This should not be possible
-                None => {
-                    runtime::revert(ApiError::User(ContractError::MissingPublicKey as u16));
-                }
-            }
-        },
-        None => {
-            let hash: String = _get_subscription_hash(data);
-            hashes.set(from, &hash, public_key);
-
-            let blocktime:u64 =runtime::get_blocktime().into();
-            let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert();
-            let next_valid_timestamp:u64=blocktime + 1000 * period_seconds;
-            let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,hash);
-            utils::set_key(&next_valid_timestamp_key,next_valid_timestamp); 
-        
-            runtime::ret(CLValue::from_t(hash).unwrap_or_revert());
-        }
+    let subscribers = Subscribers::new();
+    subscribers.add_subscription(from, subscription_id);
+    if let Some(beneficiary) = beneficiary
+    {
+        hashes.set_beneficiary(subscription_id, beneficiary);
+        subscribers.add_access(beneficiary, subscription_id);
+    }
+
+    // the account given access holds the first seat
+    if seats::is_seat_based()
+    {
+        Seats::new().add_member(subscription_id, beneficiary.unwrap_or(from));
+    }
+
+    let blocktime:u64 =runtime::get_blocktime().into();
+
+    // the signed hash commits to the full price, a coupon only lowers what is charged
+    if let Some(coupon_code) = coupon_code
+    {
+        let discount: Discount = Coupons::new().redeem(&coupon_code, erc20_contract_hash, blocktime);
+        let discounted_amount = pricing::discounted_price(token_amount, discount.discount_type, discount.discount);
+        let periods = if discount.periods == 0 { EVERY_PERIOD } else { discount.periods };
+        hashes.set_discount(subscription_id, &coupon_code, discounted_amount, periods);
     }
+
+    let next_valid_timestamp:u64=get_billing_interval().next_timestamp(blocktime);
+    hashes.set_next_valid_timestamp(subscription_id,next_valid_timestamp);
+
+    (subscription_id, hash)
+}
+
+/// Get the eip-191 standard hash of a subscription, external interface.
+/// # Parameters
+/// 
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+#[no_mangle]
+pub fn get_subscription_hash()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+
+    let (opt_hash, _opt_public_key) = Hashes::new().get(subscription_id);
+    let hash: String = opt_hash.unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    runtime::ret(CLValue::from_t(hash).unwrap_or_revert());
 }
 
 ///
-/// Given the subscription details, generate eip-191 standard hash, external interface, do not return (use this until text is fixed).
+/// Given the subscription details, register a new subscription and generate its eip-191 standard hash, external interface.
+/// An account can hold any number of subscriptions, each registration gets the next subscription id.
+/// Returns the u64 subscription id, the hash is stored in the `hashes` dictionary under it.
 /// # Parameters
 /// 
 /// * `public_key` - A string slice that holds the public key of the meta transaction signer,  Subscriber have to get it from running cryptoxide project externally.
 ///
 /// * `from` - An Accounthash that holds the account address of the subscriber/signer
 ///
+/// * `erc20_contract_hash` - An optional hash Key of the erc20 token to pay with, one of `get_token_prices`, the token set at install by default
+///
+/// * `coupon_code` - An optional string slice that holds a coupon of the publisher to redeem
+///
+/// * `max_amount_per_period` - An optional U256 that holds the most a payment can charge, usage and every seat included, a payment above it reverts
+///
+/// * `lifetime_cap` - An optional U256 that holds the most the subscription can charge over its lifetime
+///
+/// * `beneficiary` - An optional Accounthash that holds the account given access, for a subscription `from` pays for another account such as a gift or a seat
+///
+/// * `metered` - An optional bool that makes the subscription metered: each payment is the price of a period plus the usage reported, which needs a `max_amount_per_period`
+///
 #[no_mangle]
 pub fn create_subscription_hash()
 {
     let public_key: PublicKey = runtime::get_named_arg(constants::PUBLIC);
     let from: AccountHash = runtime::get_named_arg(constants::FROM);
+    let erc20_contract_hash: ContractHash = match utils::get_optional_named_arg(constants::ERC20_CONTRACT_HASH) {
+        Some(key) => get_erc20_contract_hash(key),
+        None => tokens::get_default(),
+    };
 
-    let data: String = get_subscription_data(from);
-
-    let hashes = Hashes::new();
+    let coupon_code: Option<String> = utils::get_optional_named_arg(constants::COUPON_CODE);
+    let max_amount_per_period: Option<U256> = utils::get_optional_named_arg(constants::MAX_AMOUNT_PER_PERIOD);
+    let lifetime_cap: Option<U256> = utils::get_optional_named_arg(constants::LIFETIME_CAP);
+    let beneficiary: Option<AccountHash> = utils::get_optional_named_arg(constants::BENEFICIARY);
+    let metered: bool = utils::get_optional_named_arg(constants::METERED).unwrap_or(false);
 
-    let (opt_hash, _opt_public_key) = hashes.get(from);
+    let (subscription_id, _hash) = add_subscription(from, public_key, erc20_contract_hash, coupon_code, (max_amount_per_period, lifetime_cap), beneficiary, metered);
 
-    match opt_hash {
-        Some(_hash) => {
-            runtime::revert(ApiError::User(ContractError::HashExists as u16));
-        },
-        None => {
-            let hash: String = _get_subscription_hash(data);
-            hashes.set(from, &hash, public_key);
-
-            let blocktime:u64 =runtime::get_blocktime().into();
-            let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert();
-            let next_valid_timestamp:u64=blocktime + 1000 * period_seconds;
-            let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,hash);
-            utils::set_key(&next_valid_timestamp_key,next_valid_timestamp); 
-        }
-    }
+    runtime::ret(CLValue::from_t(subscription_id).unwrap_or_revert());
 }
 
 
+/// Decode the hex encoded signature handed in by the subscriber.
+/// # Parameters
+///
+/// * `signature` - A string slice that holds the hex encoded Ed25519 signature of the meta transaction
+///
+pub fn parse_signature(signature: String) -> Result<Signature, ContractError>
+{
+    let mut sig_bytes = [0u8;64];
+
+    hex::decode_to_slice(signature, &mut sig_bytes as &mut [u8]).map_err(|error| match error {
+        hex::FromHexError::InvalidStringLength | hex::FromHexError::OddLength => ContractError::InvalidSignatureLength,
+        _ => ContractError::MalformedSignatureHex,
+    })?;
+
+    Signature::ed25519(sig_bytes).map_err(|_| ContractError::InvalidSignature)
+}
+
 /// This function is to get subcription signer and verify if it is equal
 /// to the signer public key or not. 
 /// 
@@ -212,18 +480,54 @@ pub fn create_subscription_hash()
 /// 
 /// * `get_blake2b_standard_hash` - A u8 array that holds the eip-191 standard subcription hash of the meta transaction
 /// 
-pub fn get_subscription_signer_and_verification(public_key: PublicKey, signature:Signature,blake2b_hash_bytes:[u8;32]) -> bool
+pub fn get_subscription_signer_and_verification(public_key: PublicKey, signature:Signature,blake2b_hash_bytes:[u8;32]) -> Result<(), ContractError>
 {
-    if let PublicKey::Ed25519(pub_key) = public_key 
-    {
-        if let Signature::Ed25519(sig) = signature 
-        {
-            pub_key.verify_strict(&blake2b_hash_bytes, &sig).unwrap();
-            return true;
+    match (public_key, signature) {
+        (PublicKey::Ed25519(pub_key), Signature::Ed25519(sig)) => {
+            pub_key.verify_strict(&blake2b_hash_bytes, &sig).map_err(|_| ContractError::SignatureVerificationFailed)
+        },
+        _ => Err(ContractError::UnsupportedKeyType),
+    }
+}
+
+/// Check that the subscriber has approved and holds enough erc20 tokens for a payment,
+/// reverting with `InsufficientAllowance` or `InsufficientBalance` otherwise.
+/// # Parameters
+///
+/// * `contract_hash` - A ContractHash that holds the contracthash of erc20 contract
+///
+/// * `from` - An Accounthash that holds the account address of the subscriber/signer
+///
+/// * `token_amount` - An U256 that holds the token amount of the payment
+///
+pub fn check_erc20_funds(contract_hash: ContractHash, from: AccountHash, token_amount: U256)
+{
+    let allowance:U256=runtime::call_contract(
+        contract_hash,
+        "allowance",
+        runtime_args!{
+            "owner" => Key::Account(from),
+            "spender" => get_package_key(),
         }
+    );
+
+    if allowance < token_amount
+    {
+        runtime::revert(ApiError::User(ContractError::InsufficientAllowance as u16));
     }
 
-    false
+    let balance:U256=runtime::call_contract(
+        contract_hash,
+        "balance_of",
+        runtime_args!{
+            "address" => Key::Account(from),
+        }
+    );
+
+    if balance < token_amount
+    {
+        runtime::revert(ApiError::User(ContractError::InsufficientBalance as u16));
+    }
 }
 
 /// You don't really need this if you are using the approve/transferFrom method
@@ -234,42 +538,35 @@ pub fn get_subscription_signer_and_verification(public_key: PublicKey, signature
 ///
 /// * `signature` - A string slice that holds the signature of the meta transaction,  Subscriber have to get it from running cryptoxide project externally.
 /// 
-/// * `from` - An Accounthash that holds the account address of the subscriber/signer
+/// * `subscription_id` - An u64 that holds the id of the subscription
 #[no_mangle]
 pub fn cancel_subscription()
 {
     let signature:String = runtime::get_named_arg(constants::SIGNATURE);
-    let from: AccountHash = runtime::get_named_arg(constants::FROM);
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
 
     let hashes = Hashes::new();
-    let opt_public_key = hashes.get_public_key(from);
+    let opt_public_key = hashes.get_public_key(subscription_id);
     
     match opt_public_key {
         Some(public_key) => {
-            let data:String = get_subscription_data(from);
+            let from: AccountHash = hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+            let (erc20_contract_hash, token_amount) = get_subscription_token(&hashes, subscription_id);
+            let (max_amount_per_period, lifetime_cap) = Caps::new().get(subscription_id);
+            let beneficiary: Option<AccountHash> = hashes.get_beneficiary(subscription_id);
+            let metered: bool = Metering::new().is_metered(subscription_id);
+            let data:String = get_subscription_data(from, subscription_id, erc20_contract_hash, token_amount, max_amount_per_period, lifetime_cap, beneficiary, metered);
             let subscription_hash_bytes: [u8;32] = get_subscription_hash_bytes(data);
-            let mut sig_bytes = [0u8;64];
-            
-            hex::decode_to_slice(signature, &mut sig_bytes as &mut [u8]).unwrap_or_default();
-
-            let sig = Signature::ed25519(sig_bytes).unwrap_or_else(| x| runtime::revert(ApiError::User(ContractError::InvalidSignature as u16)));
+            let sig = parse_signature(signature).unwrap_or_revert();
 
-            let result:bool = get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes);
-
-            if !result
-            {
-                //  signature verification failed  
-                runtime::revert(ApiError::User(ContractError::SignerFailed as u16));
-            }
+            // revert with the reason the signature was rejected
+            get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes).unwrap_or_revert();
 
             //subscription will become valid again Wednesday, November 16, 5138 9:46:39 AM
             //at this point the nextValidTimestamp should be a timestamp that will never
             //be reached during the brief window human existence
             
-            let next_valid_timestamp:u64=99999999999*1000;
-            let subscription_hash_string:String=hex::encode(subscription_hash_bytes);
-            let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,subscription_hash_string);
-            utils::set_key(&next_valid_timestamp_key,next_valid_timestamp); 
+            hashes.set_next_valid_timestamp(subscription_id,CANCELLED_TIMESTAMP);
         },
         None => {
             runtime::revert(ApiError::User(ContractError::MissingPublicKey as u16));
@@ -284,67 +581,51 @@ pub fn cancel_subscription()
 ///
 /// * `signature` - A string slice that holds the signature of the meta transaction,  Subscriber have to get it from running cryptoxide project externally.
 /// 
-/// * `from` - An Accounthash that holds the account address of the subscriber/signer
+/// * `subscription_id` - An u64 that holds the id of the subscription
 #[no_mangle]
 pub fn is_subscription_ready()
 {
     let signature:String = runtime::get_named_arg(constants::SIGNATURE);
-    let from:AccountHash = runtime::get_named_arg(constants::FROM);
-
-    let token_amount:U256=utils::get_key(constants::TOKEN_AMOUNT).unwrap_or_revert();
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
 
     let hashes = Hashes::new();
-    let opt_public_key = hashes.get_public_key(from);
+    let opt_public_key = hashes.get_public_key(subscription_id);
     
     match opt_public_key {
         Some(public_key) => {
-            let data:String = get_subscription_data(from);
+            let from: AccountHash = hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+            let (erc20_contract_hash, token_amount) = get_subscription_token(&hashes, subscription_id);
+            let (max_amount_per_period, lifetime_cap) = Caps::new().get(subscription_id);
+            let beneficiary: Option<AccountHash> = hashes.get_beneficiary(subscription_id);
+            let metered: bool = Metering::new().is_metered(subscription_id);
+            let data:String = get_subscription_data(from, subscription_id, erc20_contract_hash, token_amount, max_amount_per_period, lifetime_cap, beneficiary, metered);
             let subscription_hash_bytes: [u8;32] = get_subscription_hash_bytes(data);
-            let mut sig_bytes = [0u8;64];
-            
-            hex::decode_to_slice(signature, &mut sig_bytes as &mut [u8]).unwrap_or_default();
+            let sig = parse_signature(signature).unwrap_or_revert();
 
-            let sig = Signature::ed25519(sig_bytes).unwrap_or_else(| x| runtime::revert(ApiError::User(ContractError::InvalidSignature as u16)));
-
-            let result:bool = get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes);
-
-            // if signature verification Successfully
-            if result 
+            match get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes)
             {
-                let blocktime:u64=runtime::get_blocktime().into();
-                let subscription_hash_string:String=hex::encode(subscription_hash_bytes);
-                let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,subscription_hash_string);
-                let next_valid_timestamp:u64 = utils::get_key(&next_valid_timestamp_key).unwrap_or_revert();
+                // if signature verification is Successfull
+                Ok(()) => {
+                    let blocktime:u64=runtime::get_blocktime().into();
+                    let next_valid_timestamp:u64 = hashes.get_next_valid_timestamp(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
                 
-                if blocktime >= next_valid_timestamp
-                {
-                    let contract_hash: ContractHash = utils::get_key(constants::ERC20_CONTRACT_HASH).unwrap_or_revert();
-                
-                    let allowance_result:U256=runtime::call_contract(
-                        contract_hash,
-                        "allowance",
-                        runtime_args!{
-                            "owner" => Key::Account(from),
-                            "spender" => Key::Hash(utils::get_key(&"package_hash".to_string()).unwrap_or_revert()),
-                        }
-                    );
-
-                    if allowance_result < token_amount
+                    if blocktime >= next_valid_timestamp
                     {
-                        // subscription not ready (allowance is less than token_amount)
-                        runtime::revert(ApiError::User(ContractError::InsufficientAllowance as u16));
+                        // subscription not ready (past a spending cap, or allowance or balance is less than the payment amount)
+                        let payment_amount = get_payment_amount(&hashes, subscription_id, erc20_contract_hash, get_due_timestamp(&hashes, subscription_id));
+                        Caps::new().check(subscription_id, payment_amount);
+                        check_erc20_funds(erc20_contract_hash, from, payment_amount);
                     }
+                    else
+                    {
+                        // subscription not ready (blocktime is less than next_valid_timestamp)
+                        runtime::revert(ApiError::User(ContractError::InvalidBlockTime as u16));
+                    }
+                },
+                Err(error) => {
+                    // signature verification failed 
+                    runtime::revert(ApiError::from(error));
                 }
-                else
-                {
-                    // subscription not ready (blocktime is less than next_valid_timestamp)
-                    runtime::revert(ApiError::User(ContractError::InvalidBlockTime as u16));
-                }
-            }
-            else
-            {
-                // signature verification failed 
-                runtime::revert(ApiError::User(ContractError::SignerFailed as u16));
             }
         },
         None => {
@@ -360,72 +641,121 @@ pub fn is_subscription_ready()
 ///
 /// * `signature` - A string slice that holds the signature of the meta transaction, Subscriber have to get it from running cryptoxide project externally.
 /// 
-/// * `from` - An Accounthash that holds the account address of the subscriber/signer
+/// * `subscription_id` - An u64 that holds the id of the subscription
 #[no_mangle]
 pub fn execute_subscription()
 {
     let signature:String = runtime::get_named_arg(constants::SIGNATURE);
-    let from: AccountHash = runtime::get_named_arg(constants::FROM);
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
 
-    let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert();
-    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert();
-    let token_amount:U256=utils::get_key(constants::TOKEN_AMOUNT).unwrap_or_revert();
+    let billing_interval: BillingInterval = get_billing_interval();
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
 
     let hashes = Hashes::new();
-    let opt_public_key = hashes.get_public_key(from);
+    let opt_public_key = hashes.get_public_key(subscription_id);
 
     match opt_public_key {
         Some(public_key) => {
-            let data:String = get_subscription_data(from);
+            let from: AccountHash = hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+            let (erc20_contract_hash, token_amount) = get_subscription_token(&hashes, subscription_id);
+            let (max_amount_per_period, lifetime_cap) = Caps::new().get(subscription_id);
+            let beneficiary: Option<AccountHash> = hashes.get_beneficiary(subscription_id);
+            let metered: bool = Metering::new().is_metered(subscription_id);
+            let data:String = get_subscription_data(from, subscription_id, erc20_contract_hash, token_amount, max_amount_per_period, lifetime_cap, beneficiary, metered);
             let subscription_hash_bytes: [u8;32] = get_subscription_hash_bytes(data);
-            let mut sig_bytes = [0u8;64];
+            let sig = parse_signature(signature).unwrap_or_revert();
+
+            match get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes)
+            {
+                // if signature verification is Successfull
+                Ok(()) => {
+                    let blocktime:u64 =runtime::get_blocktime().into();
+                    let mut next_valid_timestamp:u64= hashes.get_next_valid_timestamp(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
 
-            hex::decode_to_slice(signature, &mut sig_bytes as &mut [u8]).unwrap_or_default();
+                    if blocktime >= next_valid_timestamp
+                    {
+                        // the period is charged the price in effect when it fell due, however late it is paid
+                        let due_at: u64 = get_due_timestamp(&hashes, subscription_id);
 
-            let sig = Signature::ed25519(sig_bytes).unwrap_or_else(| x| runtime::revert(ApiError::User(ContractError::InvalidSignature as u16)));
+                        if next_valid_timestamp == 0
+                        {
+                            next_valid_timestamp=blocktime;
+                        }
+                        next_valid_timestamp=billing_interval.next_timestamp(next_valid_timestamp);
+                        hashes.set_next_valid_timestamp(subscription_id, next_valid_timestamp);
 
-            let result:bool = get_subscription_signer_and_verification(public_key,sig,subscription_hash_bytes);
+                        // charge the discounted amount while the subscription's coupon applies, plus the usage of a metered one
+                        let payment_amount = get_payment_amount(&hashes, subscription_id, erc20_contract_hash, due_at);
+                        hashes.use_discount(subscription_id);
+                        if seats::is_seat_based()
+                        {
+                            Seats::new().reset_proration(subscription_id);
+                        }
+                        if metered
+                        {
+                            Metering::new().reset_usage(subscription_id);
+                        }
 
-            // if signature verification is Successfull
-            if result 
-            {
-                let blocktime:u64 =runtime::get_blocktime().into();
-                let subscription_hash_string:String=hex::encode(subscription_hash_bytes);
-                let next_valid_timestamp_key:String=format!("{}{}", constants::NEXT_VALID_TIMESTAMP,subscription_hash_string);
-                let mut next_valid_timestamp:u64= utils::get_key(&next_valid_timestamp_key).unwrap_or_revert();
-
-                if blocktime >= next_valid_timestamp
-                {
-                    if next_valid_timestamp == 0
-                    {
-                        next_valid_timestamp=blocktime;
-                    }
-                    next_valid_timestamp=next_valid_timestamp+(period_seconds*1000);
-                    utils::set_key(&next_valid_timestamp_key, next_valid_timestamp);
+                        // within the spending caps the subscriber signed, whatever the price and the allowance
+                        Caps::new().add_payment(subscription_id, payment_amount);
 
-                    let contract_hash: ContractHash = utils::get_key(constants::ERC20_CONTRACT_HASH).unwrap_or_revert();
+                        // revert with a contract error rather than an erc20 one
+                        check_erc20_funds(erc20_contract_hash, from, payment_amount);
                 
-                    let transfer_from_result: () = runtime::call_contract(
-                        contract_hash,
-                        "transfer_from",
-                        runtime_args!{
-                            "owner" => Key::Account(from),
-                            "recipient" => Key::Account(to),
-                            "amount" => token_amount
+                        // pay in the token the subscriber chose at signup, split between the publisher and the payees
+                        let payments = payouts::split(payment_amount, to, &get_payee_shares());
+                        let dispute_window_seconds = escrow::get_dispute_window_seconds();
+
+                        if dispute_window_seconds > 0
+                        {
+                            // hold the whole payment in the contract package until the dispute window ends
+                            let transfer_from_result: () = runtime::call_contract(
+                                erc20_contract_hash,
+                                "transfer_from",
+                                runtime_args!{
+                                    "owner" => Key::Account(from),
+                                    "recipient" => get_package_key(),
+                                    "amount" => payment_amount
+                                }
+                            );
+                            // release a batch of earlier payments, so every payment makes progress on a backlog
+                            let escrow = Escrow::new();
+                            escrow.settle(blocktime);
+                            // the window fits in u64 milliseconds since install, the release time saturates
+                            escrow.add(subscription_id, erc20_contract_hash, payments, blocktime.saturating_add(dispute_window_seconds * 1000));
                         }
-                    );
-                }
-                else
-                {
-                    //blocktime is less than next_valid_timestamp
-                    runtime::revert(ApiError::User(ContractError::InvalidBlockTime as u16));
+                        else
+                        {
+                            for (recipient, amount) in payments
+                            {
+                                if amount.is_zero()
+                                {
+                                    continue;
+                                }
+
+                                let transfer_from_result: () = runtime::call_contract(
+                                    erc20_contract_hash,
+                                    "transfer_from",
+                                    runtime_args!{
+                                        "owner" => Key::Account(from),
+                                        "recipient" => Key::Account(recipient),
+                                        "amount" => amount
+                                    }
+                                );
+                            }
+                        }
+                    }
+                    else
+                    {
+                        //blocktime is less than next_valid_timestamp
+                        runtime::revert(ApiError::User(ContractError::InvalidBlockTime as u16));
+                    }
+                },
+                Err(error) => {
+                    // signature verification failed 
+                    runtime::revert(ApiError::from(error));
                 }
             }
-            else
-            {
-                // signature verification failed 
-                runtime::revert(ApiError::User(ContractError::SignerFailed as u16));
-            }
         },
         None => {
             runtime::revert(ApiError::User(ContractError::MissingPublicKey as u16));
@@ -433,54 +763,861 @@ pub fn execute_subscription()
     }
 }
 
-/// Returns the list of the entry points in the contract with added group security.
-pub fn get_entry_points() -> EntryPoints {
-    let mut entry_points = EntryPoints::new();
-    entry_points.add_entry_point(EntryPoint::new(
-        String::from("is_subscription_active"),
-        vec![
-            Parameter::new(constants::SUBSCRIPTION_HASH, String::cl_type()),
-            Parameter::new(constants::GRACE_PERIOD_SECONDS, u64::cl_type())
-        ],
-        CLType::Unit,
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        String::from("is_subscription_ready"),
-        vec![
-            Parameter::new(constants::SIGNATURE, String::cl_type()),
-            Parameter::new(constants::FROM, AccountHash::cl_type()),
-        ],
-        CLType::Unit,
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        String::from("cancel_subscription"),
-        vec![
-            Parameter::new(constants::SIGNATURE, String::cl_type()),
-            Parameter::new(constants::FROM, AccountHash::cl_type()),
-        ],
-        CLType::Unit,
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
+/// Look up the subscription hash and next valid timestamp stored for a subscription.
+/// # Parameters
+///
+/// * `hashes` - The subscription dictionaries
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+fn get_subscription_timestamp(hashes: &Hashes, subscription_id: u64) -> Option<(String, u64)>
+{
+    let (opt_hash, _opt_public_key) = hashes.get(subscription_id);
+    let hash: String = opt_hash?;
+    let next_valid_timestamp: u64 = hashes.get_next_valid_timestamp(subscription_id)?;
+
+    Some((hash, next_valid_timestamp))
+}
+
+/// The next valid timestamp of a subscription, `None` if it is not registered.
+fn get_registered_timestamp(hashes: &Hashes, subscription_id: u64) -> Option<u64>
+{
+    get_subscription_timestamp(hashes, subscription_id).map(|(_hash, timestamp)| timestamp)
+}
+
+/// Read-only view of a subscription, returned as a map of field name to value.
+/// Fields: `from`, the subscriber paying, `beneficiary`, the account given access, `from` unless
+/// it pays for another account, `subscription_hash`, `public`, `next_valid_timestamp`, `status`,
+/// `erc20_contract_hash`, `token_amount`, the price signed for, and `payment_amount`, the
+/// amount of the next payment at the price in effect when it falls due. While a price change of its token is pending, also
+/// `pending_token_amount` and `effective_at`, when it takes effect. With a coupon, also
+/// `coupon_code` and `discounted_periods`, the discounted payments left
+/// (`18446744073709551615` for every payment). With spending caps, also `max_amount_per_period`
+/// and `lifetime_cap`. When metered, also `metered` and `usage`, the units reported since the
+/// last payment. `amount_paid` is what the subscription paid, less reversed payments.
+/// On a seat-based plan, also `seats`, the number of members `payment_amount` pays for.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+#[no_mangle]
+pub fn get_subscription()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+
+    let hashes = Hashes::new();
+    let (hash, next_valid_timestamp) = get_subscription_timestamp(&hashes, subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let from: AccountHash = hashes.get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let public_key: PublicKey = hashes.get_public_key(subscription_id).unwrap_or_revert_with(ContractError::MissingPublicKey);
+    let (erc20_contract_hash, token_amount) = get_subscription_token(&hashes, subscription_id);
+
+    let blocktime: u64 = runtime::get_blocktime().into();
+    let status = SubscriptionStatus::from_timestamps(Some(next_valid_timestamp), blocktime, 0);
+
+    let mut subscription: BTreeMap<String, String> = BTreeMap::new();
+    subscription.insert(constants::FROM.to_string(), from.to_formatted_string());
+    subscription.insert(constants::BENEFICIARY.to_string(), get_beneficiary(&hashes, subscription_id).to_formatted_string());
+    subscription.insert(constants::SUBSCRIPTION_HASH.to_string(), hash);
+    subscription.insert(constants::PUBLIC.to_string(), hex::encode(public_key.to_bytes().unwrap_or_revert()));
+    subscription.insert(constants::NEXT_VALID_TIMESTAMP.to_string(), next_valid_timestamp.to_string());
+    subscription.insert(constants::STATUS.to_string(), (status as u8).to_string());
+    subscription.insert(constants::ERC20_CONTRACT_HASH.to_string(), erc20_contract_hash.to_formatted_string());
+    subscription.insert(constants::TOKEN_AMOUNT.to_string(), token_amount.to_string());
+    subscription.insert(String::from("payment_amount"), get_payment_amount(&hashes, subscription_id, erc20_contract_hash, get_due_timestamp(&hashes, subscription_id)).to_string());
+
+    if let Some((pending_token_amount, effective_at)) = tokens::get_pending_price(erc20_contract_hash)
+    {
+        subscription.insert(String::from("pending_token_amount"), pending_token_amount.to_string());
+        subscription.insert(constants::EFFECTIVE_AT.to_string(), effective_at.to_string());
+    }
+
+    if let Some(coupon_code) = hashes.get_coupon(subscription_id)
+    {
+        let discounted_periods = hashes.get_discount(subscription_id).map_or(0, |(_discounted_amount, periods)| periods);
+        subscription.insert(constants::COUPON_CODE.to_string(), coupon_code);
+        subscription.insert(String::from("discounted_periods"), discounted_periods.to_string());
+    }
+
+    let caps = Caps::new();
+    let (max_amount_per_period, lifetime_cap) = caps.get(subscription_id);
+    if let Some(max_amount_per_period) = max_amount_per_period
+    {
+        subscription.insert(constants::MAX_AMOUNT_PER_PERIOD.to_string(), max_amount_per_period.to_string());
+    }
+    if let Some(lifetime_cap) = lifetime_cap
+    {
+        subscription.insert(constants::LIFETIME_CAP.to_string(), lifetime_cap.to_string());
+    }
+    let metering = Metering::new();
+    if metering.is_metered(subscription_id)
+    {
+        subscription.insert(constants::METERED.to_string(), true.to_string());
+        subscription.insert(constants::USAGE.to_string(), metering.get_usage(subscription_id).to_string());
+    }
+    subscription.insert(String::from("amount_paid"), caps.get_amount_paid(subscription_id).to_string());
+
+    if seats::is_seat_based()
+    {
+        subscription.insert(String::from("seats"), Seats::new().get_seats(subscription_id).to_string());
+    }
+
+    runtime::ret(CLValue::from_t(subscription).unwrap_or_revert());
+}
+
+/// Read-only view of when the next payment of a subscription can be executed,
+/// returned as a u64 blocktime in milliseconds.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+#[no_mangle]
+pub fn get_next_payment_time()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+
+    let (_hash, next_valid_timestamp) = get_subscription_timestamp(&Hashes::new(), subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    runtime::ret(CLValue::from_t(next_valid_timestamp).unwrap_or_revert());
+}
+
+/// Read-only view of the status of a subscription, returned as a u8 `SubscriptionStatus`:
+/// 0 not found, 1 active, 2 due, 3 cancelled.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+#[no_mangle]
+pub fn get_subscription_status()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+
+    let next_valid_timestamp: Option<u64> = get_subscription_timestamp(&Hashes::new(), subscription_id).map(|(_hash, timestamp)| timestamp);
+    let blocktime: u64 = runtime::get_blocktime().into();
+    let status = SubscriptionStatus::from_timestamps(next_valid_timestamp, blocktime, 0);
+
+    runtime::ret(CLValue::from_t(status as u8).unwrap_or_revert());
+}
+
+/// Read-only view of a subscription giving an account access and active within the grace period,
+/// for other contracts gating content on it (see the `eip1337-access` crate): the id of the
+/// oldest one the account pays for itself, else of the first one it receives as beneficiary or
+/// holds a seat of, returned as an `Option<u64>`, `None` without one. Unlike `is_subscription_active`, it does not revert
+/// for an account without access, so the calling contract can handle it.
+/// # Parameters
+///
+/// * `account` - An Accounthash that holds the account address to check access for
+///
+/// * `grace_period_seconds` - An u64 that holds the seconds a payment may be late
+///
+#[no_mangle]
+pub fn get_active_subscription()
+{
+    let account: AccountHash = runtime::get_named_arg(constants::ACCOUNT);
+    let grace_period_seconds: u64 = runtime::get_named_arg(constants::GRACE_PERIOD_SECONDS);
+
+    let blocktime: u64 = runtime::get_blocktime().into();
+    let hashes = Hashes::new();
+    let subscribers = Subscribers::new();
+    let subscription_id: Option<u64> = subscribers.get_subscriptions(account).into_iter().chain(subscribers.get_access(account)).find(|subscription_id| {
+        let next_valid_timestamp: Option<u64> = hashes.get_next_valid_timestamp(*subscription_id);
+
+        gives_access(&hashes, *subscription_id, account)
+            && SubscriptionStatus::from_timestamps(next_valid_timestamp, blocktime, grace_period_seconds) == SubscriptionStatus::Active
+    });
+
+    runtime::ret(CLValue::from_t(subscription_id).unwrap_or_revert());
+}
+
+/// Read-only view of the ids of every subscription registered by an account, received as
+/// beneficiary or given a seat of, returned as a list of u64: the ones it registered oldest first,
+/// then the ones it was given access to in the order it was given it.
+/// # Parameters
+///
+/// * `from` - An Accounthash that holds the account address of the subscriber/signer
+///
+#[no_mangle]
+pub fn get_account_subscriptions()
+{
+    let from: AccountHash = runtime::get_named_arg(constants::FROM);
+
+    let subscribers = Subscribers::new();
+    let mut subscription_ids: Vec<u64> = subscribers.get_subscriptions(from);
+    for subscription_id in subscribers.get_access(from) {
+        if !subscription_ids.contains(&subscription_id) {
+            subscription_ids.push(subscription_id);
+        }
+    }
+
+    runtime::ret(CLValue::from_t(subscription_ids).unwrap_or_revert());
+}
+
+/// Read-only view of the subscription plan, returned as a map of field name to value.
+/// Fields: `to`, `token_amount`, the current price in the default token, `period_seconds`,
+/// `erc20_contract_hash`, the token subscriptions pay with by default, `dispute_window_seconds`, 0 without escrow, and
+/// `usage_oracle`, the account reporting usage besides the publisher, and `interval_type` and
+/// `billing_day`, the calendar period (see `calendar`), and `seat_based`, whether subscriptions
+/// pay for every member.
+/// See `get_token_prices` for every accepted token.
+#[no_mangle]
+pub fn get_plan()
+{
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
+    let period_seconds:u64=utils::get_key(constants::PERIOD_SECONDS).unwrap_or_revert_with(ContractError::MissingKey);
+    let contract_hash: ContractHash = tokens::get_default();
+    let token_amount:U256=tokens::get_price(contract_hash).unwrap_or_revert_with(ContractError::UnsupportedToken);
+
+    let mut plan: BTreeMap<String, String> = BTreeMap::new();
+    plan.insert(constants::TO.to_string(), to.to_formatted_string());
+    plan.insert(constants::TOKEN_AMOUNT.to_string(), token_amount.to_string());
+    plan.insert(constants::PERIOD_SECONDS.to_string(), period_seconds.to_string());
+    plan.insert(constants::ERC20_CONTRACT_HASH.to_string(), contract_hash.to_formatted_string());
+    plan.insert(escrow::DISPUTE_WINDOW_SECONDS.to_string(), escrow::get_dispute_window_seconds().to_string());
+    plan.insert(metering::USAGE_ORACLE.to_string(), metering::get_usage_oracle().to_formatted_string());
+
+    let billing_interval: BillingInterval = get_billing_interval();
+    plan.insert(calendar::INTERVAL_TYPE.to_string(), (billing_interval.interval_type as u8).to_string());
+    plan.insert(calendar::BILLING_DAY.to_string(), billing_interval.billing_day.to_string());
+    plan.insert(seats::SEAT_BASED.to_string(), seats::is_seat_based().to_string());
+
+    runtime::ret(CLValue::from_t(plan).unwrap_or_revert());
+}
+
+/// Read-only view of the erc20 tokens the plan accepts, returned as a map of
+/// formatted contract hash to the price of a period in that token.
+#[no_mangle]
+pub fn get_token_prices()
+{
+    let prices: BTreeMap<String, String> = tokens::get_prices()
+        .into_iter()
+        .map(|(erc20_contract_hash, token_amount)| (erc20_contract_hash.to_formatted_string(), token_amount.to_string()))
+        .collect();
+
+    runtime::ret(CLValue::from_t(prices).unwrap_or_revert());
+}
+
+/// Accept another erc20 token, so subscribers can choose to pay with it at signup.
+/// Only the publisher (`to`) can add tokens. Adding a token twice reverts with `TokenExists`,
+/// the price of an accepted token only changes with notice, see `schedule_price_change`.
+/// # Parameters
+///
+/// * `erc20_contract_hash` - A hash Key that holds the contracthash of the erc20 contract
+///
+/// * `token_amount` - An U256 that holds the price of a period in that token
+///
+#[no_mangle]
+pub fn add_token()
+{
+    let erc20_contract_hash: Key = runtime::get_named_arg(constants::ERC20_CONTRACT_HASH);
+    let token_amount: U256 = runtime::get_named_arg(constants::TOKEN_AMOUNT);
+
+    require_publisher();
+
+    tokens::add(get_erc20_contract_hash(erc20_contract_hash), token_amount);
+}
+
+/// Read-only view of the price changes that have not taken effect yet, returned as a map of
+/// formatted erc20 contract hash to the new price of a period and the blocktime in milliseconds
+/// it takes effect at.
+#[no_mangle]
+pub fn get_pending_prices()
+{
+    let blocktime: u64 = runtime::get_blocktime().into();
+    let pending_prices: BTreeMap<String, (U256, u64)> = tokens::get_pending_prices()
+        .into_iter()
+        .filter(|(_erc20_contract_hash, (_token_amount, effective_at))| *effective_at > blocktime)
+        .map(|(erc20_contract_hash, pending_price)| (erc20_contract_hash.to_formatted_string(), pending_price))
+        .collect();
+
+    runtime::ret(CLValue::from_t(pending_prices).unwrap_or_revert());
+}
+
+/// Change the price of a period in an accepted erc20 token from `effective_at`. Only the
+/// publisher (`to`) can change prices. Periods falling due before `effective_at` are charged the
+/// old price, so subscribers can cancel before the new one applies, and it must leave them a whole
+/// billing period of notice, reverting with `PriceNoticeTooShort` otherwise. Subscription hashes keep the
+/// price they were signed for. A new change of the token replaces the pending one.
+/// # Parameters
+///
+/// * `erc20_contract_hash` - A hash Key that holds the contracthash of the erc20 contract
+///
+/// * `token_amount` - An U256 that holds the new price of a period in that token
+///
+/// * `effective_at` - An u64 that holds the blocktime in milliseconds the new price applies from
+///
+#[no_mangle]
+pub fn schedule_price_change()
+{
+    let erc20_contract_hash: Key = runtime::get_named_arg(constants::ERC20_CONTRACT_HASH);
+    let token_amount: U256 = runtime::get_named_arg(constants::TOKEN_AMOUNT);
+    let effective_at: u64 = runtime::get_named_arg(constants::EFFECTIVE_AT);
+
+    require_publisher();
+
+    let blocktime: u64 = runtime::get_blocktime().into();
+    if effective_at < get_billing_interval().notice_end(blocktime)
+    {
+        runtime::revert(ApiError::User(ContractError::PriceNoticeTooShort as u16));
+    }
+
+    tokens::schedule(get_erc20_contract_hash(erc20_contract_hash), token_amount, effective_at);
+}
+
+/// Read-only view of the price of a unit of usage, returned as a map of formatted erc20
+/// contract hash to unit price. Usage in a token without a unit price is not charged.
+#[no_mangle]
+pub fn get_unit_prices()
+{
+    let unit_prices: BTreeMap<String, String> = metering::get_unit_prices()
+        .into_iter()
+        .map(|(erc20_contract_hash, unit_price)| (erc20_contract_hash.to_formatted_string(), unit_price.to_string()))
+        .collect();
+
+    runtime::ret(CLValue::from_t(unit_prices).unwrap_or_revert());
+}
+
+/// Set the price of a unit of usage in an accepted erc20 token, charged to metered subscriptions
+/// on top of the price of a period. Only the publisher (`to`) can set unit prices. Each metered
+/// subscription signed the most it can be charged in a period, a payment above it reverts.
+/// # Parameters
+///
+/// * `erc20_contract_hash` - A hash Key that holds the contracthash of the erc20 contract
+///
+/// * `unit_price` - An U256 that holds the price of a unit of usage in that token, 0 stops charging usage
+///
+#[no_mangle]
+pub fn set_unit_price()
+{
+    let erc20_contract_hash: Key = runtime::get_named_arg(constants::ERC20_CONTRACT_HASH);
+    let unit_price: U256 = runtime::get_named_arg(constants::UNIT_PRICE);
+
+    require_publisher();
+
+    let erc20_contract_hash = get_erc20_contract_hash(erc20_contract_hash);
+    if tokens::get_price(erc20_contract_hash).is_none()
+    {
+        runtime::revert(ApiError::User(ContractError::UnsupportedToken as u16));
+    }
+
+    metering::set_unit_price(erc20_contract_hash, unit_price);
+}
+
+/// Let an account, such as an oracle metering the service, report usage besides the publisher.
+/// Only the publisher (`to`) can set the oracle, which replaces the previous one. Setting the
+/// publisher leaves it the only reporter.
+/// # Parameters
+///
+/// * `oracle` - An Accounthash that holds the account address of the oracle
+///
+#[no_mangle]
+pub fn set_usage_oracle()
+{
+    let oracle: AccountHash = runtime::get_named_arg(constants::ORACLE);
+
+    require_publisher();
+
+    utils::set_key(metering::USAGE_ORACLE, oracle);
+}
+
+/// Report units of usage of a metered subscription, added to the usage charged by its next
+/// payment. Only the publisher (`to`) or the usage oracle can report usage.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+/// * `usage` - An u64 that holds the units of usage to add
+///
+#[no_mangle]
+pub fn report_usage()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+    let usage: u64 = runtime::get_named_arg(constants::USAGE);
+
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
+    let caller = runtime::get_caller();
+    if caller != to && caller != metering::get_usage_oracle()
+    {
+        runtime::revert(ApiError::User(ContractError::NotUsageReporter as u16));
+    }
+
+    let next_valid_timestamp: u64 = Hashes::new().get_next_valid_timestamp(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    if next_valid_timestamp == CANCELLED_TIMESTAMP
+    {
+        runtime::revert(ApiError::User(ContractError::SubscriptionNotActive as u16));
+    }
+
+    let metering = Metering::new();
+    if !metering.is_metered(subscription_id)
+    {
+        runtime::revert(ApiError::User(ContractError::NotMetered as u16));
+    }
+
+    metering.add_usage(subscription_id, usage);
+}
+
+/// Give an account a seat of a subscription to a seat-based plan, and access to it. Only the
+/// subscriber paying for it can add members. The next payment is charged for the new seat from
+/// now only, the time before it was added being credited. Every seat must stay within the
+/// spending caps the subscriber signed.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+/// * `member` - An Accounthash that holds the account address of the new member
+///
+#[no_mangle]
+pub fn add_member()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+    let member: AccountHash = runtime::get_named_arg(constants::MEMBER);
+
+    let hashes = Hashes::new();
+    require_seat_owner(&hashes, subscription_id);
+
+    // the price of a period for every seat must stay within the spending caps
+    let seats = Seats::new();
+    let erc20_contract_hash: ContractHash = hashes.get_token(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+    let seat_price: U256 = get_seat_price(&hashes, subscription_id, erc20_contract_hash, get_due_timestamp(&hashes, subscription_id));
+    Caps::new().require_cover(subscription_id, pricing::seats_price(seat_price, seats.get_seats(subscription_id) + 1, U256::zero(), U256::zero()));
+
+    seats.add_member(subscription_id, member);
+    seats.add_credit(subscription_id, get_elapsed_seat_price(&hashes, subscription_id));
+    Subscribers::new().add_access(member, subscription_id);
+}
+
+/// Take the seat of a member of a subscription to a seat-based plan, and its access. Only the
+/// subscriber paying for it can remove members, and the last member cannot be removed. The next
+/// payment is charged for the removed seat until now only.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+/// * `member` - An Accounthash that holds the account address of the member to remove
+///
+#[no_mangle]
+pub fn remove_member()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+    let member: AccountHash = runtime::get_named_arg(constants::MEMBER);
+
+    let hashes = Hashes::new();
+    require_seat_owner(&hashes, subscription_id);
+
+    let seats = Seats::new();
+    seats.remove_member(subscription_id, member);
+    seats.add_charge(subscription_id, get_elapsed_seat_price(&hashes, subscription_id));
+    Subscribers::new().remove_access(member, subscription_id);
+}
+
+/// Read-only view of the members of a subscription to a seat-based plan, in the order they were
+/// added, returned as a list of account hashes. Empty on other plans.
+/// # Parameters
+///
+/// * `subscription_id` - An u64 that holds the id of the subscription
+///
+#[no_mangle]
+pub fn get_members()
+{
+    let subscription_id: u64 = runtime::get_named_arg(constants::SUBSCRIPTION_ID);
+
+    let members: Vec<AccountHash> = Seats::new().get_members(subscription_id);
+
+    runtime::ret(CLValue::from_t(members).unwrap_or_revert());
+}
+
+/// Create a coupon subscribers can redeem at `create_subscription_hash`. Only the publisher
+/// (`to`) can create coupons. The discounted price is stored with each subscription that
+/// redeems the coupon, so later changes to the coupon do not affect it.
+/// # Parameters
+///
+/// * `coupon_code` - A string slice that holds the code subscribers redeem, 1 to 64 bytes
+///
+/// * `discount_type` - An u8 that holds 0 for a percentage in basis points, 1 for a fixed amount
+///
+/// * `discount` - An U256 that holds the basis points (at most 10000) or the token amount taken off
+///
+/// * `erc20_contract_hash` - An optional hash Key of the token a fixed amount is in, the token set at install by default
+///
+/// * `periods` - An u64 that holds the number of payments discounted, 0 for every payment
+///
+/// * `max_redemptions` - An u64 that holds the number of subscriptions that can redeem it, 0 for no limit
+///
+/// * `expires_at` - An u64 that holds the blocktime in milliseconds it can no longer be redeemed, 0 for never
+///
+#[no_mangle]
+pub fn add_coupon()
+{
+    let coupon_code: String = runtime::get_named_arg(constants::COUPON_CODE);
+    let discount_type: u8 = runtime::get_named_arg(constants::DISCOUNT_TYPE);
+    let discount: U256 = runtime::get_named_arg(constants::DISCOUNT);
+    let periods: u64 = runtime::get_named_arg(constants::PERIODS);
+    let max_redemptions: u64 = runtime::get_named_arg(constants::MAX_REDEMPTIONS);
+    let expires_at: u64 = runtime::get_named_arg(constants::EXPIRES_AT);
+    let erc20_contract_hash: ContractHash = match utils::get_optional_named_arg(constants::ERC20_CONTRACT_HASH) {
+        Some(key) => get_erc20_contract_hash(key),
+        None => tokens::get_default(),
+    };
+
+    require_publisher();
+
+    let discount_type = DiscountType::from_u8(discount_type).unwrap_or_revert_with(ContractError::InvalidCoupon);
+    let discount = Discount { discount_type, discount, periods };
+    Coupons::new().add(&coupon_code, discount, erc20_contract_hash, max_redemptions, expires_at);
+}
+
+/// Stop a coupon from being redeemed. Only the publisher (`to`) can expire coupons.
+/// Subscriptions that already redeemed it keep their discount.
+/// # Parameters
+///
+/// * `coupon_code` - A string slice that holds the code of the coupon
+///
+#[no_mangle]
+pub fn expire_coupon()
+{
+    let coupon_code: String = runtime::get_named_arg(constants::COUPON_CODE);
+
+    require_publisher();
+
+    Coupons::new().expire(&coupon_code, runtime::get_blocktime().into());
+}
+
+/// Read-only view of the payees of the plan, returned as a map of formatted account hash to
+/// the share of each payment in basis points. The publisher (`to`) gets the rest.
+#[no_mangle]
+pub fn get_payees()
+{
+    let payees: BTreeMap<String, u32> = get_payee_shares()
+        .into_iter()
+        .map(|(recipient, basis_points)| (recipient.to_formatted_string(), basis_points))
+        .collect();
+
+    runtime::ret(CLValue::from_t(payees).unwrap_or_revert());
+}
+
+/// Pay an account a share of each payment, on top of the publisher. Only the publisher (`to`)
+/// can set payees. Each payee gets its basis points of a payment rounded down and the publisher
+/// gets the rest, so the transfers of an execution always add up to the token amount.
+/// Setting a payee again replaces its share, and 0 basis points removes it.
+/// # Parameters
+///
+/// * `recipient` - An Accounthash that holds the account address of the payee
+///
+/// * `basis_points` - An u32 that holds the share of each payment, out of 10000
+///
+#[no_mangle]
+pub fn set_payee()
+{
+    let recipient: AccountHash = runtime::get_named_arg(constants::RECIPIENT);
+    let basis_points: u32 = runtime::get_named_arg(constants::BASIS_POINTS);
+
+    require_publisher();
+
+    let to:AccountHash=utils::get_key(constants::TO).unwrap_or_revert_with(ContractError::MissingKey);
+    if recipient == to
+    {
+        runtime::revert(ApiError::User(ContractError::InvalidPayout as u16));
+    }
+
+    let mut payees = get_payee_shares();
+    if basis_points == 0
+    {
+        payees.remove(&recipient);
+    }
+    else
+    {
+        payees.insert(recipient, basis_points);
+    }
+
+    if !payouts::is_valid(&payees)
+    {
+        runtime::revert(ApiError::User(ContractError::InvalidPayout as u16));
+    }
+
+    utils::set_key(payouts::PAYEES, payees);
+}
+
+/// Read-only view of the escrow balances of an account, returned as a map with the fields
+/// `pending`, still within a dispute window, and `available`, ready to withdraw, each a map of
+/// formatted erc20 contract hash to amount. Payments whose dispute window ended count as available
+/// once released, or when the next `withdraw` releases them, at most `MAX_SETTLE_BATCH` at a time,
+/// so `available` is what a `withdraw` pays.
+/// # Parameters
+///
+/// * `account` - An Accounthash that holds the account address of the publisher or a payee
+///
+#[no_mangle]
+pub fn get_escrow_balance()
+{
+    let account: AccountHash = runtime::get_named_arg(constants::ACCOUNT);
+
+    let (pending, available) = Escrow::new().get_balances_at(account, runtime::get_blocktime().into());
+
+    let format_balances = |balances: BTreeMap<ContractHash, U256>| -> BTreeMap<String, String> {
+        balances
+            .into_iter()
+            .map(|(erc20_contract_hash, amount)| (erc20_contract_hash.to_formatted_string(), amount.to_string()))
+            .collect()
+    };
+
+    let mut balance: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    balance.insert(String::from("pending"), format_balances(pending));
+    balance.insert(String::from("available"), format_balances(available));
+
+    runtime::ret(CLValue::from_t(balance).unwrap_or_revert());
+}
+
+/// Read-only view of the erc20 tokens held in escrow by the contract package, returned as a map
+/// of formatted contract hash to the amount held, pending and available balances together.
+#[no_mangle]
+pub fn get_escrow_held()
+{
+    let held: BTreeMap<String, String> = Escrow::new().get_held()
+        .into_iter()
+        .map(|(erc20_contract_hash, amount)| (erc20_contract_hash.to_formatted_string(), amount.to_string()))
+        .collect();
+
+    runtime::ret(CLValue::from_t(held).unwrap_or_revert());
+}
+
+/// Read-only view of a payment held in escrow, returned as a map of field name to value.
+/// Fields: `subscription_id`, `erc20_contract_hash`, `token_amount`, `release_timestamp`,
+/// the end of the dispute window in milliseconds, and `state`, a `PaymentState` as a number.
+/// # Parameters
+///
+/// * `payment_id` - An u64 that holds the id of the payment
+///
+#[no_mangle]
+pub fn get_escrow_payment()
+{
+    let payment_id: u64 = runtime::get_named_arg(constants::PAYMENT_ID);
+
+    let escrow = Escrow::new();
+    let subscription_id = escrow.get_subscription_id(payment_id).unwrap_or_revert_with(ContractError::UnknownPayment);
+    let token_amount = escrow.get_shares(payment_id).iter().fold(U256::zero(), |total, (_, amount)| total + *amount);
+
+    let mut payment: BTreeMap<String, String> = BTreeMap::new();
+    payment.insert(constants::SUBSCRIPTION_ID.to_string(), subscription_id.to_string());
+    payment.insert(constants::ERC20_CONTRACT_HASH.to_string(), escrow.get_token(payment_id).to_formatted_string());
+    payment.insert(constants::TOKEN_AMOUNT.to_string(), token_amount.to_string());
+    payment.insert(String::from("release_timestamp"), escrow.get_release_timestamp(payment_id).to_string());
+    payment.insert(String::from("state"), (escrow.get_state(payment_id) as u8).to_string());
+
+    runtime::ret(CLValue::from_t(payment).unwrap_or_revert());
+}
+
+/// Refund a payment held in escrow to the subscriber, within its dispute window.
+/// Only the publisher (`to`) can refund.
+/// # Parameters
+///
+/// * `payment_id` - An u64 that holds the id of the payment
+///
+#[no_mangle]
+pub fn refund_payment()
+{
+    let payment_id: u64 = runtime::get_named_arg(constants::PAYMENT_ID);
+
+    require_publisher();
+
+    reverse_payment(payment_id, PaymentState::Refunded);
+}
+
+/// Take a payment held in escrow back, within its dispute window.
+/// Only the subscriber who made the payment can charge it back.
+/// # Parameters
+///
+/// * `payment_id` - An u64 that holds the id of the payment
+///
+#[no_mangle]
+pub fn chargeback_payment()
+{
+    let payment_id: u64 = runtime::get_named_arg(constants::PAYMENT_ID);
+
+    let escrow = Escrow::new();
+    let subscription_id = escrow.get_subscription_id(payment_id).unwrap_or_revert_with(ContractError::UnknownPayment);
+    let from: AccountHash = Hashes::new().get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    if runtime::get_caller() != from
+    {
+        runtime::revert(ApiError::User(ContractError::NotSubscriber as u16));
+    }
+
+    reverse_payment(payment_id, PaymentState::ChargedBack);
+}
+
+/// Give a payment held in escrow back to its subscriber, leaving it in `state`.
+fn reverse_payment(payment_id: u64, state: PaymentState)
+{
+    let escrow = Escrow::new();
+    let subscription_id = escrow.get_subscription_id(payment_id).unwrap_or_revert_with(ContractError::UnknownPayment);
+    let from: AccountHash = Hashes::new().get_account(subscription_id).unwrap_or_revert_with(ContractError::UnknownSubscription);
+
+    let token_amount = escrow.reverse(payment_id, runtime::get_blocktime().into(), state);
+    Caps::new().sub_payment(subscription_id, token_amount);
+    transfer(escrow.get_token(payment_id), from, token_amount);
+}
+
+/// Withdraw the caller's whole available escrow balance in an erc20 token, once the dispute
+/// windows of its payments ended. The publisher and every payee withdraw their own share. Each
+/// call releases the next payments whose dispute window ended, at most `MAX_SETTLE_BATCH`.
+/// # Parameters
+///
+/// * `erc20_contract_hash` - A hash Key that holds the contracthash of the erc20 contract
+///
+#[no_mangle]
+pub fn withdraw()
+{
+    let erc20_contract_hash: Key = runtime::get_named_arg(constants::ERC20_CONTRACT_HASH);
+    let erc20_contract_hash = get_erc20_contract_hash(erc20_contract_hash);
+    let caller = runtime::get_caller();
+
+    let escrow = Escrow::new();
+    escrow.settle(runtime::get_blocktime().into());
+
+    let amount = escrow.withdraw(caller, erc20_contract_hash);
+    transfer(erc20_contract_hash, caller, amount);
+}
+
+/// Read-only view of how many accounts have registered a subscription, returned as a u64.
+#[no_mangle]
+pub fn get_subscriber_count()
+{
+    let count: u64 = Subscribers::new().count();
+
+    runtime::ret(CLValue::from_t(count).unwrap_or_revert());
+}
+
+/// Read-only view of how many subscriptions have been registered, returned as a u64.
+/// Subscription ids run from 0 to this count minus one.
+#[no_mangle]
+pub fn get_subscription_count()
+{
+    let count: u64 = Hashes::new().count();
+
+    runtime::ret(CLValue::from_t(count).unwrap_or_revert());
+}
+
+/// Page through the subscriber index so publishers can audit their customers
+/// and keepers can discover due subscriptions, returned as a list of AccountHash.
+/// `offset` and `limit` address positions in the index, so filtered out accounts
+/// still count towards the page and a page can hold fewer than `limit` accounts.
+/// # Parameters
+///
+/// * `offset` - An u64 that holds the first index position to read
+///
+/// * `limit` - An u64 that holds how many index positions to read, at most 100
+///
+/// * `status` - An optional u8 `SubscriptionStatus`, only accounts with a subscription in this status are returned
+///
+#[no_mangle]
+pub fn list_subscribers()
+{
+    let offset: u64 = runtime::get_named_arg(constants::OFFSET);
+    let limit: u64 = runtime::get_named_arg(constants::LIMIT);
+    let status: Option<u8> = utils::get_optional_named_arg(constants::STATUS);
+
+    let subscribers = Subscribers::new();
+    let hashes = Hashes::new();
+    let blocktime: u64 = runtime::get_blocktime().into();
+
+    let mut accounts: Vec<AccountHash> = Vec::new();
+
+    for index in paging::page(offset, limit, subscribers.count()) {
+        let account: AccountHash = subscribers.get(index).unwrap_or_revert_with(ContractError::DictionaryError);
+
+        // only read the account's subscriptions when filtering
+        let subscription_ids: Vec<u64> = if status.is_some() { subscribers.get_subscriptions(account) } else { Vec::new() };
+        let next_valid_timestamps = subscription_ids.into_iter().map(|subscription_id| get_registered_timestamp(&hashes, subscription_id));
+
+        if paging::matches_status(status, next_valid_timestamps, blocktime) {
+            accounts.push(account);
+        }
+    }
+
+    runtime::ret(CLValue::from_t(accounts).unwrap_or_revert());
+}
+
+/// Page through every subscription by id so keepers can discover the ones that are due,
+/// returned as a list of u64 subscription ids.
+/// `offset` and `limit` address subscription ids, so filtered out subscriptions
+/// still count towards the page and a page can hold fewer than `limit` ids.
+/// # Parameters
+///
+/// * `offset` - An u64 that holds the first subscription id to read
+///
+/// * `limit` - An u64 that holds how many subscription ids to read, at most 100
+///
+/// * `status` - An optional u8 `SubscriptionStatus`, only subscriptions with this status are returned
+///
+#[no_mangle]
+pub fn list_subscriptions()
+{
+    let offset: u64 = runtime::get_named_arg(constants::OFFSET);
+    let limit: u64 = runtime::get_named_arg(constants::LIMIT);
+    let status: Option<u8> = utils::get_optional_named_arg(constants::STATUS);
+
+    let hashes = Hashes::new();
+    let blocktime: u64 = runtime::get_blocktime().into();
+
+    let subscription_ids: Vec<u64> = paging::page(offset, limit, hashes.count())
+        .filter(|subscription_id| paging::matches_status(status, Some(get_registered_timestamp(&hashes, *subscription_id)), blocktime))
+        .collect();
+
+    runtime::ret(CLValue::from_t(subscription_ids).unwrap_or_revert());
+}
+
+/// Returns the list of the entry points in the contract with added group security.
+pub fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("is_subscription_active"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_HASH, String::cl_type()),
+            Parameter::new(constants::GRACE_PERIOD_SECONDS, u64::cl_type()),
+            Parameter::new(constants::BENEFICIARY, AccountHash::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("is_subscription_ready"),
+        vec![
+            Parameter::new(constants::SIGNATURE, String::cl_type()),
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("cancel_subscription"),
+        vec![
+            Parameter::new(constants::SIGNATURE, String::cl_type()),
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
     entry_points.add_entry_point(EntryPoint::new(
         String::from("create_subscription_hash"),
         vec![
             Parameter::new(constants::PUBLIC, PublicKey::cl_type()),
             Parameter::new(constants::FROM, AccountHash::cl_type()),
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+            Parameter::new(constants::COUPON_CODE, String::cl_type()),
+            Parameter::new(constants::MAX_AMOUNT_PER_PERIOD, U256::cl_type()),
+            Parameter::new(constants::LIFETIME_CAP, U256::cl_type()),
+            Parameter::new(constants::BENEFICIARY, AccountHash::cl_type()),
+            Parameter::new(constants::METERED, bool::cl_type()),
         ],
-        CLType::Unit,
+        CLType::U64,
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
         String::from("get_subscription_hash"),
         vec![
-            Parameter::new(constants::PUBLIC, PublicKey::cl_type()),
-            Parameter::new(constants::FROM, AccountHash::cl_type()),
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
         ],
         CLType::String,
         EntryPointAccess::Public,
@@ -490,12 +1627,294 @@ pub fn get_entry_points() -> EntryPoints {
         String::from("execute_subscription"),
         vec![
             Parameter::new(constants::SIGNATURE,String::cl_type()),
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_subscription"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_next_payment_time"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_subscription_status"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        CLType::U8,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_active_subscription"),
+        vec![
+            Parameter::new(constants::ACCOUNT, AccountHash::cl_type()),
+            Parameter::new(constants::GRACE_PERIOD_SECONDS, u64::cl_type()),
+        ],
+        Option::<u64>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_account_subscriptions"),
+        vec![
             Parameter::new(constants::FROM, AccountHash::cl_type()),
         ],
+        Vec::<u64>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_plan"),
+        vec![],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_token_prices"),
+        vec![],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("add_token"),
+        vec![
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+            Parameter::new(constants::TOKEN_AMOUNT, U256::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_pending_prices"),
+        vec![],
+        BTreeMap::<String, (U256, u64)>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("schedule_price_change"),
+        vec![
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+            Parameter::new(constants::TOKEN_AMOUNT, U256::cl_type()),
+            Parameter::new(constants::EFFECTIVE_AT, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_unit_prices"),
+        vec![],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("set_unit_price"),
+        vec![
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+            Parameter::new(constants::UNIT_PRICE, U256::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("set_usage_oracle"),
+        vec![
+            Parameter::new(constants::ORACLE, AccountHash::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("report_usage"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+            Parameter::new(constants::USAGE, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("add_member"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+            Parameter::new(constants::MEMBER, AccountHash::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("remove_member"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+            Parameter::new(constants::MEMBER, AccountHash::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_members"),
+        vec![
+            Parameter::new(constants::SUBSCRIPTION_ID, u64::cl_type()),
+        ],
+        Vec::<AccountHash>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("add_coupon"),
+        vec![
+            Parameter::new(constants::COUPON_CODE, String::cl_type()),
+            Parameter::new(constants::DISCOUNT_TYPE, u8::cl_type()),
+            Parameter::new(constants::DISCOUNT, U256::cl_type()),
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+            Parameter::new(constants::PERIODS, u64::cl_type()),
+            Parameter::new(constants::MAX_REDEMPTIONS, u64::cl_type()),
+            Parameter::new(constants::EXPIRES_AT, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("expire_coupon"),
+        vec![
+            Parameter::new(constants::COUPON_CODE, String::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_payees"),
+        vec![],
+        BTreeMap::<String, u32>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("set_payee"),
+        vec![
+            Parameter::new(constants::RECIPIENT, AccountHash::cl_type()),
+            Parameter::new(constants::BASIS_POINTS, u32::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_escrow_balance"),
+        vec![
+            Parameter::new(constants::ACCOUNT, AccountHash::cl_type()),
+        ],
+        BTreeMap::<String, BTreeMap<String, String>>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_escrow_held"),
+        vec![],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_escrow_payment"),
+        vec![
+            Parameter::new(constants::PAYMENT_ID, u64::cl_type()),
+        ],
+        BTreeMap::<String, String>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("refund_payment"),
+        vec![
+            Parameter::new(constants::PAYMENT_ID, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("chargeback_payment"),
+        vec![
+            Parameter::new(constants::PAYMENT_ID, u64::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("withdraw"),
+        vec![
+            Parameter::new(constants::ERC20_CONTRACT_HASH, Key::cl_type()),
+        ],
         CLType::Unit,
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_subscriber_count"),
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("get_subscription_count"),
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("list_subscribers"),
+        vec![
+            Parameter::new(constants::OFFSET, u64::cl_type()),
+            Parameter::new(constants::LIMIT, u64::cl_type()),
+            Parameter::new(constants::STATUS, u8::cl_type()),
+        ],
+        Vec::<AccountHash>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("list_subscriptions"),
+        vec![
+            Parameter::new(constants::OFFSET, u64::cl_type()),
+            Parameter::new(constants::LIMIT, u64::cl_type()),
+            Parameter::new(constants::STATUS, u8::cl_type()),
+        ],
+        Vec::<u64>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
     entry_points
 }
 
@@ -511,6 +1930,17 @@ pub fn get_entry_points() -> EntryPoints {
 /// * `period_seconds` - An u64 that holds the interval in seconds between payments
 ///
 /// * `erc20_contract_hash` - A String slice that holds the contracthash of erc20 contract
+///
+/// The optional `dispute_window_seconds` runtime argument turns on escrow: payments are held by
+/// the contract package for that many seconds, when they can be refunded or charged back, before
+/// the publisher and payees can withdraw them. Left out or 0 pays them directly. A window whose
+/// milliseconds do not fit in a u64 reverts with `InvalidDisputeWindow`.
+///
+/// The optional `interval_type` and `billing_day` runtime arguments bill on calendar periods in
+/// UTC instead of every `period_seconds`, see `calendar::IntervalType`. An unknown interval type
+/// or a billing day out of range for it reverts with `InvalidInterval`.
+///
+/// The optional `seat_based` runtime argument charges subscriptions for every member, see `seats`.
 pub fn install_or_upgrade_contract(
     name: String,
     to: AccountHash,
@@ -522,7 +1952,7 @@ pub fn install_or_upgrade_contract(
     let contract_package_hash: ContractPackageHash =
          match runtime::get_key(&format!("{}-package", name)) {
             Some(contract_package_hash) => {
-                contract_package_hash.into_hash().unwrap_or_revert().into()
+                contract_package_hash.into_hash().unwrap_or_revert_with(ContractError::InvalidKey).into()
             }
             None => {
                 let (contract_package_hash, access_token) =
@@ -547,19 +1977,133 @@ pub fn install_or_upgrade_contract(
                 named_keys.insert(constants::PERIOD_SECONDS.to_string(), storage::new_uref(period_seconds).into());
 
                 // Coerce Contract Hash
-                let _erc20_contract_hash: ContractHash = ContractHash::from(erc20_contract_hash.into_hash().unwrap_or_default());
+                let _erc20_contract_hash: ContractHash = get_erc20_contract_hash(erc20_contract_hash);
                 named_keys.insert(constants::ERC20_CONTRACT_HASH.to_string(), storage::new_uref(_erc20_contract_hash).into());
 
+                // The token set at install is the first accepted token.
+                let mut token_prices: BTreeMap<ContractHash, U256> = BTreeMap::new();
+                token_prices.insert(_erc20_contract_hash, token_amount);
+                named_keys.insert(tokens::TOKEN_PRICES.to_string(), storage::new_uref(token_prices).into());
+                named_keys.insert(tokens::PENDING_PRICES.to_string(), storage::new_uref(BTreeMap::<ContractHash, (U256, u64)>::new()).into());
+
+                // Payments are due every period_seconds unless a calendar interval is set, it cannot change once subscriptions are signed.
+                let billing_interval = BillingInterval {
+                    interval_type: IntervalType::from_u8(utils::get_optional_named_arg(calendar::INTERVAL_TYPE).unwrap_or(0)).unwrap_or_revert_with(ContractError::InvalidInterval),
+                    period_seconds,
+                    billing_day: utils::get_optional_named_arg(calendar::BILLING_DAY).unwrap_or(0),
+                };
+                if !billing_interval.is_valid() {
+                    runtime::revert(ApiError::User(ContractError::InvalidInterval as u16));
+                }
+                named_keys.insert(calendar::INTERVAL_TYPE.to_string(), storage::new_uref(billing_interval.interval_type as u8).into());
+                named_keys.insert(calendar::BILLING_DAY.to_string(), storage::new_uref(billing_interval.billing_day).into());
+
+                // Escrow is off unless a dispute window is set, it cannot change once payments are held.
+                let dispute_window_seconds: u64 = utils::get_optional_named_arg(escrow::DISPUTE_WINDOW_SECONDS).unwrap_or(0);
+                if dispute_window_seconds.checked_mul(1000).is_none() {
+                    runtime::revert(ApiError::User(ContractError::InvalidDisputeWindow as u16));
+                }
+                named_keys.insert(escrow::DISPUTE_WINDOW_SECONDS.to_string(), storage::new_uref(dispute_window_seconds).into());
+                named_keys.insert(escrow::ESCROW_HELD.to_string(), storage::new_uref(BTreeMap::<ContractHash, U256>::new()).into());
+                named_keys.insert(escrow::ESCROW_PAYMENT_COUNT.to_string(), storage::new_uref(0u64).into());
+                named_keys.insert(escrow::ESCROW_SETTLED_COUNT.to_string(), storage::new_uref(0u64).into());
+
+                // Usage is not charged until unit prices are set, and only the publisher reports it until an oracle is set.
+                named_keys.insert(metering::UNIT_PRICES.to_string(), storage::new_uref(BTreeMap::<ContractHash, U256>::new()).into());
+                named_keys.insert(metering::USAGE_ORACLE.to_string(), storage::new_uref(to).into());
+                for dict in &[metering::USAGES_DICT, metering::METERED_DICT] {
+                    let metering_dict = storage::new_dictionary(dict).unwrap_or_revert_with(ContractError::DictionaryError);
+                    named_keys.insert(dict.to_string(), metering_dict.into());
+                }
+
+                // Subscriptions pay for a single account unless the plan is seat-based, it cannot change once subscriptions are signed.
+                let seat_based: bool = utils::get_optional_named_arg(seats::SEAT_BASED).unwrap_or(false);
+                named_keys.insert(seats::SEAT_BASED.to_string(), storage::new_uref(seat_based).into());
+                for dict in &[seats::MEMBERS_DICT, seats::SEAT_CHARGES_DICT, seats::SEAT_CREDITS_DICT] {
+                    let seats_dict = storage::new_dictionary(dict).unwrap_or_revert_with(ContractError::DictionaryError);
+                    named_keys.insert(dict.to_string(), seats_dict.into());
+                }
+
+                // Add empty dictionaries for the spending caps subscribers sign and what they paid.
+                for dict in &[caps::MAX_AMOUNTS_PER_PERIOD_DICT, caps::LIFETIME_CAPS_DICT, caps::AMOUNTS_PAID_DICT] {
+                    let caps_dict = storage::new_dictionary(dict).unwrap_or_revert_with(ContractError::DictionaryError);
+                    named_keys.insert(dict.to_string(), caps_dict.into());
+                }
+
+                // The publisher gets whole payments until payees are set.
+                named_keys.insert(payouts::PAYEES.to_string(), storage::new_uref(BTreeMap::<AccountHash, u32>::new()).into());
+
                 // Add empty dictionary for hashes.
-                let hashes_dict = storage::new_dictionary(hashes::HASHES_DICT).unwrap_or_revert();
+                let hashes_dict = storage::new_dictionary(hashes::HASHES_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
                 named_keys.insert(hashes::HASHES_DICT.to_string(), hashes_dict.into());
 
-                let pubkeys_dict = storage::new_dictionary(hashes::PUBKEYS_DICT).unwrap_or_revert();
+                let pubkeys_dict = storage::new_dictionary(hashes::PUBKEYS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
                 named_keys.insert(hashes::PUBKEYS_DICT.to_string(), pubkeys_dict.into());
 
+                let accounts_dict = storage::new_dictionary(hashes::ACCOUNTS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::ACCOUNTS_DICT.to_string(), accounts_dict.into());
+
+                let timestamps_dict = storage::new_dictionary(hashes::NEXT_VALID_TIMESTAMPS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::NEXT_VALID_TIMESTAMPS_DICT.to_string(), timestamps_dict.into());
+
+                let subscription_ids_dict = storage::new_dictionary(hashes::SUBSCRIPTION_IDS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::SUBSCRIPTION_IDS_DICT.to_string(), subscription_ids_dict.into());
+
+                let subscription_tokens_dict = storage::new_dictionary(hashes::SUBSCRIPTION_TOKENS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::SUBSCRIPTION_TOKENS_DICT.to_string(), subscription_tokens_dict.into());
+
+                let signed_prices_dict = storage::new_dictionary(hashes::SIGNED_PRICES_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::SIGNED_PRICES_DICT.to_string(), signed_prices_dict.into());
+
+                let beneficiaries_dict = storage::new_dictionary(hashes::BENEFICIARIES_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(hashes::BENEFICIARIES_DICT.to_string(), beneficiaries_dict.into());
+                named_keys.insert(hashes::SUBSCRIPTION_COUNT.to_string(), storage::new_uref(0u64).into());
+
+                // Add empty coupon dictionaries, and the discounts of subscriptions that redeemed one.
+                for dict in &[
+                    coupons::COUPON_TYPES_DICT,
+                    coupons::COUPON_DISCOUNTS_DICT,
+                    coupons::COUPON_TOKENS_DICT,
+                    coupons::COUPON_PERIODS_DICT,
+                    coupons::COUPON_MAX_REDEMPTIONS_DICT,
+                    coupons::COUPON_EXPIRATIONS_DICT,
+                    coupons::COUPON_REDEMPTIONS_DICT,
+                    hashes::SUBSCRIPTION_COUPONS_DICT,
+                    hashes::DISCOUNTED_AMOUNTS_DICT,
+                    hashes::DISCOUNTED_PERIODS_DICT,
+                ] {
+                    let coupon_dict = storage::new_dictionary(dict).unwrap_or_revert_with(ContractError::DictionaryError);
+                    named_keys.insert(dict.to_string(), coupon_dict.into());
+                }
+
+                // Add empty escrow dictionaries.
+                for dict in &[
+                    escrow::ESCROW_SUBSCRIPTIONS_DICT,
+                    escrow::ESCROW_TOKENS_DICT,
+                    escrow::ESCROW_SHARES_DICT,
+                    escrow::ESCROW_RELEASE_TIMESTAMPS_DICT,
+                    escrow::ESCROW_STATES_DICT,
+                    escrow::ESCROW_PENDING_DICT,
+                    escrow::ESCROW_AVAILABLE_DICT,
+                ] {
+                    let escrow_dict = storage::new_dictionary(dict).unwrap_or_revert_with(ContractError::DictionaryError);
+                    named_keys.insert(dict.to_string(), escrow_dict.into());
+                }
+
+                // Add empty subscriber index.
+                let subscribers_dict = storage::new_dictionary(subscribers::SUBSCRIBERS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(subscribers::SUBSCRIBERS_DICT.to_string(), subscribers_dict.into());
+                named_keys.insert(subscribers::SUBSCRIBER_COUNT.to_string(), storage::new_uref(0u64).into());
+
+                let account_subscriptions_dict = storage::new_dictionary(subscribers::ACCOUNT_SUBSCRIPTIONS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(subscribers::ACCOUNT_SUBSCRIPTIONS_DICT.to_string(), account_subscriptions_dict.into());
+
+                let account_access_dict = storage::new_dictionary(subscribers::ACCOUNT_ACCESS_DICT).unwrap_or_revert_with(ContractError::DictionaryError);
+                named_keys.insert(subscribers::ACCOUNT_ACCESS_DICT.to_string(), account_access_dict.into());
+
                 // Store package hash.
                 named_keys.insert(
-                    "package_hash".to_string(),
+                    constants::PACKAGE_HASH.to_string(),
                     storage::new_uref(contract_package_hash).into(),
                 ); 
 
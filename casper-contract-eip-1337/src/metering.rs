@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use contract::{
+    contract_api::{storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{
+    account::AccountHash,
+    ContractHash, URef, U256,
+};
+
+use crate::errors::ContractError;
+use crate::utils;
+
+pub const UNIT_PRICES: &str = "unit_prices";
+pub const USAGE_ORACLE: &str = "usage_oracle";
+pub const USAGES_DICT: &str = "usages";
+pub const METERED_DICT: &str = "metered";
+
+/// The price of a unit of usage in each erc20 token, stored in the `unit_prices` named key.
+pub fn get_unit_prices() -> BTreeMap<ContractHash, U256> {
+    utils::get_key(UNIT_PRICES).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// The price of a unit of usage in the token, 0 if the publisher has not set one.
+pub fn get_unit_price(erc20_contract_hash: ContractHash) -> U256 {
+    get_unit_prices().get(&erc20_contract_hash).copied().unwrap_or_default()
+}
+
+/// Set the price of a unit of usage in the token, 0 removes it.
+/// Metered subscriptions signed a max amount per period, a payment above it reverts.
+pub fn set_unit_price(erc20_contract_hash: ContractHash, unit_price: U256) {
+    let mut unit_prices = get_unit_prices();
+
+    if unit_price.is_zero() {
+        unit_prices.remove(&erc20_contract_hash);
+    } else {
+        unit_prices.insert(erc20_contract_hash, unit_price);
+    }
+    utils::set_key(UNIT_PRICES, unit_prices);
+}
+
+/// The account reporting usage besides the publisher, the publisher itself until one is set.
+pub fn get_usage_oracle() -> AccountHash {
+    utils::get_key(USAGE_ORACLE).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// Metered subscriptions, the ones whose subscriber signed `metered` within a max amount per
+/// period (see `caps`), keyed by subscription id (decimal string): whether a subscription is
+/// metered and the units reported since the last payment.
+pub struct Metering {
+    usages_dict_uref: URef,
+    metered_dict_uref: URef,
+}
+
+impl Metering {
+    pub fn new() -> Metering {
+        Metering {
+            usages_dict_uref: utils::get_dictionary_uref(USAGES_DICT),
+            metered_dict_uref: utils::get_dictionary_uref(METERED_DICT),
+        }
+    }
+
+    pub fn set_metered(&self, subscription_id: u64) {
+        storage::dictionary_put(self.metered_dict_uref, &subscription_id.to_string(), true);
+    }
+
+    /// Whether the usage of a subscription is charged, `false` for a fixed price one.
+    pub fn is_metered(&self, subscription_id: u64) -> bool {
+        storage::dictionary_get(self.metered_dict_uref, &subscription_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default()
+    }
+
+    /// Units of usage reported since the last payment.
+    pub fn get_usage(&self, subscription_id: u64) -> u64 {
+        storage::dictionary_get(self.usages_dict_uref, &subscription_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default()
+    }
+
+    /// Add reported units to the usage of the period, saturating rather than wrapping around.
+    pub fn add_usage(&self, subscription_id: u64, usage: u64) {
+        let total = self.get_usage(subscription_id).saturating_add(usage);
+        storage::dictionary_put(self.usages_dict_uref, &subscription_id.to_string(), total);
+    }
+
+    /// Start the usage of the next period, once a payment billed it.
+    pub fn reset_usage(&self, subscription_id: u64) {
+        storage::dictionary_put(self.usages_dict_uref, &subscription_id.to_string(), 0u64);
+    }
+}
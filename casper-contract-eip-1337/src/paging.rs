@@ -0,0 +1,30 @@
+//! Pages of the subscriber index and of the subscription ids, and the status filter applied to
+//! them, for `list_subscribers` and `list_subscriptions`.
+
+use core::ops::Range;
+
+use crate::status::SubscriptionStatus;
+
+/// Most index positions `list_subscribers` and `list_subscriptions` will scan in one call.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// The positions of a page of `limit` positions from `offset`, in a list of `len` positions.
+/// At most `MAX_PAGE_SIZE` of them, none past the end of the list, and `offset + limit` saturates
+/// rather than overflowing.
+pub fn page(offset: u64, limit: u64, len: u64) -> Range<u64> {
+    let end: u64 = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(len);
+
+    offset.min(end)..end
+}
+
+/// Whether an entry of a page passes the optional u8 `SubscriptionStatus` filter: without one,
+/// always, with one, when any of its subscriptions, given by their next valid timestamps (`None`
+/// for an unknown subscription), has that status at `blocktime`, without grace period.
+pub fn matches_status<I: IntoIterator<Item = Option<u64>>>(status: Option<u8>, next_valid_timestamps: I, blocktime: u64) -> bool {
+    match status {
+        Some(status) => next_valid_timestamps
+            .into_iter()
+            .any(|next_valid_timestamp| SubscriptionStatus::from_timestamps(next_valid_timestamp, blocktime, 0) as u8 == status),
+        None => true,
+    }
+}
@@ -0,0 +1,42 @@
+//! How a payment is split between the publisher and the payees of the plan.
+
+use std::collections::BTreeMap;
+
+use types::{account::AccountHash, U256};
+
+/// Named key holding the payees of the plan, each with its share of a payment in basis points.
+pub const PAYEES: &str = "payees";
+
+/// Basis points of a whole payment.
+pub const TOTAL_BASIS_POINTS: u32 = 10_000;
+
+/// Split a payment of `token_amount` between the payees and the publisher `to`.
+///
+/// Every payee gets its basis points of the payment, rounded down. The publisher gets the rest,
+/// rounding remainders included, so the amounts always add up to `token_amount`. The publisher
+/// comes first, then the payees in account hash order.
+///
+/// The basis points of the payees must add up to at most `TOTAL_BASIS_POINTS`.
+pub fn split(token_amount: U256, to: AccountHash, payees: &BTreeMap<AccountHash, u32>) -> Vec<(AccountHash, U256)> {
+    let total = U256::from(TOTAL_BASIS_POINTS);
+    // each share is token_amount * basis_points / total, computed from these so it never multiplies
+    // the whole amount
+    let (quotient, remainder) = (token_amount / total, token_amount % total);
+
+    let mut payments: Vec<(AccountHash, U256)> = vec![(to, token_amount)];
+
+    for (payee, basis_points) in payees {
+        let basis_points = U256::from(*basis_points);
+        let amount = quotient * basis_points + remainder * basis_points / total;
+
+        payments[0].1 -= amount;
+        payments.push((*payee, amount));
+    }
+
+    payments
+}
+
+/// Whether the payees can be paid from a single payment.
+pub fn is_valid(payees: &BTreeMap<AccountHash, u32>) -> bool {
+    payees.values().map(|basis_points| u64::from(*basis_points)).sum::<u64>() <= u64::from(TOTAL_BASIS_POINTS)
+}
@@ -0,0 +1,75 @@
+//! Coupon discounts on the price of a period, metered charges and seats.
+
+use types::U256;
+
+use crate::payouts::TOTAL_BASIS_POINTS;
+
+/// Enum for DiscountType, how a coupon takes its `discount` off the price of a period.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscountType {
+    /// 0 for a share of the price in basis points, out of 10000
+    Percentage = 0,
+    /// 1 for a fixed amount of the coupon's erc20 token
+    FixedAmount = 1,
+}
+
+impl DiscountType {
+    /// Read a discount type passed as a u8, `None` for an unknown value.
+    pub fn from_u8(discount_type: u8) -> Option<DiscountType> {
+        match discount_type {
+            0 => Some(DiscountType::Percentage),
+            1 => Some(DiscountType::FixedAmount),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a coupon can take `discount` off a price: at most 100% for a percentage.
+pub fn is_valid_discount(discount_type: DiscountType, discount: U256) -> bool {
+    match discount_type {
+        DiscountType::Percentage => discount <= U256::from(TOTAL_BASIS_POINTS),
+        DiscountType::FixedAmount => true,
+    }
+}
+
+/// The price of a period with the discount taken off, never below 0.
+/// A percentage discount is rounded down, so the publisher keeps the rounding remainder.
+pub fn discounted_price(price: U256, discount_type: DiscountType, discount: U256) -> U256 {
+    match discount_type {
+        DiscountType::Percentage => {
+            let total = U256::from(TOTAL_BASIS_POINTS);
+            // price * discount / total, dividing first: the product could overflow, the quotient and
+            // remainder times at most `total` cannot
+            let off = price / total * discount + price % total * discount / total;
+            price.saturating_sub(off)
+        }
+        DiscountType::FixedAmount => price.saturating_sub(discount),
+    }
+}
+
+/// The charge of a metered period: `base` plus `usage` units at `unit_price`. Never overflows, a
+/// charge past `U256::MAX` saturates, well past any max amount per period a subscriber signs.
+pub fn metered_price(base: U256, usage: u64, unit_price: U256) -> U256 {
+    base.saturating_add(unit_price.saturating_mul(U256::from(usage)))
+}
+
+/// The share of `price` for `millis` of a period lasting `period_millis`, rounded down, 0 for a
+/// period of 0. `millis` past the end of the period count as the whole period.
+pub fn prorated_price(price: U256, millis: u64, period_millis: u64) -> U256 {
+    if period_millis == 0 {
+        return U256::zero();
+    }
+
+    let millis = U256::from(millis.min(period_millis));
+    let period = U256::from(period_millis);
+    // price * millis / period, dividing first as in `discounted_price`
+    price / period * millis + price % period * millis / period
+}
+
+/// The charge of a period of a seat-based subscription: `seats` at `seat_price`, plus the
+/// prorated `charges` of the seats removed during the period, less the prorated `credits` of
+/// the seats added during it. Never below 0.
+pub fn seats_price(seat_price: U256, seats: u64, charges: U256, credits: U256) -> U256 {
+    seat_price.saturating_mul(U256::from(seats)).saturating_add(charges).saturating_sub(credits)
+}
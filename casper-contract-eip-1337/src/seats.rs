@@ -0,0 +1,109 @@
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{account::AccountHash, ApiError, URef, U256};
+
+use crate::errors::ContractError;
+use crate::utils;
+
+pub const SEAT_BASED: &str = "seat_based";
+pub const MEMBERS_DICT: &str = "members";
+pub const SEAT_CHARGES_DICT: &str = "seat_charges";
+pub const SEAT_CREDITS_DICT: &str = "seat_credits";
+
+/// Whether the plan charges subscriptions per seat, set at install.
+pub fn is_seat_based() -> bool {
+    utils::get_key(SEAT_BASED).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// Members of the subscriptions of a seat-based plan, each holding a seat the subscriber pays
+/// for, and the prorated amounts of the seats added or removed since the last payment, keyed by
+/// subscription id (decimal string).
+pub struct Seats {
+    members_dict_uref: URef,
+    charges_dict_uref: URef,
+    credits_dict_uref: URef,
+}
+
+impl Seats {
+    pub fn new() -> Seats {
+        Seats {
+            members_dict_uref: utils::get_dictionary_uref(MEMBERS_DICT),
+            charges_dict_uref: utils::get_dictionary_uref(SEAT_CHARGES_DICT),
+            credits_dict_uref: utils::get_dictionary_uref(SEAT_CREDITS_DICT),
+        }
+    }
+
+    /// The accounts holding a seat, in the order they were added.
+    pub fn get_members(&self, subscription_id: u64) -> Vec<AccountHash> {
+        storage::dictionary_get(self.members_dict_uref, &subscription_id.to_string())
+            .unwrap_or_revert_with(ContractError::DictionaryError)
+            .unwrap_or_default()
+    }
+
+    /// The number of seats paid for, one per member.
+    pub fn get_seats(&self, subscription_id: u64) -> u64 {
+        self.get_members(subscription_id).len() as u64
+    }
+
+    pub fn is_member(&self, subscription_id: u64, account: AccountHash) -> bool {
+        self.get_members(subscription_id).contains(&account)
+    }
+
+    /// Give an account a seat, reverting with `MemberExists` if it holds one.
+    pub fn add_member(&self, subscription_id: u64, member: AccountHash) {
+        let mut members = self.get_members(subscription_id);
+
+        if members.contains(&member) {
+            runtime::revert(ApiError::User(ContractError::MemberExists as u16));
+        }
+
+        members.push(member);
+        storage::dictionary_put(self.members_dict_uref, &subscription_id.to_string(), members);
+    }
+
+    /// Take the seat of an account, reverting with `UnknownMember` if it holds none and with
+    /// `LastSeat` for the last one.
+    pub fn remove_member(&self, subscription_id: u64, member: AccountHash) {
+        let mut members = self.get_members(subscription_id);
+
+        let index = members.iter().position(|account| *account == member).unwrap_or_revert_with(ContractError::UnknownMember);
+        if members.len() == 1 {
+            runtime::revert(ApiError::User(ContractError::LastSeat as u16));
+        }
+
+        members.remove(index);
+        storage::dictionary_put(self.members_dict_uref, &subscription_id.to_string(), members);
+    }
+
+    /// The prorated charges of the seats removed and credits of the seats added since the last
+    /// payment, see `pricing::seats_price`.
+    pub fn get_proration(&self, subscription_id: u64) -> (U256, U256) {
+        let key = &subscription_id.to_string();
+        let charges: Option<U256> = storage::dictionary_get(self.charges_dict_uref, key).unwrap_or_revert_with(ContractError::DictionaryError);
+        let credits: Option<U256> = storage::dictionary_get(self.credits_dict_uref, key).unwrap_or_revert_with(ContractError::DictionaryError);
+
+        (charges.unwrap_or_default(), credits.unwrap_or_default())
+    }
+
+    /// Charge the next payment for the part of the period a removed seat was held.
+    pub fn add_charge(&self, subscription_id: u64, amount: U256) {
+        let (charges, _credits) = self.get_proration(subscription_id);
+        storage::dictionary_put(self.charges_dict_uref, &subscription_id.to_string(), charges.saturating_add(amount));
+    }
+
+    /// Credit the next payment, which counts an added seat as a whole, for the part of the period
+    /// before it was added.
+    pub fn add_credit(&self, subscription_id: u64, amount: U256) {
+        let (_charges, credits) = self.get_proration(subscription_id);
+        storage::dictionary_put(self.credits_dict_uref, &subscription_id.to_string(), credits.saturating_add(amount));
+    }
+
+    /// Start the prorated amounts of the next period, once a payment billed them.
+    pub fn reset_proration(&self, subscription_id: u64) {
+        let key = &subscription_id.to_string();
+        storage::dictionary_put(self.charges_dict_uref, key, U256::zero());
+        storage::dictionary_put(self.credits_dict_uref, key, U256::zero());
+    }
+}
@@ -0,0 +1,70 @@
+/// next_valid_timestamp written by `cancel_subscription`.
+/// The subscription would become valid again Wednesday, November 16, 5138 9:46:39 AM,
+/// a timestamp that will never be reached during the brief window human existence.
+pub const CANCELLED_TIMESTAMP: u64 = 99999999999 * 1000;
+
+/// Enum for SubscriptionStatus, returned as a u8 by `get_subscription_status`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    /// 0 for no subscription registered
+    NotFound = 0,
+    /// 1 for paid up until the next valid timestamp (plus grace period)
+    Active = 1,
+    /// 2 for the next payment is due
+    Due = 2,
+    /// 3 for cancelled by the subscriber
+    Cancelled = 3,
+}
+
+impl SubscriptionStatus {
+    /// Derive the status of a subscription from its next valid timestamp.
+    /// # Parameters
+    ///
+    /// * `next_valid_timestamp` - The stored next valid timestamp in milliseconds, if the subscription exists
+    ///
+    /// * `blocktime` - The current blocktime in milliseconds
+    ///
+    /// * `grace_period_seconds` - Seconds a due subscription still counts as active
+    ///
+    pub fn from_timestamps(next_valid_timestamp: Option<u64>, blocktime: u64, grace_period_seconds: u64) -> SubscriptionStatus {
+        match next_valid_timestamp {
+            None => SubscriptionStatus::NotFound,
+            Some(CANCELLED_TIMESTAMP) => SubscriptionStatus::Cancelled,
+            Some(next_valid_timestamp) => {
+                if blocktime < next_valid_timestamp.saturating_add(grace_period_seconds.saturating_mul(1000)) {
+                    SubscriptionStatus::Active
+                } else {
+                    SubscriptionStatus::Due
+                }
+            }
+        }
+    }
+}
+
+/// Enum for PaymentState, the state of a payment held in escrow.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentState {
+    /// 0 for held until the dispute window ends
+    Held = 0,
+    /// 1 for released to the publisher and payees after the dispute window
+    Released = 1,
+    /// 2 for refunded to the subscriber by the publisher
+    Refunded = 2,
+    /// 3 for charged back by the subscriber within the dispute window
+    ChargedBack = 3,
+}
+
+impl PaymentState {
+    /// Read a state stored in the `escrow_states` dictionary, `None` for an unknown value.
+    pub fn from_u8(state: u8) -> Option<PaymentState> {
+        match state {
+            0 => Some(PaymentState::Held),
+            1 => Some(PaymentState::Released),
+            2 => Some(PaymentState::Refunded),
+            3 => Some(PaymentState::ChargedBack),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use contract::{
+    contract_api::{storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{
+    account::AccountHash,
+    URef,
+};
+
+use crate::errors::ContractError;
+use crate::keys::account_key;
+use crate::utils;
+
+pub const SUBSCRIBERS_DICT: &str = "subscribers";
+pub const SUBSCRIBER_COUNT: &str = "subscriber_count";
+pub const ACCOUNT_SUBSCRIPTIONS_DICT: &str = "account_subscriptions";
+pub const ACCOUNT_ACCESS_DICT: &str = "account_access";
+
+/// Index of every account that registered a subscription, in registration order.
+/// The `subscribers` dictionary maps the index position (decimal string) to the account,
+/// and `account_subscriptions` maps an account to the ids of its subscriptions.
+/// Accounts given access to a subscription they do not pay for, its beneficiary or the members of
+/// a seat-based plan, are kept apart in `account_access`, so the index only counts paying accounts.
+pub struct Subscribers {
+    dict_uref: URef,
+    subscriptions_dict_uref: URef,
+    access_dict_uref: URef,
+}
+
+impl Subscribers {
+    pub fn new() -> Subscribers {
+        Subscribers {
+            dict_uref: utils::get_dictionary_uref(SUBSCRIBERS_DICT),
+            subscriptions_dict_uref: utils::get_dictionary_uref(ACCOUNT_SUBSCRIPTIONS_DICT),
+            access_dict_uref: utils::get_dictionary_uref(ACCOUNT_ACCESS_DICT),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        utils::get_key(SUBSCRIBER_COUNT).unwrap_or_revert_with(ContractError::MissingKey)
+    }
+
+    /// Append an account to the index and return its position.
+    fn add(&self, account: AccountHash) -> u64 {
+        let index = self.count();
+
+        storage::dictionary_put(self.dict_uref, &index.to_string(), account);
+        utils::set_key(SUBSCRIBER_COUNT, index + 1);
+
+        index
+    }
+
+    pub fn get(&self, index: u64) -> Option<AccountHash> {
+        storage::dictionary_get(self.dict_uref, &index.to_string()).unwrap_or_revert_with(ContractError::DictionaryError)
+    }
+
+    /// Record a new subscription of an account, indexing the account on its first subscription.
+    pub fn add_subscription(&self, account: AccountHash, subscription_id: u64) {
+        let mut subscription_ids = self.get_subscriptions(account);
+
+        if subscription_ids.is_empty() {
+            self.add(account);
+        }
+
+        subscription_ids.push(subscription_id);
+        storage::dictionary_put(self.subscriptions_dict_uref, &account_key(account), subscription_ids);
+    }
+
+    /// The ids of every subscription registered by an account, oldest first.
+    pub fn get_subscriptions(&self, account: AccountHash) -> Vec<u64> {
+        let subscription_ids: Option<Vec<u64>> = storage::dictionary_get(self.subscriptions_dict_uref, &account_key(account)).unwrap_or_revert_with(ContractError::DictionaryError);
+
+        subscription_ids.unwrap_or_default()
+    }
+
+    /// Record access of an account to a subscription it does not pay for, without indexing the
+    /// account. Access already recorded, e.g. a seat it held before or as beneficiary, is kept once.
+    pub fn add_access(&self, account: AccountHash, subscription_id: u64) {
+        let mut subscription_ids = self.get_access(account);
+
+        if subscription_ids.contains(&subscription_id) {
+            return;
+        }
+
+        subscription_ids.push(subscription_id);
+        storage::dictionary_put(self.access_dict_uref, &account_key(account), subscription_ids);
+    }
+
+    /// Forget the access of an account to a subscription, once its seat is taken.
+    pub fn remove_access(&self, account: AccountHash, subscription_id: u64) {
+        let mut subscription_ids = self.get_access(account);
+
+        subscription_ids.retain(|id| *id != subscription_id);
+        storage::dictionary_put(self.access_dict_uref, &account_key(account), subscription_ids);
+    }
+
+    /// The ids of every subscription an account is given access to without paying for it,
+    /// oldest first.
+    pub fn get_access(&self, account: AccountHash) -> Vec<u64> {
+        let subscription_ids: Option<Vec<u64>> = storage::dictionary_get(self.access_dict_uref, &account_key(account)).unwrap_or_revert_with(ContractError::DictionaryError);
+
+        subscription_ids.unwrap_or_default()
+    }
+}
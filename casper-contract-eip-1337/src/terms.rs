@@ -0,0 +1,65 @@
+//! The terms a subscriber signs, and the message the contract hashes into a subscription hash.
+
+use types::{account::AccountHash, ContractHash, U256};
+
+/// The terms of one subscription, bound into its subscription hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionTerms {
+    /// Account receiving the payments
+    pub to: AccountHash,
+    /// Account of the subscriber, who signs and pays
+    pub from: AccountHash,
+    /// Tokens paid every period, the plan's price in the chosen token
+    pub token_amount: U256,
+    /// Seconds between two payments
+    pub period_seconds: u64,
+    /// Id of the subscription, so a signature only pays for the subscription it was made for
+    pub subscription_id: u64,
+    /// Erc20 token the subscriber chose to pay with
+    pub erc20_contract_hash: ContractHash,
+    /// Most a payment can charge, usage of a metered subscription and every seat included,
+    /// `None` for no cap
+    pub max_amount_per_period: Option<U256>,
+    /// Most the subscription can charge over its lifetime, `None` for no cap
+    pub lifetime_cap: Option<U256>,
+    /// Account given access when another account pays for it, `None` for the subscriber itself
+    pub beneficiary: Option<AccountHash>,
+    /// Whether the usage reported is charged on top of the price, which needs a max amount per period
+    pub metered: bool,
+}
+
+impl SubscriptionTerms {
+    /// The meta transaction data hashed into the subscription hash.
+    /// New terms are appended at the end. The spending caps are only appended when the subscriber
+    /// signed one, a beneficiary or metered billing, a cap left out as an empty string, then the
+    /// beneficiary and `metered` when set, so the hashes without them do not change.
+    pub fn data(&self) -> String {
+        let data = format!(
+            "{}_{}_{}_{}_{}_{}",
+            self.to,
+            self.from,
+            self.token_amount,
+            self.period_seconds,
+            self.subscription_id,
+            self.erc20_contract_hash.to_formatted_string()
+        );
+
+        if self.max_amount_per_period.is_none() && self.lifetime_cap.is_none() && self.beneficiary.is_none() && !self.metered {
+            return data;
+        }
+
+        let cap = |cap: Option<U256>| cap.map(|cap| cap.to_string()).unwrap_or_default();
+        let data = format!("{}_{}_{}", data, cap(self.max_amount_per_period), cap(self.lifetime_cap));
+
+        let data = match self.beneficiary {
+            Some(beneficiary) => format!("{}_{}", data, beneficiary),
+            None => data,
+        };
+
+        if self.metered {
+            format!("{}_metered", data)
+        } else {
+            data
+        }
+    }
+}
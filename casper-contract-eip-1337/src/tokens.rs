@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use contract::{
+    contract_api::{runtime},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{ApiError, ContractHash, U256};
+
+use crate::constants;
+use crate::errors::ContractError;
+use crate::utils;
+
+pub const TOKEN_PRICES: &str = "token_prices";
+pub const PENDING_PRICES: &str = "pending_prices";
+
+/// The erc20 tokens the plan accepts, each with the price of a period in that token at the
+/// current blocktime. Stored in the `token_prices` named key, with the price changes in
+/// `pending_prices` that took effect applied. The token set at install is always accepted.
+pub fn get_prices() -> BTreeMap<ContractHash, U256> {
+    get_prices_at(runtime::get_blocktime().into())
+}
+
+/// Like `get_prices`, with the price changes in `pending_prices` that took effect by `timestamp`
+/// applied, e.g. the time a period fell due. Changes already written to `token_prices` when the
+/// next one was scheduled apply whatever the timestamp.
+pub fn get_prices_at(timestamp: u64) -> BTreeMap<ContractHash, U256> {
+    let mut prices: BTreeMap<ContractHash, U256> = utils::get_key(TOKEN_PRICES).unwrap_or_revert_with(ContractError::MissingKey);
+
+    for (erc20_contract_hash, (token_amount, effective_at)) in get_pending_prices() {
+        if effective_at <= timestamp {
+            prices.insert(erc20_contract_hash, token_amount);
+        }
+    }
+    prices
+}
+
+/// The price changes the publisher scheduled, each token with its new price and the blocktime in
+/// milliseconds it takes effect at. Stored in the `pending_prices` named key.
+pub fn get_pending_prices() -> BTreeMap<ContractHash, (U256, u64)> {
+    utils::get_key(PENDING_PRICES).unwrap_or_revert_with(ContractError::MissingKey)
+}
+
+/// The price change scheduled for the token that has not taken effect yet, if any.
+pub fn get_pending_price(erc20_contract_hash: ContractHash) -> Option<(U256, u64)> {
+    let blocktime: u64 = runtime::get_blocktime().into();
+
+    get_pending_prices()
+        .get(&erc20_contract_hash)
+        .copied()
+        .filter(|(_token_amount, effective_at)| *effective_at > blocktime)
+}
+
+/// Schedule a new price for an accepted token, replacing the change pending for it.
+/// Changes that took effect are written to `token_prices` first, so they are not lost.
+pub fn schedule(erc20_contract_hash: ContractHash, token_amount: U256, effective_at: u64) {
+    let blocktime: u64 = runtime::get_blocktime().into();
+    let prices = get_prices();
+
+    if !prices.contains_key(&erc20_contract_hash) {
+        runtime::revert(ApiError::User(ContractError::UnsupportedToken as u16));
+    }
+
+    let mut pending_prices = get_pending_prices();
+    pending_prices.retain(|_, (_token_amount, effective_at)| *effective_at > blocktime);
+    pending_prices.insert(erc20_contract_hash, (token_amount, effective_at));
+
+    utils::set_key(TOKEN_PRICES, prices);
+    utils::set_key(PENDING_PRICES, pending_prices);
+}
+
+/// The price of a period in the token, or `None` if the plan does not accept it.
+pub fn get_price(erc20_contract_hash: ContractHash) -> Option<U256> {
+    get_prices().get(&erc20_contract_hash).copied()
+}
+
+/// The price of a period in the token at `timestamp`, or `None` if the plan does not accept it.
+pub fn get_price_at(erc20_contract_hash: ContractHash, timestamp: u64) -> Option<U256> {
+    get_prices_at(timestamp).get(&erc20_contract_hash).copied()
+}
+
+/// Accept a new token at the given price.
+/// An accepted token only changes price with notice, see `schedule`.
+pub fn add(erc20_contract_hash: ContractHash, token_amount: U256) {
+    let mut prices = get_prices();
+
+    if prices.contains_key(&erc20_contract_hash) {
+        runtime::revert(ApiError::User(ContractError::TokenExists as u16));
+    }
+
+    prices.insert(erc20_contract_hash, token_amount);
+    utils::set_key(TOKEN_PRICES, prices);
+}
+
+/// The token paid with when none is chosen, the one set at install.
+pub fn get_default() -> ContractHash {
+    utils::get_key(constants::ERC20_CONTRACT_HASH).unwrap_or_revert_with(ContractError::MissingKey)
+}
@@ -1,22 +1,28 @@
 use std::convert::TryInto;
 
 use contract::{
-    contract_api::{runtime, storage},
+    contract_api::{self, runtime, storage},
+    ext_ffi,
     unwrap_or_revert::UnwrapOrRevert,
 };
 
 use types::{
-    bytesrepr::{FromBytes, ToBytes},
-    CLTyped,
+    api_error,
+    bytesrepr::{self, FromBytes, ToBytes},
+    ApiError, CLTyped, Key, URef,
 };
 
+use crate::errors::ContractError;
+
 /// Getter function from context storage.
 /// Returns the previously data previously stored under `name` key,
 /// or returns the default value of the type expected at the end of the call.
 pub fn get_key<T: FromBytes + CLTyped + Default>(name: &str) -> Option<T> {
     runtime::get_key(name).map(|value| {
-        let key = value.try_into().unwrap_or_revert();
-        storage::read(key).unwrap_or_revert().unwrap_or_revert()
+        let key = value.try_into().unwrap_or_revert_with(ContractError::InvalidKey);
+        storage::read(key)
+            .unwrap_or_revert_with(ContractError::InvalidKey)
+            .unwrap_or_revert_with(ContractError::MissingKey)
     })
 }
 
@@ -25,7 +31,7 @@ pub fn get_key<T: FromBytes + CLTyped + Default>(name: &str) -> Option<T> {
 pub fn set_key<T: ToBytes + CLTyped>(name: &str, value: T) {
     match runtime::get_key(name) {
         Some(key) => {
-            let key_ref = key.try_into().unwrap_or_revert();
+            let key_ref = key.try_into().unwrap_or_revert_with(ContractError::InvalidKey);
             storage::write(key_ref, value);
         }
         None => {
@@ -35,3 +41,45 @@ pub fn set_key<T: ToBytes + CLTyped>(name: &str, value: T) {
     }
 }
 
+/// Returns the URef of the dictionary stored under the `name` key.
+pub fn get_dictionary_uref(name: &str) -> URef {
+    let dict_key: Key = runtime::get_key(name).unwrap_or_revert_with(ContractError::MissingDictionary);
+    *dict_key.as_uref().unwrap_or_revert_with(ContractError::MissingDictionary)
+}
+
+/// Returns the runtime argument `name`, or `None` if the caller did not pass it.
+/// Reverts with `InvalidArgument` if the argument does not deserialize to `T`.
+pub fn get_optional_named_arg<T: FromBytes>(name: &str) -> Option<T> {
+    let mut arg_size: usize = 0;
+    let ret = unsafe {
+        ext_ffi::casper_get_named_arg_size(
+            name.as_bytes().as_ptr(),
+            name.len(),
+            &mut arg_size as *mut usize,
+        )
+    };
+    match api_error::result_from(ret) {
+        Ok(_) => {}
+        Err(ApiError::MissingArgument) => return None,
+        Err(error) => runtime::revert(error),
+    }
+
+    let arg_bytes = if arg_size > 0 {
+        let data_non_null_ptr = contract_api::alloc_bytes(arg_size);
+        let ret = unsafe {
+            ext_ffi::casper_get_named_arg(
+                name.as_bytes().as_ptr(),
+                name.len(),
+                data_non_null_ptr.as_ptr(),
+                arg_size,
+            )
+        };
+        let data = unsafe { Vec::from_raw_parts(data_non_null_ptr.as_ptr(), arg_size, arg_size) };
+        api_error::result_from(ret).unwrap_or_revert();
+        data
+    } else {
+        Vec::new()
+    };
+
+    Some(bytesrepr::deserialize(arg_bytes).unwrap_or_revert_with(ContractError::InvalidArgument))
+}
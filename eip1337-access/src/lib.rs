@@ -0,0 +1,62 @@
+//! Access checks against the EIP-1337 subscription contract, for other contracts to gate content
+//! on a subscription.
+//!
+//! [`require_active_subscription`] calls the contract's `get_active_subscription` entry point and
+//! returns a [`Result`], instead of calling `is_subscription_active` with a subscription hash and
+//! having the whole deploy revert. The crate is `no_std`, for contract code:
+//!
+//! ```ignore
+//! let subscription_id = eip1337_access::require_active_subscription(eip1337_package, runtime::get_caller())
+//!     .unwrap_or_revert();
+//! ```
+#![no_std]
+
+use contract::contract_api::runtime;
+use types::{account::AccountHash, runtime_args, ApiError, ContractPackageHash, RuntimeArgs};
+
+/// Entry point of the EIP-1337 contract returning the subscription giving an account access.
+pub const GET_ACTIVE_SUBSCRIPTION: &str = "get_active_subscription";
+pub const ACCOUNT: &str = "account";
+pub const GRACE_PERIOD_SECONDS: &str = "grace_period_seconds";
+
+/// Enum for AccessError, why an account has no access. It converts into `ApiError::User(code)`,
+/// so a gating contract can revert with it, with a code the EIP-1337 contract does not use.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessError {
+    /// 1337 for no subscription giving the account access is active
+    NoActiveSubscription = 1337,
+}
+
+impl From<AccessError> for ApiError {
+    fn from(error: AccessError) -> ApiError {
+        ApiError::User(error as u16)
+    }
+}
+
+/// The id of an active subscription giving `account` access, from the latest version of the
+/// EIP-1337 contract in `eip1337_package`. The account may pay for it itself, receive it as the
+/// beneficiary of a subscription another account pays for, or hold a seat of it.
+pub fn require_active_subscription(eip1337_package: ContractPackageHash, account: AccountHash) -> Result<u64, AccessError> {
+    require_active_subscription_with_grace(eip1337_package, account, 0)
+}
+
+/// Like [`require_active_subscription`], also giving access for `grace_period_seconds` after a
+/// payment is due, to allow the publisher to execute it.
+pub fn require_active_subscription_with_grace(
+    eip1337_package: ContractPackageHash,
+    account: AccountHash,
+    grace_period_seconds: u64,
+) -> Result<u64, AccessError> {
+    let subscription_id: Option<u64> = runtime::call_versioned_contract(
+        eip1337_package,
+        None,
+        GET_ACTIVE_SUBSCRIPTION,
+        runtime_args! {
+            ACCOUNT => account,
+            GRACE_PERIOD_SECONDS => grace_period_seconds,
+        },
+    );
+
+    subscription_id.ok_or(AccessError::NoActiveSubscription)
+}
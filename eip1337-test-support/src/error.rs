@@ -0,0 +1,72 @@
+use std::any::Any;
+use std::fmt;
+
+use crate::ContractError;
+
+const USER_ERROR_MARKER: &str = "ApiError::User(";
+
+/// Why a deploy run through the harness failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The EIP-1337 contract reverted with one of its documented errors.
+    Contract(ContractError),
+    /// A user error the EIP-1337 contract does not define, e.g. one raised by the ERC-20 contract.
+    User(u16),
+    /// Any other execution failure, holding the engine's message.
+    Other(String),
+}
+
+impl CallError {
+    /// Reads the error out of the panic `TestContext::run` raises for a failed deploy.
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> CallError {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => String::from("deploy failed"),
+            },
+        };
+
+        match user_error_code(&message) {
+            Some(code) => match ContractError::from_code(code) {
+                Some(error) => CallError::Contract(error),
+                None => CallError::User(code),
+            },
+            None => CallError::Other(message),
+        }
+    }
+
+    /// The contract error, if the EIP-1337 contract reverted with one.
+    pub fn contract_error(&self) -> Option<ContractError> {
+        match self {
+            CallError::Contract(error) => Some(*error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ContractError> for CallError {
+    fn from(error: ContractError) -> CallError {
+        CallError::Contract(error)
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Contract(error) => write!(f, "User error: {} ({:?}: {})", error.code(), error, error.description()),
+            CallError::User(code) => write!(f, "User error: {}", code),
+            CallError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// The code of the first `ApiError::User(<code>)` in an execution error message.
+fn user_error_code(message: &str) -> Option<u16> {
+    let start = message.find(USER_ERROR_MARKER)? + USER_ERROR_MARKER.len();
+    let end = start + message[start..].find(')')?;
+
+    message[start..end].parse().ok()
+}
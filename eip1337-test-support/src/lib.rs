@@ -0,0 +1,206 @@
+//! Test harness for the EIP-1337 subscription contract.
+//!
+//! [`Subscription::deployment`] builds an engine test context with an ERC-20 token and the
+//! EIP-1337 contract installed, and drives the contract through typed methods named after its
+//! entry points. A failed deploy comes back as a [`CallError`], so reverts can be asserted
+//! against the contract's [`ContractError`] codes.
+//!
+//! The `casper-contract-eip-1337.wasm` and `erc-20.wasm` files are loaded from the `wasm`
+//! directory of the crate running the tests (`make copy-wasm-file-to-test` sets this up for
+//! the `tests` crate).
+
+// The contract modules below are pure, free of the contract API and the runtime, so they are
+// compiled in here as they are and the tests check error codes, signed terms, prices, payment
+// splits, dictionary keys, pages and calendar dates off-chain against the code the contract runs.
+#[path = "../../casper-contract-eip-1337/src/errors.rs"]
+mod contract_errors;
+#[path = "../../casper-contract-eip-1337/src/terms.rs"]
+mod terms;
+#[path = "../../casper-contract-eip-1337/src/paging.rs"]
+mod paging;
+#[path = "../../casper-contract-eip-1337/src/payouts.rs"]
+mod payouts;
+#[path = "../../casper-contract-eip-1337/src/pricing.rs"]
+mod pricing;
+#[path = "../../casper-contract-eip-1337/src/status.rs"]
+mod status;
+#[path = "../../casper-contract-eip-1337/src/keys.rs"]
+mod keys;
+#[path = "../../casper-contract-eip-1337/src/calendar.rs"]
+mod calendar;
+#[path = "../../subscription_hash_signer/src/hash.rs"]
+mod hash;
+
+mod error;
+mod subscription;
+mod utils;
+
+pub use calendar::{
+    civil_from_days, days_from_civil, days_in_month, is_leap_year, weekday, BillingInterval, IntervalType,
+    MILLIS_PER_DAY,
+};
+pub use contract_errors::ContractError;
+pub use error::CallError;
+pub use paging::{matches_status, page, MAX_PAGE_SIZE};
+pub use payouts::{is_valid as is_valid_payout, split as split_payment, TOTAL_BASIS_POINTS};
+pub use pricing::{discounted_price, is_valid_discount, metered_price, prorated_price, seats_price, DiscountType};
+pub use status::{PaymentState, SubscriptionStatus, CANCELLED_TIMESTAMP};
+pub use subscription::*;
+pub use terms::SubscriptionTerms;
+pub use utils::{generate_eip_1337_secret_key, get_hash_bytes, get_hex, get_subscription_data};
+
+// The date arithmetic of `calendar` and the pages of `paging` are tested here rather than in the
+// `tests` crate, so `cargo test -p eip1337-test-support` checks them without building the contract
+// or running the engine.
+#[cfg(test)]
+mod tests {
+    use crate::calendar::{
+        civil_from_days, days_from_civil, days_in_month, is_leap_year, weekday, BillingInterval, IntervalType,
+        MILLIS_PER_DAY,
+    };
+    use crate::paging::{matches_status, page, MAX_PAGE_SIZE};
+    use crate::status::{SubscriptionStatus, CANCELLED_TIMESTAMP};
+
+    // Dates convert to and from days since the epoch, with the Gregorian leap years
+    #[test]
+    fn test_calendar_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+        assert_eq!(civil_from_days(days_from_civil(2023, 12, 31) + 1), (2024, 1, 1));
+
+        for days in 0..150_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert!(day >= 1 && day <= days_in_month(year, month));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2023));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+
+        // January 1st 1970 was a Thursday, January 1st 2024 a Monday
+        assert_eq!(weekday(0), 4);
+        assert_eq!(weekday(days_from_civil(2024, 1, 1)), 1);
+        assert_eq!(weekday(days_from_civil(2024, 1, 7)), 7);
+    }
+
+    // Each interval type finds the next billing day strictly after a timestamp, at midnight UTC
+    #[test]
+    fn test_billing_intervals() {
+        let at = |year: u64, month: u32, day: u32| days_from_civil(year, month, day) * MILLIS_PER_DAY;
+        let interval = |interval_type: IntervalType, billing_day: u8| BillingInterval { interval_type, period_seconds: 60, billing_day };
+
+        // a fixed period ignores the calendar
+        assert_eq!(interval(IntervalType::Seconds, 0).next_timestamp(1_500), 61_500);
+
+        let daily = interval(IntervalType::Daily, 0);
+        assert_eq!(daily.next_timestamp(at(2024, 2, 28) + 1), at(2024, 2, 29));
+        assert_eq!(daily.next_timestamp(at(2024, 12, 31)), at(2025, 1, 1));
+
+        // Mondays, from a Monday and a Wednesday
+        let weekly = interval(IntervalType::Weekly, 1);
+        assert_eq!(weekly.next_timestamp(at(2024, 1, 1)), at(2024, 1, 8));
+        assert_eq!(weekly.next_timestamp(at(2024, 1, 3) + 5_000), at(2024, 1, 8));
+        assert_eq!(interval(IntervalType::Weekly, 7).next_timestamp(at(2024, 1, 1)), at(2024, 1, 7));
+
+        // the 31st falls on the last day of shorter months, and comes back in longer ones
+        let monthly = interval(IntervalType::Monthly, 31);
+        let mut timestamp = at(2024, 1, 31);
+        let mut dates = Vec::new();
+        for _ in 0..4 {
+            timestamp = monthly.next_timestamp(timestamp);
+            dates.push(civil_from_days(timestamp / MILLIS_PER_DAY));
+        }
+        assert_eq!(dates, vec![(2024, 2, 29), (2024, 3, 31), (2024, 4, 30), (2024, 5, 31)]);
+        assert_eq!(monthly.next_timestamp(at(2023, 1, 31)), at(2023, 2, 28));
+        assert_eq!(interval(IntervalType::Monthly, 1).next_timestamp(at(2024, 12, 15)), at(2025, 1, 1));
+        assert_eq!(interval(IntervalType::Monthly, 20).next_timestamp(at(2024, 3, 15)), at(2024, 3, 20));
+
+        // February 29th falls on the 28th outside leap years
+        let yearly = interval(IntervalType::Yearly, 29);
+        let mut timestamp = at(2024, 2, 29);
+        let mut dates = Vec::new();
+        for _ in 0..4 {
+            timestamp = yearly.next_timestamp(timestamp);
+            dates.push(civil_from_days(timestamp / MILLIS_PER_DAY));
+        }
+        assert_eq!(dates, vec![(2025, 2, 28), (2026, 2, 28), (2027, 2, 28), (2028, 2, 29)]);
+        assert_eq!(interval(IntervalType::Yearly, 20).next_timestamp(at(2024, 6, 10)), at(2024, 6, 20));
+
+        assert!(interval(IntervalType::Daily, 0).is_valid());
+        assert!(interval(IntervalType::Weekly, 7).is_valid());
+        assert!(!interval(IntervalType::Weekly, 0).is_valid());
+        assert!(!interval(IntervalType::Weekly, 8).is_valid());
+        assert!(interval(IntervalType::Monthly, 31).is_valid());
+        assert!(!interval(IntervalType::Monthly, 32).is_valid());
+        assert!(!interval(IntervalType::Yearly, 0).is_valid());
+        assert_eq!(IntervalType::from_u8(4), Some(IntervalType::Yearly));
+        assert_eq!(IntervalType::from_u8(5), None);
+
+        // a price change leaves a whole period of notice: a fixed period, or up to the second billing day
+        assert_eq!(interval(IntervalType::Seconds, 0).notice_end(1_500), 61_500);
+        assert_eq!(monthly.notice_end(at(2024, 1, 15)), at(2024, 2, 29));
+        assert_eq!(weekly.notice_end(at(2024, 1, 3)), at(2024, 1, 15));
+
+        // the period ending at a due date starts at the billing day before it
+        assert_eq!(interval(IntervalType::Seconds, 0).previous_timestamp(61_500), 1_500);
+        assert_eq!(daily.previous_timestamp(at(2025, 1, 1)), at(2024, 12, 31));
+        assert_eq!(weekly.previous_timestamp(at(2024, 1, 8)), at(2024, 1, 1));
+        assert_eq!(monthly.previous_timestamp(at(2024, 3, 31)), at(2024, 2, 29));
+        assert_eq!(monthly.previous_timestamp(at(2024, 2, 29)), at(2024, 1, 31));
+        assert_eq!(interval(IntervalType::Monthly, 1).previous_timestamp(at(2025, 1, 1)), at(2024, 12, 1));
+        assert_eq!(yearly.previous_timestamp(at(2025, 2, 28)), at(2024, 2, 29));
+    }
+
+    // A page holds at most MAX_PAGE_SIZE positions and never runs past the end of the list
+    #[test]
+    fn test_page() {
+        assert_eq!(page(0, 10, 25), 0..10);
+        assert_eq!(page(20, 10, 25), 20..25);
+        assert_eq!(page(0, 0, 25), 0..0);
+
+        // past the end, the page is empty
+        assert_eq!(page(25, 10, 25), 25..25);
+        assert!(page(30, 10, 25).is_empty());
+        assert!(page(0, 10, 0).is_empty());
+
+        // the limit is clamped to MAX_PAGE_SIZE
+        assert_eq!(MAX_PAGE_SIZE, 100);
+        assert_eq!(page(0, 101, 1_000), 0..100);
+        assert_eq!(page(50, u64::MAX, 1_000), 50..150);
+
+        // offset + limit saturates instead of overflowing
+        assert_eq!(page(u64::MAX - 5, 10, u64::MAX), u64::MAX - 5..u64::MAX);
+        assert!(page(u64::MAX, u64::MAX, 10).is_empty());
+    }
+
+    // The status filter keeps the entries with a subscription in that status, and everything without one
+    #[test]
+    fn test_matches_status() {
+        let blocktime = 60_000;
+        let active = Some(120_000);
+        let due = Some(60_000);
+        let cancelled = Some(CANCELLED_TIMESTAMP);
+
+        assert!(matches_status(None, vec![], blocktime));
+        assert!(matches_status(None, vec![None], blocktime));
+
+        assert!(matches_status(Some(SubscriptionStatus::Active as u8), vec![active], blocktime));
+        assert!(!matches_status(Some(SubscriptionStatus::Active as u8), vec![due], blocktime));
+        assert!(matches_status(Some(SubscriptionStatus::Due as u8), vec![due], blocktime));
+        assert!(matches_status(Some(SubscriptionStatus::Cancelled as u8), vec![cancelled], blocktime));
+        assert!(matches_status(Some(SubscriptionStatus::NotFound as u8), vec![None], blocktime));
+        assert!(!matches_status(Some(4), vec![active, due, cancelled, None], blocktime));
+
+        // an account matches when any of its subscriptions does, and never without subscriptions
+        assert!(matches_status(Some(SubscriptionStatus::Due as u8), vec![active, cancelled, due], blocktime));
+        assert!(!matches_status(Some(SubscriptionStatus::Due as u8), vec![active, cancelled], blocktime));
+        assert!(!matches_status(Some(SubscriptionStatus::Active as u8), vec![], blocktime));
+    }
+}
@@ -0,0 +1,1286 @@
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+
+use engine_test_support::{Code, Hash, Session, SessionBuilder, TestContext, TestContextBuilder};
+
+use types::{
+    account::AccountHash, bytesrepr::{FromBytes, ToBytes}, runtime_args, CLTyped, ContractHash,
+    ContractPackageHash, Key, PublicKey, RuntimeArgs, SecretKey, U256, U512,
+};
+
+use erc20::{
+    constants::{AMOUNT_RUNTIME_ARG_NAME, RECIPIENT_RUNTIME_ARG_NAME, SPENDER_RUNTIME_ARG_NAME},
+    Address,
+};
+
+use crate::calendar::{IntervalType, BILLING_DAY, INTERVAL_TYPE};
+use crate::error::CallError;
+use crate::keys::account_key;
+use crate::pricing::DiscountType;
+pub use crate::payouts::PAYEES;
+use crate::utils::generate_eip_1337_secret_key;
+
+pub const TOKEN_NAME: &str = "TEST";
+pub const TOKEN_SYMBOL: &str = "TST";
+pub const TOKEN_DECIMALS: u8 = 100;
+pub const TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000;
+/// The token amount paid to the publisher every period.
+pub const TOKEN_AMOUNT_VALUE: u64 = 10;
+
+pub const EIP_1337_WASM: &str = "casper-contract-eip-1337.wasm";
+pub const ERC20_WASM: &str = "erc-20.wasm";
+/// The example contract gating content on a subscription, see `gated-content-example`.
+pub const GATED_CONTENT_WASM: &str = "gated-content.wasm";
+const GATED_CONTENT_CONTRACT_NAME: &str = "gated-content";
+/// Session code storing the return value of a view for the tests, see `eip1337-view-probe`.
+pub const VIEW_PROBE_WASM: &str = "view-probe.wasm";
+
+const ARG_NAME: &str = "name";
+const ARG_SYMBOL: &str = "symbol";
+const ARG_DECIMALS: &str = "decimals";
+const ARG_TOTAL_SUPPLY: &str = "total_supply";
+
+pub const TRANSFER_ENTRY_POINT_NAME: &str = "transfer";
+pub const APPROVE_ENTRY_POINT_NAME: &str = "approve";
+
+pub const HASHES_DICT: &str = "hashes";
+pub const NEXT_VALID_TIMESTAMPS_DICT: &str = "next_valid_timestamps";
+pub const ACCOUNT_SUBSCRIPTIONS_DICT: &str = "account_subscriptions";
+pub const ACCOUNT_ACCESS_DICT: &str = "account_access";
+pub const SUBSCRIBERS_DICT: &str = "subscribers";
+pub const ESCROW_STATES_DICT: &str = "escrow_states";
+pub const COUPON_REDEMPTIONS_DICT: &str = "coupon_redemptions";
+pub const DISCOUNTED_PERIODS_DICT: &str = "discounted_periods";
+pub const USAGES_DICT: &str = "usages";
+pub const AMOUNTS_PAID_DICT: &str = "amounts_paid";
+pub const BENEFICIARIES_DICT: &str = "beneficiaries";
+pub const MEMBERS_DICT: &str = "members";
+pub const SEAT_CHARGES_DICT: &str = "seat_charges";
+pub const SEAT_CREDITS_DICT: &str = "seat_credits";
+pub const ESCROW_PENDING_DICT: &str = "escrow_pending";
+pub const ESCROW_AVAILABLE_DICT: &str = "escrow_available";
+pub const ESCROW_HELD: &str = "escrow_held";
+pub const ESCROW_PAYMENT_COUNT: &str = "escrow_payment_count";
+pub const SUBSCRIBER_COUNT: &str = "subscriber_count";
+pub const SUBSCRIPTION_COUNT: &str = "subscription_count";
+pub const TOKEN_PRICES: &str = "token_prices";
+pub const PENDING_PRICES: &str = "pending_prices";
+pub const UNIT_PRICES: &str = "unit_prices";
+pub const USAGE_ORACLE: &str = "usage_oracle";
+pub const SEAT_BASED: &str = "seat_based";
+pub const BALANCES_KEY_NAME: &str = "balances";
+
+const CONTRACT_NAME: &str = "casper-contract-eip-1337"; //contract name
+const ERC20_CONTRACT_NAME: &str = "erc20_token_contract"; //erc20 contract hash
+const ERC20_CONTRACT_HASH: &str = "erc20_contract_hash"; //erc20 contract hash2
+
+const TO: &str = "to"; //the publisher
+const TOKEN_AMOUNT: &str = "token_amount"; //the token amount paid to the publisher
+const PERIOD_SECONDS: &str = "period_seconds"; //the period in seconds between payments
+const DISPUTE_WINDOW_SECONDS: &str = "dispute_window_seconds"; //the seconds payments stay in escrow
+const PACKAGE_HASH: &str = "package_hash"; //the contract package, holding escrowed payments
+
+/// Install an ERC-20 token whose whole supply is held by `installer`.
+fn install_erc_20(context: &mut TestContext, installer: AccountHash) -> ContractHash {
+    let erc_20_session_code = Code::from(ERC20_WASM);
+
+    let erc_20_session_args = runtime_args! {
+        ARG_NAME => TOKEN_NAME,
+        ARG_SYMBOL => TOKEN_SYMBOL,
+        ARG_DECIMALS => TOKEN_DECIMALS,
+        ARG_TOTAL_SUPPLY => U256::from(TOKEN_TOTAL_SUPPLY),
+    };
+
+    let erc_20_session = SessionBuilder::new(erc_20_session_code, erc_20_session_args)
+        .with_address(installer)
+        .with_authorization_keys(&[installer])
+        .build();
+
+    context.run(erc_20_session);
+
+    context
+        .get_account(installer)
+        .unwrap()
+        .named_keys()
+        .get(ERC20_CONTRACT_NAME)
+        .unwrap()
+        .normalize()
+        .into_hash()
+        .unwrap()
+        .into()
+}
+
+/// Run a deploy, turning the panic `TestContext::run` raises on failure into a `CallError`.
+fn run(context: &mut TestContext, session: Session) -> Result<(), CallError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        context.run(session);
+    }))
+    .map_err(CallError::from_panic)
+}
+
+/// The terms of a coupon for `Subscription::add_coupon`. The constructors leave every limit
+/// off, set the others with struct update syntax.
+#[derive(Debug, Clone)]
+pub struct Coupon {
+    pub discount_type: DiscountType,
+    pub discount: U256,
+    /// Token of a fixed amount, the token set at install if `None`
+    pub erc20_contract_hash: Option<ContractHash>,
+    pub periods: u64,
+    pub max_redemptions: u64,
+    pub expires_at: u64,
+}
+
+impl Coupon {
+    /// `basis_points` out of 10000 off every payment.
+    pub fn percentage(basis_points: u32) -> Coupon {
+        Coupon::new(DiscountType::Percentage, U256::from(basis_points))
+    }
+
+    /// `amount` tokens off every payment.
+    pub fn fixed_amount(amount: U256) -> Coupon {
+        Coupon::new(DiscountType::FixedAmount, amount)
+    }
+
+    fn new(discount_type: DiscountType, discount: U256) -> Coupon {
+        Coupon {
+            discount_type,
+            discount,
+            erc20_contract_hash: None,
+            periods: 0,
+            max_redemptions: 0,
+            expires_at: 0,
+        }
+    }
+}
+
+/// An engine test context with an ERC-20 token and the EIP-1337 contract installed.
+///
+/// The ERC-20 admin holds the whole token supply, `user_from` is the subscriber and
+/// `user_to` the publisher that gets paid.
+pub struct Subscription {
+    context: TestContext,
+    block_time: u64,
+    pub eip_1337_admin: AccountHash,
+    pub eip_1337_admin_pk: PublicKey,
+    pub eip_1337_contract_hash: ContractPackageHash,
+    pub erc_20_admin: AccountHash,
+    pub erc_20_admin_pk: PublicKey,
+    pub erc_20_contract_hash: ContractHash,
+    pub user_to: AccountHash,
+    pub user_to_pk: PublicKey,
+    pub user_from: AccountHash,
+    pub user_from_pk: PublicKey,
+}
+
+impl Subscription {
+
+    /// Install the ERC-20 token and an EIP-1337 contract charging `TOKEN_AMOUNT_VALUE`
+    /// every `period_seconds`.
+    pub fn deployment(period_seconds: u64) -> Subscription {
+        Subscription::install(period_seconds, None, None, false)
+    }
+
+    /// Like [`Subscription::deployment`], with payments held in escrow for `dispute_window_seconds`.
+    pub fn deployment_with_escrow(period_seconds: u64, dispute_window_seconds: u64) -> Subscription {
+        Subscription::install(period_seconds, Some(dispute_window_seconds), None, false)
+    }
+
+    /// Like [`Subscription::deployment`], billing on calendar periods of `interval_type`, due on
+    /// `billing_day`.
+    pub fn deployment_with_interval(period_seconds: u64, interval_type: IntervalType, billing_day: u8) -> Subscription {
+        Subscription::install(period_seconds, None, Some((interval_type, billing_day)), false)
+    }
+
+    /// Like [`Subscription::deployment`], for a seat-based plan charging subscriptions for every member.
+    pub fn deployment_with_seats(period_seconds: u64) -> Subscription {
+        Subscription::install(period_seconds, None, None, true)
+    }
+
+    fn install(
+        period_seconds: u64,
+        dispute_window_seconds: Option<u64>,
+        billing_interval: Option<(IntervalType, u8)>,
+        seat_based: bool,
+    ) -> Subscription {
+
+        // Create EIP 1337 contract admin.
+        let admin_secret = generate_eip_1337_secret_key();
+        let admin_key: PublicKey = (&admin_secret).into();
+        let admin_addr = AccountHash::from(&admin_key);
+
+        // Create ERC 20 contract admin.
+        let erc_20_admin_secret = SecretKey::ed25519_from_bytes([2u8; 32]).unwrap();
+        let erc_20_admin_key: PublicKey = (&erc_20_admin_secret).into();
+        let erc_20_admin_addr = AccountHash::from(&erc_20_admin_key);
+
+        // Create user.
+        let user_secret = SecretKey::ed25519_from_bytes([4u8; 32]).unwrap();
+        let user_key: PublicKey = (&user_secret).into();
+        let user_addr = AccountHash::from(&user_key);
+
+        // Create user.
+        let user_secret_2 = SecretKey::ed25519_from_bytes([5u8; 32]).unwrap();
+        let user_key_2: PublicKey = (&user_secret_2).into();
+        let user_addr_2 = AccountHash::from(&user_key_2);
+
+        // Create context.
+        let mut context = TestContextBuilder::new()
+            .with_public_key(admin_key.clone(), U512::from(500_000_000_000_000_000u64))
+            .with_public_key(
+                erc_20_admin_key.clone(),
+                U512::from(500_000_000_000_000_000u64),
+            )
+            .with_public_key(user_key.clone(), U512::from(500_000_000_000_000_000u64))
+            .with_public_key(user_key_2.clone(), U512::from(500_000_000_000_000_000u64))
+            .build();
+
+        // Deploy the ERC 20 contract onto the context.
+        let erc_20_contract_hash: ContractHash = install_erc_20(&mut context, erc_20_admin_addr);
+
+        // Deploy the EIP 1337 contract onto the context.
+        let session_code = Code::from(EIP_1337_WASM);
+
+        let mut session_args = runtime_args! {
+            TO => user_addr_2,
+            TOKEN_AMOUNT => U256::from(TOKEN_AMOUNT_VALUE),
+            PERIOD_SECONDS => period_seconds,
+            ERC20_CONTRACT_HASH => Key::Hash(erc_20_contract_hash.value()),
+        };
+        if let Some(dispute_window_seconds) = dispute_window_seconds {
+            session_args.insert(DISPUTE_WINDOW_SECONDS, dispute_window_seconds).unwrap();
+        }
+        if let Some((interval_type, billing_day)) = billing_interval {
+            session_args.insert(INTERVAL_TYPE, interval_type as u8).unwrap();
+            session_args.insert(BILLING_DAY, billing_day).unwrap();
+        }
+        if seat_based {
+            session_args.insert(SEAT_BASED, true).unwrap();
+        }
+
+        let session = SessionBuilder::new(session_code, session_args)
+            .with_address(admin_addr)
+            .with_authorization_keys(&[admin_addr])
+            .build();
+
+        context.run(session);
+
+        let contract_package_hash: Hash = context
+            .query(
+                admin_addr,
+                &[format!("{}-latest-version-contract-hash", CONTRACT_NAME)],
+            )
+            .unwrap()
+            .into_t()
+            .unwrap();
+
+        Subscription {
+            context,
+            block_time: 0,
+            eip_1337_admin: admin_addr,
+            eip_1337_admin_pk: admin_key,
+            eip_1337_contract_hash: ContractPackageHash::from(contract_package_hash),
+            erc_20_admin: erc_20_admin_addr,
+            erc_20_admin_pk: erc_20_admin_key,
+            erc_20_contract_hash,
+            user_from: user_addr,
+            user_from_pk: user_key,
+            user_to: user_addr_2,
+            user_to_pk: user_key_2,
+        }
+    }
+
+    /// Install another ERC-20 token whose whole supply is held by `installer`, e.g. to offer it
+    /// with `add_token`. Each account can install one token.
+    pub fn install_token(&mut self, installer: AccountHash) -> ContractHash {
+        install_erc_20(&mut self.context, installer)
+    }
+
+    /// Install the gated-content example contract, giving access to the subscribers of this
+    /// EIP-1337 contract. Each account can install one.
+    pub fn install_gated_content(&mut self, installer: AccountHash) -> ContractHash {
+        let session_args = runtime_args! {
+            "eip1337_package_hash" => Key::Hash(self.package_hash().value()),
+        };
+
+        let session = SessionBuilder::new(Code::from(GATED_CONTENT_WASM), session_args)
+            .with_address(installer)
+            .with_authorization_keys(&[installer])
+            .build();
+
+        self.context.run(session);
+
+        self.context
+            .get_account(installer)
+            .unwrap()
+            .named_keys()
+            .get(GATED_CONTENT_CONTRACT_NAME)
+            .unwrap()
+            .normalize()
+            .into_hash()
+            .unwrap()
+            .into()
+    }
+
+    /// Call `read_content` on a gated-content contract.
+    pub fn read_content(&mut self, caller: AccountHash, gated_content_contract_hash: ContractHash) -> Result<(), CallError> {
+        let code = Code::Hash(gated_content_contract_hash.value(), "read_content".to_string());
+        let session = SessionBuilder::new(code, runtime_args! {})
+            .with_address(caller)
+            .with_authorization_keys(&[caller])
+            .with_block_time(self.block_time)
+            .build();
+        run(&mut self.context, session)
+    }
+
+    /// The number of reads of the gated-content contract `installer` installed.
+    pub fn gated_content_reads(&self, installer: AccountHash) -> u64 {
+        self.context
+            .query(installer, &[GATED_CONTENT_CONTRACT_NAME.to_string(), "reads".to_string()])
+            .unwrap()
+            .into_t()
+            .unwrap()
+    }
+
+    /// The underlying test context, to run deploys of other contracts against the same state.
+    pub fn context(&mut self) -> &mut TestContext {
+        &mut self.context
+    }
+
+    /// Run the following calls in blocks at `block_time` milliseconds, 0 until set.
+    pub fn set_block_time(&mut self, block_time: u64) {
+        self.block_time = block_time;
+    }
+
+    /// Call an entry point of the EIP-1337 contract.
+    pub fn call(&mut self, caller: &AccountHash, function: &str, args: RuntimeArgs) -> Result<(), CallError> {
+        let code = Code::Hash(self.eip_1337_contract_hash.value(), function.to_string());
+        let session = SessionBuilder::new(code, args)
+            .with_address(*caller)
+            .with_authorization_keys(&[*caller])
+            .with_block_time(self.block_time)
+            .build();
+        run(&mut self.context, session)
+    }
+
+    /// Call the view `entry_point` of the contract as `caller` through the view-probe session
+    /// code, see `eip1337-view-probe`, and return the value it stored under the named key of the
+    /// same name in the caller's account.
+    pub fn view<T: CLTyped + FromBytes>(&mut self, caller: AccountHash, entry_point: &str, mut args: RuntimeArgs) -> Result<T, CallError> {
+        args.insert("eip1337_package_hash", Key::Hash(self.package_hash().value())).unwrap();
+        args.insert("entry_point", entry_point.to_string()).unwrap();
+
+        let session = SessionBuilder::new(Code::from(VIEW_PROBE_WASM), args)
+            .with_address(caller)
+            .with_authorization_keys(&[caller])
+            .with_block_time(self.block_time)
+            .build();
+        run(&mut self.context, session)?;
+
+        Ok(self.context.query(caller, &[entry_point.to_string()]).unwrap().into_t().unwrap())
+    }
+
+    /// Call an entry point of the ERC-20 contract.
+    pub fn call_erc_20(&mut self, caller: &AccountHash, function: &str, args: RuntimeArgs) -> Result<(), CallError> {
+        let erc_20_contract_hash = self.erc_20_contract_hash;
+        self.call_token(caller, erc_20_contract_hash, function, args)
+    }
+
+    /// Call an entry point of any ERC-20 token.
+    pub fn call_token(
+        &mut self,
+        caller: &AccountHash,
+        erc20_contract_hash: ContractHash,
+        function: &str,
+        args: RuntimeArgs,
+    ) -> Result<(), CallError> {
+        let code = Code::Hash(erc20_contract_hash.value(), function.to_string());
+        let session = SessionBuilder::new(code, args)
+            .with_address(*caller)
+            .with_authorization_keys(&[*caller])
+            .with_block_time(self.block_time)
+            .build();
+        run(&mut self.context, session)
+    }
+
+    /// Read a named key of the EIP-1337 contract.
+    pub fn query_contract<T: CLTyped + FromBytes>(&self, name: &str) -> Option<T> {
+        match self.context.query(
+            self.eip_1337_admin,
+            &[format!("{}-latest-version-contract", CONTRACT_NAME), name.to_string()],
+        ) {
+            Err(_) => None,
+            Ok(maybe_value) => {
+                let value = maybe_value
+                    .into_t()
+                    .unwrap();
+                Some(value)
+            }
+        }
+    }
+
+    /// Read a named key of the ERC-20 contract.
+    pub fn query_contract_erc20<T: CLTyped + FromBytes>(&self, name: &str) -> Option<T> {
+        match self.context.query(
+            self.erc_20_admin,
+            &[ERC20_CONTRACT_NAME.to_string(), name.to_string()],
+        ) {
+            Err(_) => None,
+            Ok(maybe_value) => {
+                let value = maybe_value
+                    .into_t()
+                    .unwrap();
+                Some(value)
+            }
+        }
+    }
+
+    /// Read an item of an EIP-1337 dictionary, see "Reading State Off-Chain" in the README for the keys.
+    pub fn query_dictionary_value<T: CLTyped + FromBytes>(
+        &self,
+        dict_name: &str,
+        key: &str,
+    ) -> Option<T> {
+        match self.context.query_dictionary_item(
+            Key::from(self.eip_1337_contract_hash),
+            Some(dict_name.to_string()),
+            key.to_string(),
+        ) {
+            Err(_) => None,
+            Ok(maybe_value) => {
+                let value: T = maybe_value
+                    .into_t()
+                    .unwrap();
+                Option::Some(value)
+            }
+        }
+    }
+
+    /// Read an item of an ERC-20 dictionary.
+    pub fn query_dictionary_value_erc20<T: CLTyped + FromBytes>(
+        &self,
+        dict_name: &str,
+        key: &str,
+    ) -> Option<T> {
+        self.query_token_dictionary_value(self.erc_20_contract_hash, dict_name, key)
+    }
+
+    /// Read an item of a dictionary of any ERC-20 token.
+    pub fn query_token_dictionary_value<T: CLTyped + FromBytes>(
+        &self,
+        erc20_contract_hash: ContractHash,
+        dict_name: &str,
+        key: &str,
+    ) -> Option<T> {
+        match self.context.query_dictionary_item(
+            Key::from(erc20_contract_hash),
+            Some(dict_name.to_string()),
+            key.to_string(),
+        ) {
+            Err(_) => None,
+            Ok(maybe_value) => {
+                let value: T = maybe_value
+                    .into_t()
+                    .unwrap();
+                Option::Some(value)
+            }
+        }
+    }
+
+    /// The ERC-20 balance of an account.
+    pub fn balance_of(&self, account: AccountHash) -> U256 {
+        self.token_balance_of(self.erc_20_contract_hash, account)
+    }
+
+    /// The balance of an account in any ERC-20 token.
+    pub fn token_balance_of(&self, erc20_contract_hash: ContractHash, account: AccountHash) -> U256 {
+        let key_bytes = Key::Account(account).to_bytes().unwrap();
+
+        self.query_token_dictionary_value(erc20_contract_hash, BALANCES_KEY_NAME, &base64::encode(&key_bytes))
+            .unwrap_or_default()
+    }
+
+    /// The balance of the contract package in any ERC-20 token, the payments it holds in escrow.
+    pub fn package_token_balance(&self, erc20_contract_hash: ContractHash) -> U256 {
+        let key_bytes = Key::Hash(self.package_hash().value()).to_bytes().unwrap();
+
+        self.query_token_dictionary_value(erc20_contract_hash, BALANCES_KEY_NAME, &base64::encode(&key_bytes))
+            .unwrap_or_default()
+    }
+
+    /// Give the subscriber `balance` tokens and approve the contract package to spend `allowance` of them.
+    pub fn fund_and_approve(&mut self, balance: u64, allowance: u64) -> Result<(), CallError> {
+        let erc_20_contract_hash = self.erc_20_contract_hash;
+        let erc_20_admin = self.erc_20_admin;
+        self.fund_and_approve_token(erc_20_contract_hash, erc_20_admin, balance, allowance)
+    }
+
+    /// Have `token_admin` give the subscriber `balance` tokens of any ERC-20 token, and approve
+    /// the contract package to spend `allowance` of them.
+    pub fn fund_and_approve_token(
+        &mut self,
+        erc20_contract_hash: ContractHash,
+        token_admin: AccountHash,
+        balance: u64,
+        allowance: u64,
+    ) -> Result<(), CallError> {
+        let user_from = self.user_from;
+        let eip_1337_contract_hash = self.eip_1337_contract_hash;
+
+        // Give the owner `balance` tokens
+        self.call_token(
+            &token_admin,
+            erc20_contract_hash,
+            TRANSFER_ENTRY_POINT_NAME,
+            runtime_args! {
+                RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from),
+                AMOUNT_RUNTIME_ARG_NAME => U256::from(balance),
+            },
+        )?;
+
+        // Give the spender contract permission to spend `allowance` tokens
+        self.call_token(
+            &user_from,
+            erc20_contract_hash,
+            APPROVE_ENTRY_POINT_NAME,
+            runtime_args! {
+                SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash),
+                AMOUNT_RUNTIME_ARG_NAME => U256::from(allowance),
+            },
+        )
+    }
+
+    pub fn to(&self) -> AccountHash {
+        self.query_contract(TO).unwrap()
+    }
+
+    pub fn token_amount(&self) -> U256 {
+        self.query_contract(TOKEN_AMOUNT).unwrap()
+    }
+
+    pub fn period_seconds(&self) -> u64 {
+        self.query_contract(PERIOD_SECONDS).unwrap()
+    }
+
+    /// The interval type of the plan, `IntervalType::Seconds` unless installed with another.
+    pub fn interval_type(&self) -> Option<IntervalType> {
+        IntervalType::from_u8(self.query_contract(INTERVAL_TYPE).unwrap())
+    }
+
+    pub fn billing_day(&self) -> u8 {
+        self.query_contract(BILLING_DAY).unwrap()
+    }
+
+    pub fn seat_based(&self) -> bool {
+        self.query_contract(SEAT_BASED).unwrap()
+    }
+
+    /// The accepted ERC-20 tokens and the price of a period in each, without the price changes in
+    /// [`Subscription::pending_prices`] that took effect since they were written.
+    pub fn token_prices(&self) -> BTreeMap<ContractHash, U256> {
+        self.query_contract(TOKEN_PRICES).unwrap()
+    }
+
+    /// The price changes scheduled for each ERC-20 token, with the blocktime they take effect at,
+    /// including those that took effect since the last change was scheduled.
+    pub fn pending_prices(&self) -> BTreeMap<ContractHash, (U256, u64)> {
+        self.query_contract(PENDING_PRICES).unwrap()
+    }
+
+    /// The price of a unit of usage in each ERC-20 token that has one.
+    pub fn unit_prices(&self) -> BTreeMap<ContractHash, U256> {
+        self.query_contract(UNIT_PRICES).unwrap()
+    }
+
+    pub fn usage_oracle(&self) -> AccountHash {
+        self.query_contract(USAGE_ORACLE).unwrap()
+    }
+
+    pub fn payees(&self) -> BTreeMap<AccountHash, u32> {
+        self.query_contract(PAYEES).unwrap()
+    }
+
+    /// The contract package, which holds escrowed payments.
+    pub fn package_hash(&self) -> ContractPackageHash {
+        self.query_contract(PACKAGE_HASH).unwrap()
+    }
+
+    pub fn escrow_held(&self) -> BTreeMap<ContractHash, U256> {
+        self.query_contract(ESCROW_HELD).unwrap()
+    }
+
+    pub fn escrow_payment_count(&self) -> u64 {
+        self.query_contract(ESCROW_PAYMENT_COUNT).unwrap()
+    }
+
+    pub fn escrow_payment_state(&self, payment_id: u64) -> Option<u8> {
+        self.query_dictionary_value(ESCROW_STATES_DICT, &payment_id.to_string())
+    }
+
+    /// Escrow balances of an account still within a dispute window, as of the last release.
+    pub fn escrow_pending(&self, account: AccountHash) -> BTreeMap<ContractHash, U256> {
+        self.query_dictionary_value(ESCROW_PENDING_DICT, &account_key(account)).unwrap_or_default()
+    }
+
+    /// Escrow balances an account can withdraw, as of the last release.
+    pub fn escrow_available(&self, account: AccountHash) -> BTreeMap<ContractHash, U256> {
+        self.query_dictionary_value(ESCROW_AVAILABLE_DICT, &account_key(account)).unwrap_or_default()
+    }
+
+    /// How many subscriptions redeemed a coupon.
+    pub fn coupon_redemptions(&self, coupon_code: &str) -> Option<u64> {
+        self.query_dictionary_value(COUPON_REDEMPTIONS_DICT, coupon_code)
+    }
+
+    /// The discounted payments left of a subscription that redeemed a coupon.
+    pub fn discounted_periods(&self, subscription_id: u64) -> Option<u64> {
+        self.query_dictionary_value(DISCOUNTED_PERIODS_DICT, &subscription_id.to_string())
+    }
+
+    /// Units of usage reported for a metered subscription since its last payment.
+    pub fn usage(&self, subscription_id: u64) -> Option<u64> {
+        self.query_dictionary_value(USAGES_DICT, &subscription_id.to_string())
+    }
+
+    /// What a subscription has paid, less refunded and charged back payments.
+    pub fn amount_paid(&self, subscription_id: u64) -> Option<U256> {
+        self.query_dictionary_value(AMOUNTS_PAID_DICT, &subscription_id.to_string())
+    }
+
+    /// The account given access to a subscription another account pays for, `None` otherwise.
+    pub fn beneficiary(&self, subscription_id: u64) -> Option<AccountHash> {
+        self.query_dictionary_value(BENEFICIARIES_DICT, &subscription_id.to_string())
+    }
+
+    /// The accounts holding a seat of a subscription to a seat-based plan, in the order they were added.
+    pub fn members(&self, subscription_id: u64) -> Vec<AccountHash> {
+        self.query_dictionary_value(MEMBERS_DICT, &subscription_id.to_string()).unwrap_or_default()
+    }
+
+    /// The prorated charges of the seats removed and credits of the seats added since the last payment.
+    pub fn seat_proration(&self, subscription_id: u64) -> (U256, U256) {
+        let key = subscription_id.to_string();
+        (
+            self.query_dictionary_value(SEAT_CHARGES_DICT, &key).unwrap_or_default(),
+            self.query_dictionary_value(SEAT_CREDITS_DICT, &key).unwrap_or_default(),
+        )
+    }
+
+    pub fn subscription_hash(&self, subscription_id: u64) -> Option<String> {
+        self.query_dictionary_value(HASHES_DICT, &subscription_id.to_string())
+    }
+
+    pub fn next_valid_timestamp(&self, subscription_id: u64) -> Option<u64> {
+        self.query_dictionary_value(NEXT_VALID_TIMESTAMPS_DICT, &subscription_id.to_string())
+    }
+
+    pub fn subscription_count(&self) -> u64 {
+        self.query_contract(SUBSCRIPTION_COUNT).unwrap()
+    }
+
+    pub fn account_subscriptions(&self, account: AccountHash) -> Vec<u64> {
+        self.query_dictionary_value(ACCOUNT_SUBSCRIPTIONS_DICT, &account.to_string()).unwrap_or_default()
+    }
+
+    /// The ids of the subscriptions an account receives as beneficiary or holds a seat of without
+    /// paying for them.
+    pub fn account_access(&self, account: AccountHash) -> Vec<u64> {
+        self.query_dictionary_value(ACCOUNT_ACCESS_DICT, &account.to_string()).unwrap_or_default()
+    }
+
+    pub fn subscriber_count(&self) -> u64 {
+        self.query_contract(SUBSCRIBER_COUNT).unwrap()
+    }
+
+    pub fn subscriber(&self, index: u64) -> Option<AccountHash> {
+        self.query_dictionary_value(SUBSCRIBERS_DICT, &index.to_string())
+    }
+
+    /// Register a new subscription, returning its id and the subscription hash to sign.
+    pub fn create_subscription_hash(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        self.call(
+            &caller,
+            "create_subscription_hash",
+            runtime_args! {
+                "public" => public,
+                "from" => from,
+            },
+        )?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Register a new subscription paid in one of the accepted tokens, returning its id and the
+    /// subscription hash to sign.
+    pub fn create_subscription_hash_with_token(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+        erc20_contract_hash: ContractHash,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        self.call(
+            &caller,
+            "create_subscription_hash",
+            runtime_args! {
+                "public" => public,
+                "from" => from,
+                "erc20_contract_hash" => Key::from(erc20_contract_hash),
+            },
+        )?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Register a subscription in the token set at install, redeeming a coupon.
+    pub fn create_subscription_hash_with_coupon(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+        coupon_code: &str,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        self.call(
+            &caller,
+            "create_subscription_hash",
+            runtime_args! {
+                "public" => public,
+                "from" => from,
+                "coupon_code" => coupon_code.to_string(),
+            },
+        )?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Register a subscription in the token set at install with the spending caps the subscriber
+    /// signs, returning its id and the subscription hash to sign.
+    pub fn create_subscription_hash_with_caps(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+        max_amount_per_period: Option<U256>,
+        lifetime_cap: Option<U256>,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        let mut args = runtime_args! {
+            "public" => public,
+            "from" => from,
+        };
+        if let Some(max_amount_per_period) = max_amount_per_period {
+            args.insert("max_amount_per_period", max_amount_per_period).unwrap();
+        }
+        if let Some(lifetime_cap) = lifetime_cap {
+            args.insert("lifetime_cap", lifetime_cap).unwrap();
+        }
+
+        self.call(&caller, "create_subscription_hash", args)?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Register a metered subscription in the token set at install, charging usage on top of the
+    /// price within `max_amount_per_period`, returning its id and the subscription hash to sign.
+    pub fn create_metered_subscription_hash(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+        max_amount_per_period: U256,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        self.call(
+            &caller,
+            "create_subscription_hash",
+            runtime_args! {
+                "public" => public,
+                "from" => from,
+                "max_amount_per_period" => max_amount_per_period,
+                "metered" => true
+            },
+        )?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Register a subscription `from` pays for and `beneficiary` is given access to, returning its
+    /// id and the subscription hash for `from` to sign.
+    pub fn create_subscription_hash_with_beneficiary(
+        &mut self,
+        caller: AccountHash,
+        public: PublicKey,
+        from: AccountHash,
+        beneficiary: AccountHash,
+    ) -> Result<(u64, String), CallError> {
+        let subscription_id = self.subscription_count();
+
+        self.call(
+            &caller,
+            "create_subscription_hash",
+            runtime_args! {
+                "public" => public,
+                "from" => from,
+                "beneficiary" => beneficiary
+            },
+        )?;
+
+        Ok((subscription_id, self.subscription_hash(subscription_id).unwrap()))
+    }
+
+    /// Charge metered subscriptions `unit_price` a unit of usage, only the publisher may.
+    pub fn set_unit_price(
+        &mut self,
+        caller: AccountHash,
+        erc20_contract_hash: ContractHash,
+        unit_price: U256,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "set_unit_price",
+            runtime_args! {
+                "erc20_contract_hash" => Key::from(erc20_contract_hash),
+                "unit_price" => unit_price,
+            },
+        )
+    }
+
+    pub fn get_unit_prices(&mut self, caller: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_unit_prices",
+            runtime_args! {},
+        )
+    }
+
+    pub fn set_usage_oracle(&mut self, caller: AccountHash, oracle: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "set_usage_oracle",
+            runtime_args! {
+                "oracle" => oracle,
+            },
+        )
+    }
+
+    /// Report units of usage of a metered subscription, only the publisher or the usage oracle may.
+    pub fn report_usage(&mut self, caller: AccountHash, subscription_id: u64, usage: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "report_usage",
+            runtime_args! {
+                "subscription_id" => subscription_id,
+                "usage" => usage,
+            },
+        )
+    }
+
+    /// Give an account a seat of a subscription to a seat-based plan, only its subscriber may.
+    pub fn add_member(&mut self, caller: AccountHash, subscription_id: u64, member: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "add_member",
+            runtime_args! {
+                "subscription_id" => subscription_id,
+                "member" => member,
+            },
+        )
+    }
+
+    /// Take the seat of a member of a subscription to a seat-based plan, only its subscriber may.
+    pub fn remove_member(&mut self, caller: AccountHash, subscription_id: u64, member: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "remove_member",
+            runtime_args! {
+                "subscription_id" => subscription_id,
+                "member" => member,
+            },
+        )
+    }
+
+    /// Create a coupon, only the publisher may.
+    pub fn add_coupon(&mut self, caller: AccountHash, coupon_code: &str, coupon: Coupon) -> Result<(), CallError> {
+        let mut args = runtime_args! {
+            "coupon_code" => coupon_code.to_string(),
+            "discount_type" => coupon.discount_type as u8,
+            "discount" => coupon.discount,
+            "periods" => coupon.periods,
+            "max_redemptions" => coupon.max_redemptions,
+            "expires_at" => coupon.expires_at,
+        };
+        if let Some(erc20_contract_hash) = coupon.erc20_contract_hash {
+            args.insert("erc20_contract_hash", Key::from(erc20_contract_hash)).unwrap();
+        }
+
+        self.call(&caller, "add_coupon", args)
+    }
+
+    pub fn expire_coupon(&mut self, caller: AccountHash, coupon_code: &str) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "expire_coupon",
+            runtime_args! {
+                "coupon_code" => coupon_code.to_string(),
+            },
+        )
+    }
+
+    /// Accept another ERC-20 token at `token_amount` a period, only the publisher may.
+    pub fn add_token(
+        &mut self,
+        caller: AccountHash,
+        erc20_contract_hash: ContractHash,
+        token_amount: U256,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "add_token",
+            runtime_args! {
+                "erc20_contract_hash" => Key::from(erc20_contract_hash),
+                "token_amount" => token_amount,
+            },
+        )
+    }
+
+    pub fn get_token_prices(&mut self, caller: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_token_prices",
+            runtime_args! {},
+        )
+    }
+
+    pub fn schedule_price_change(
+        &mut self,
+        caller: AccountHash,
+        erc20_contract_hash: ContractHash,
+        token_amount: U256,
+        effective_at: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "schedule_price_change",
+            runtime_args! {
+                "erc20_contract_hash" => Key::from(erc20_contract_hash),
+                "token_amount" => token_amount,
+                "effective_at" => effective_at,
+            },
+        )
+    }
+
+    pub fn get_pending_prices(&mut self, caller: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_pending_prices",
+            runtime_args! {},
+        )
+    }
+
+    pub fn set_payee(&mut self, caller: AccountHash, recipient: AccountHash, basis_points: u32) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "set_payee",
+            runtime_args! {
+                "recipient" => recipient,
+                "basis_points" => basis_points,
+            },
+        )
+    }
+
+    pub fn get_payees(&mut self, caller: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_payees",
+            runtime_args! {},
+        )
+    }
+
+    pub fn withdraw(&mut self, caller: AccountHash, erc20_contract_hash: ContractHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "withdraw",
+            runtime_args! {
+                "erc20_contract_hash" => Key::from(erc20_contract_hash),
+            },
+        )
+    }
+
+    pub fn refund_payment(&mut self, caller: AccountHash, payment_id: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "refund_payment",
+            runtime_args! {
+                "payment_id" => payment_id,
+            },
+        )
+    }
+
+    pub fn chargeback_payment(&mut self, caller: AccountHash, payment_id: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "chargeback_payment",
+            runtime_args! {
+                "payment_id" => payment_id,
+            },
+        )
+    }
+
+    pub fn get_escrow_balance(&mut self, caller: AccountHash, account: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_escrow_balance",
+            runtime_args! {
+                "account" => account,
+            },
+        )
+    }
+
+    pub fn get_escrow_held(&mut self, caller: AccountHash) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_escrow_held",
+            runtime_args! {},
+        )
+    }
+
+    pub fn get_escrow_payment(&mut self, caller: AccountHash, payment_id: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_escrow_payment",
+            runtime_args! {
+                "payment_id" => payment_id,
+            },
+        )
+    }
+
+    pub fn get_subscription_hash(&mut self, caller: AccountHash, subscription_id: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_subscription_hash",
+            runtime_args! {
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn execute_subscription(
+        &mut self,
+        caller: AccountHash,
+        signature: String,
+        subscription_id: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "execute_subscription",
+            runtime_args! {
+                "signature" => signature,
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn cancel_subscription(
+        &mut self,
+        caller: AccountHash,
+        signature: String,
+        subscription_id: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "cancel_subscription",
+            runtime_args! {
+                "signature" => signature,
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn is_subscription_ready(
+        &mut self,
+        caller: AccountHash,
+        signature: String,
+        subscription_id: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "is_subscription_ready",
+            runtime_args! {
+                "signature" => signature,
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn is_subscription_active(
+        &mut self,
+        caller: AccountHash,
+        subscription_hash: String,
+        grace_period_seconds: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "is_subscription_active",
+            runtime_args! {
+                "subscription_hash" => subscription_hash,
+                "grace_period_seconds" => grace_period_seconds
+            },
+        )
+    }
+
+    /// Check a subscription is active and gives `beneficiary` access.
+    pub fn is_subscription_active_for(
+        &mut self,
+        caller: AccountHash,
+        subscription_hash: String,
+        grace_period_seconds: u64,
+        beneficiary: AccountHash,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "is_subscription_active",
+            runtime_args! {
+                "subscription_hash" => subscription_hash,
+                "grace_period_seconds" => grace_period_seconds,
+                "beneficiary" => beneficiary
+            },
+        )
+    }
+
+    pub fn get_subscription(&mut self, caller: AccountHash, subscription_id: u64) -> Result<BTreeMap<String, String>, CallError> {
+        self.view(
+            caller,
+            "get_subscription",
+            runtime_args! {
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn get_next_payment_time(&mut self, caller: AccountHash, subscription_id: u64) -> Result<u64, CallError> {
+        self.view(
+            caller,
+            "get_next_payment_time",
+            runtime_args! {
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn get_subscription_status(&mut self, caller: AccountHash, subscription_id: u64) -> Result<u8, CallError> {
+        self.view(
+            caller,
+            "get_subscription_status",
+            runtime_args! {
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn get_account_subscriptions(&mut self, caller: AccountHash, from: AccountHash) -> Result<Vec<u64>, CallError> {
+        self.view(
+            caller,
+            "get_account_subscriptions",
+            runtime_args! {
+                "from" => from
+            },
+        )
+    }
+
+    pub fn get_active_subscription(
+        &mut self,
+        caller: AccountHash,
+        account: AccountHash,
+        grace_period_seconds: u64,
+    ) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_active_subscription",
+            runtime_args! {
+                "account" => account,
+                "grace_period_seconds" => grace_period_seconds
+            },
+        )
+    }
+
+    pub fn get_members(&mut self, caller: AccountHash, subscription_id: u64) -> Result<(), CallError> {
+        self.call(
+            &caller,
+            "get_members",
+            runtime_args! {
+                "subscription_id" => subscription_id
+            },
+        )
+    }
+
+    pub fn get_plan(&mut self, caller: AccountHash) -> Result<BTreeMap<String, String>, CallError> {
+        self.view(
+            caller,
+            "get_plan",
+            runtime_args! {},
+        )
+    }
+
+    pub fn get_subscriber_count(&mut self, caller: AccountHash) -> Result<u64, CallError> {
+        self.view(
+            caller,
+            "get_subscriber_count",
+            runtime_args! {},
+        )
+    }
+
+    pub fn get_subscription_count(&mut self, caller: AccountHash) -> Result<u64, CallError> {
+        self.view(
+            caller,
+            "get_subscription_count",
+            runtime_args! {},
+        )
+    }
+
+    pub fn list_subscribers(
+        &mut self,
+        caller: AccountHash,
+        offset: u64,
+        limit: u64,
+        status: Option<u8>,
+    ) -> Result<(), CallError> {
+        let mut args = runtime_args! {
+            "offset" => offset,
+            "limit" => limit,
+        };
+        if let Some(status) = status {
+            args.insert("status", status).unwrap();
+        }
+        self.call(&caller, "list_subscribers", args)
+    }
+
+    pub fn list_subscriptions(
+        &mut self,
+        caller: AccountHash,
+        offset: u64,
+        limit: u64,
+        status: Option<u8>,
+    ) -> Result<(), CallError> {
+        let mut args = runtime_args! {
+            "offset" => offset,
+            "limit" => limit,
+        };
+        if let Some(status) = status {
+            args.insert("status", status).unwrap();
+        }
+        self.call(&caller, "list_subscriptions", args)
+    }
+}
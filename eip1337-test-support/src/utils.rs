@@ -0,0 +1,41 @@
+use types::{account::AccountHash, ContractHash, SecretKey, U256};
+
+use crate::{hash, terms::SubscriptionTerms};
+
+/// The secret key of the account that installs the EIP-1337 contract.
+pub fn generate_eip_1337_secret_key() -> SecretKey {
+    SecretKey::ed25519_from_bytes([1u8; 32]).unwrap()
+}
+
+/// The message the contract hashes for a subscription without spending caps, beneficiary or metering, see `get_subscription_data` in the contract.
+pub fn get_subscription_data(
+    from: AccountHash,
+    to: AccountHash,
+    token_amount: U256,
+    period_seconds: u64,
+    subscription_id: u64,
+    erc20_contract_hash: ContractHash,
+) -> String {
+    SubscriptionTerms {
+        to,
+        from,
+        token_amount,
+        period_seconds,
+        subscription_id,
+        erc20_contract_hash,
+        max_amount_per_period: None,
+        lifetime_cap: None,
+        beneficiary: None,
+        metered: false,
+    }
+    .data()
+}
+
+/// The 32 byte blake2b hash of a message, as `runtime::blake2b` computes it.
+pub fn get_hash_bytes(data: String) -> [u8; 32] {
+    hash::blake2b(data)
+}
+
+pub fn get_hex(bytes: [u8; 32]) -> String {
+    hex::encode(bytes)
+}
@@ -0,0 +1,59 @@
+//! Session code calling a view entry point of the EIP-1337 contract and storing its return value
+//! under a named key of the calling account with the name of the entry point, for the tests to
+//! read. Deploys can not read return values in this engine version.
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{
+    account::AccountHash, bytesrepr::{FromBytes, ToBytes}, runtime_args, ApiError, CLTyped,
+    ContractPackageHash, Key, RuntimeArgs,
+};
+
+/// Hash key of the package of the EIP-1337 contract to call.
+const EIP1337_PACKAGE_HASH: &str = "eip1337_package_hash";
+/// The view entry point to call, also the named key its return value is stored under.
+const ENTRY_POINT: &str = "entry_point";
+const SUBSCRIPTION_ID: &str = "subscription_id";
+const FROM: &str = "from";
+
+/// Call `entry_point` of the latest version of the contract with `args` and store the result.
+fn probe<T: CLTyped + FromBytes + ToBytes>(eip1337_package: ContractPackageHash, entry_point: &str, args: RuntimeArgs) {
+    let value: T = runtime::call_versioned_contract(eip1337_package, None, entry_point, args);
+
+    runtime::put_key(entry_point, storage::new_uref(value).into());
+}
+
+// All session code must have a `call` entrypoint,
+#[no_mangle]
+fn call() {
+    let eip1337_package: Key = runtime::get_named_arg(EIP1337_PACKAGE_HASH);
+    let eip1337_package = ContractPackageHash::new(eip1337_package.into_hash().unwrap_or_revert_with(ApiError::UnexpectedKeyVariant));
+    let entry_point: String = runtime::get_named_arg(ENTRY_POINT);
+
+    match entry_point.as_str() {
+        "get_subscription" => {
+            let subscription_id: u64 = runtime::get_named_arg(SUBSCRIPTION_ID);
+            probe::<BTreeMap<String, String>>(eip1337_package, &entry_point, runtime_args! { SUBSCRIPTION_ID => subscription_id });
+        }
+        "get_next_payment_time" => {
+            let subscription_id: u64 = runtime::get_named_arg(SUBSCRIPTION_ID);
+            probe::<u64>(eip1337_package, &entry_point, runtime_args! { SUBSCRIPTION_ID => subscription_id });
+        }
+        "get_subscription_status" => {
+            let subscription_id: u64 = runtime::get_named_arg(SUBSCRIPTION_ID);
+            probe::<u8>(eip1337_package, &entry_point, runtime_args! { SUBSCRIPTION_ID => subscription_id });
+        }
+        "get_account_subscriptions" => {
+            let from: AccountHash = runtime::get_named_arg(FROM);
+            probe::<Vec<u64>>(eip1337_package, &entry_point, runtime_args! { FROM => from });
+        }
+        "get_plan" => probe::<BTreeMap<String, String>>(eip1337_package, &entry_point, runtime_args! {}),
+        "get_subscriber_count" | "get_subscription_count" => probe::<u64>(eip1337_package, &entry_point, runtime_args! {}),
+        _ => runtime::revert(ApiError::InvalidArgument),
+    }
+}
@@ -0,0 +1,10 @@
+#![no_main]
+use contract::{
+    contract_api::{runtime},
+};
+
+// All session code must have a `call` entrypoint,
+#[no_mangle]
+fn call() {
+    gated_content_example::install(runtime::get_named_arg(gated_content_example::EIP1337_PACKAGE_HASH));
+}
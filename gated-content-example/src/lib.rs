@@ -0,0 +1,77 @@
+//! Example contract gating content on an EIP-1337 subscription with the `eip1337-access` crate.
+//! Only accounts with an active subscription can call `read_content`, which counts the reads.
+
+use contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{
+    contracts::{EntryPoint, EntryPointAccess, EntryPointType, EntryPoints, NamedKeys},
+    bytesrepr::FromBytes,
+    ApiError, CLTyped, CLValue, ContractPackageHash, Key, URef,
+};
+
+pub const CONTRACT_NAME: &str = "gated-content";
+/// Named key holding the package of the EIP-1337 contract giving access.
+pub const EIP1337_PACKAGE_HASH: &str = "eip1337_package_hash";
+/// Named key holding the number of times the content was read.
+pub const READS: &str = "reads";
+
+/// The uref behind a named key of the contract, reverting with `MissingKey` if it is not there.
+fn get_uref(name: &str) -> URef {
+    runtime::get_key(name)
+        .unwrap_or_revert_with(ApiError::MissingKey)
+        .into_uref()
+        .unwrap_or_revert_with(ApiError::UnexpectedKeyVariant)
+}
+
+fn read<T: CLTyped + FromBytes>(uref: URef) -> T {
+    storage::read(uref).unwrap_or_revert().unwrap_or_revert_with(ApiError::ValueNotFound)
+}
+
+/// Serve the content to the caller, reverting with `AccessError::NoActiveSubscription` unless it
+/// has an active subscription. Returns the id of that subscription.
+#[no_mangle]
+pub fn read_content() {
+    let eip1337_package: ContractPackageHash = read(get_uref(EIP1337_PACKAGE_HASH));
+
+    let subscription_id: u64 = eip1337_access::require_active_subscription(eip1337_package, runtime::get_caller()).unwrap_or_revert();
+
+    let reads_uref = get_uref(READS);
+    let reads: u64 = read(reads_uref);
+    storage::write(reads_uref, reads + 1);
+
+    runtime::ret(CLValue::from_t(subscription_id).unwrap_or_revert());
+}
+
+fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(EntryPoint::new(
+        String::from("read_content"),
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points
+}
+
+/// Install the contract, giving access to the subscribers of the EIP-1337 contract in
+/// `eip1337_package`, a hash key. The contract hash is stored in the `gated-content` named key of
+/// the installing account.
+pub fn install(eip1337_package: Key) {
+    let eip1337_package = ContractPackageHash::new(eip1337_package.into_hash().unwrap_or_revert_with(ApiError::UnexpectedKeyVariant));
+
+    let mut named_keys: NamedKeys = Default::default();
+    named_keys.insert(EIP1337_PACKAGE_HASH.to_string(), storage::new_uref(eip1337_package).into());
+    named_keys.insert(READS.to_string(), storage::new_uref(0u64).into());
+
+    let (contract_hash, _version) = storage::new_contract(
+        get_entry_points(),
+        Some(named_keys),
+        Some(format!("{}-package", CONTRACT_NAME)),
+        Some(format!("{}-package-access-uref", CONTRACT_NAME)),
+    );
+
+    runtime::put_key(CONTRACT_NAME, contract_hash.into());
+}
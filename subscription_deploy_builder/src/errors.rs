@@ -0,0 +1,23 @@
+use std::result;
+
+use thiserror::Error;
+
+/// A specialized `std::result::Result` type for deploy building errors.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Deploy building errors.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Error from the casper client while making or signing the deploy.
+    #[error("making deploy: {0}")]
+    Client(#[from] casper_client::Error),
+
+    /// Error resulting when a deploy argument is not valid.
+    #[error("invalid argument {name}: {reason}")]
+    InvalidArgument {
+        /// The name of the argument.
+        name: &'static str,
+        /// Why it was rejected.
+        reason: String,
+    },
+}
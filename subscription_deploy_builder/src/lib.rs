@@ -0,0 +1,515 @@
+//! Builds signed deploys for the EIP-1337 subscription contract.
+//!
+//! Every entry point has a constructor on [`SubscriptionDeploy`] taking typed arguments, and
+//! [`SubscriptionDeploy::write`] signs the deploy and writes it as JSON, ready to be sent by
+//! `casper-client send-deploy` or any other node client.
+
+use casper_client::{DeployStrParams, PaymentStrParams, SessionStrParams};
+
+use types::{
+    account::AccountHash,
+    AsymmetricType, ContractHash, ContractPackageHash, Key, PublicKey, U256,
+};
+
+mod errors;
+pub use errors::{Error, Result};
+
+/// Length of a hex encoded Ed25519 signature.
+const SIGNATURE_HEX_LENGTH: usize = 128;
+/// Length of a hex encoded subscription hash.
+const SUBSCRIPTION_HASH_HEX_LENGTH: usize = 64;
+
+/// Deploy settings shared by every deploy: who signs and pays for it and on which chain.
+#[derive(Debug, Clone)]
+pub struct DeployParams {
+    /// Name of the chain, e.g. `casper-test`.
+    pub chain_name: String,
+    /// Path to the PEM secret key of the account sending the deploy.
+    pub secret_key: String,
+    /// Payment amount in motes.
+    pub payment_amount: u64,
+    /// Time to live, e.g. `30min` or `1day`.
+    pub ttl: String,
+    /// Gas price in motes per unit of gas.
+    pub gas_price: u64,
+}
+
+impl DeployParams {
+    /// Deploy settings with a 30 minute time to live and a gas price of 1.
+    pub fn new(chain_name: &str, secret_key: &str, payment_amount: u64) -> DeployParams {
+        DeployParams {
+            chain_name: chain_name.to_string(),
+            secret_key: secret_key.to_string(),
+            payment_amount,
+            ttl: String::from("30min"),
+            gas_price: 1,
+        }
+    }
+}
+
+/// The terms of a coupon for [`SubscriptionDeploy::add_coupon`]. For `periods`,
+/// `max_redemptions` and `expires_at`, 0 means no limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coupon {
+    /// 0 for a percentage in basis points, 1 for a fixed amount.
+    pub discount_type: u8,
+    /// Basis points out of 10000, or the token amount, taken off a payment.
+    pub discount: U256,
+    /// Token of a fixed amount, the token set at install if `None`.
+    pub erc20_contract_hash: Option<ContractHash>,
+    /// Number of payments discounted.
+    pub periods: u64,
+    /// Number of subscriptions that can redeem the coupon.
+    pub max_redemptions: u64,
+    /// Blocktime in milliseconds from which the coupon can no longer be redeemed.
+    pub expires_at: u64,
+}
+
+/// What the deploy executes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Session {
+    /// Session code from a wasm file, used to install the contract.
+    Wasm { path: String },
+    /// An entry point of a stored contract.
+    StoredContract { contract_hash: ContractHash, entry_point: String },
+}
+
+/// An unsigned deploy of the EIP-1337 contract: its session and runtime arguments.
+///
+/// Arguments are kept in the casper-client `name:type='value'` form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionDeploy {
+    session: Session,
+    args: Vec<String>,
+}
+
+impl SubscriptionDeploy {
+    fn call(contract_hash: ContractHash, entry_point: &str, args: Vec<String>) -> SubscriptionDeploy {
+        SubscriptionDeploy {
+            session: Session::StoredContract {
+                contract_hash,
+                entry_point: entry_point.to_string(),
+            },
+            args,
+        }
+    }
+
+    /// Install the contract from `casper-contract-eip-1337.wasm`.
+    pub fn install(
+        wasm_path: &str,
+        to: AccountHash,
+        token_amount: U256,
+        period_seconds: u64,
+        erc20_contract_hash: ContractHash,
+    ) -> SubscriptionDeploy {
+        SubscriptionDeploy {
+            session: Session::Wasm { path: wasm_path.to_string() },
+            args: vec![
+                account_hash_arg("to", to),
+                arg("token_amount", "u256", token_amount),
+                arg("period_seconds", "u64", period_seconds),
+                erc20_contract_hash_arg(erc20_contract_hash),
+            ],
+        }
+    }
+
+    /// Install the contract with payments held in escrow for `dispute_window_seconds`.
+    pub fn install_with_escrow(
+        wasm_path: &str,
+        to: AccountHash,
+        token_amount: U256,
+        period_seconds: u64,
+        erc20_contract_hash: ContractHash,
+        dispute_window_seconds: u64,
+    ) -> SubscriptionDeploy {
+        let mut deploy = SubscriptionDeploy::install(wasm_path, to, token_amount, period_seconds, erc20_contract_hash);
+        deploy.args.push(arg("dispute_window_seconds", "u64", dispute_window_seconds));
+        deploy
+    }
+
+    /// Bill the plan of an install deploy on calendar periods instead of every `period_seconds`:
+    /// `interval_type` 1 daily, 2 weekly, 3 monthly or 4 yearly, due on `billing_day`, the
+    /// weekday (1 for Monday to 7) of a weekly plan or the day of the month of a monthly or
+    /// yearly one.
+    pub fn with_billing_interval(mut self, interval_type: u8, billing_day: u8) -> SubscriptionDeploy {
+        self.args.push(arg("interval_type", "u8", interval_type));
+        self.args.push(arg("billing_day", "u8", billing_day));
+        self
+    }
+
+    /// Make the plan of an install deploy seat-based: subscriptions pay for every member their
+    /// subscriber adds.
+    pub fn with_seats(mut self) -> SubscriptionDeploy {
+        self.args.push(arg("seat_based", "bool", true));
+        self
+    }
+
+    /// Approve the contract package to spend `amount` erc20 tokens of the sender.
+    pub fn approve(
+        erc20_contract_hash: ContractHash,
+        contract_package_hash: ContractPackageHash,
+        amount: U256,
+    ) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(erc20_contract_hash, "approve", vec![
+            arg("spender", "key", Key::from(contract_package_hash).to_formatted_string()),
+            arg("amount", "u256", amount),
+        ])
+    }
+
+    pub fn create_subscription_hash(contract_hash: ContractHash, public: &PublicKey, from: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "create_subscription_hash", vec![
+            arg("public", "public_key", public.to_hex()),
+            account_hash_arg("from", from),
+        ])
+    }
+
+    /// Register a subscription paid in `erc20_contract_hash`, one of the tokens the plan accepts.
+    pub fn create_subscription_hash_with_token(
+        contract_hash: ContractHash,
+        public: &PublicKey,
+        from: AccountHash,
+        erc20_contract_hash: ContractHash,
+    ) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "create_subscription_hash", vec![
+            arg("public", "public_key", public.to_hex()),
+            account_hash_arg("from", from),
+            erc20_contract_hash_arg(erc20_contract_hash),
+        ])
+    }
+
+    /// Redeem a coupon with a `create_subscription_hash` deploy.
+    pub fn with_coupon_code(mut self, coupon_code: &str) -> SubscriptionDeploy {
+        self.args.push(arg("coupon_code", "string", coupon_code));
+        self
+    }
+
+    /// Cap each payment of a `create_subscription_hash` deploy at `max_amount_per_period`, usage
+    /// and every seat included. A payment above it reverts.
+    pub fn with_max_amount_per_period(mut self, max_amount_per_period: U256) -> SubscriptionDeploy {
+        self.args.push(arg("max_amount_per_period", "u256", max_amount_per_period));
+        self
+    }
+
+    /// Make the subscription of a `create_subscription_hash` deploy metered, charging usage on top
+    /// of the price of a period. It needs `with_max_amount_per_period`.
+    pub fn with_metered(mut self) -> SubscriptionDeploy {
+        self.args.push(arg("metered", "bool", true));
+        self
+    }
+
+    /// Cap what the subscription of a `create_subscription_hash` deploy can charge over its lifetime.
+    pub fn with_lifetime_cap(mut self, lifetime_cap: U256) -> SubscriptionDeploy {
+        self.args.push(arg("lifetime_cap", "u256", lifetime_cap));
+        self
+    }
+
+    /// Give another account access to the subscription of a `create_subscription_hash` deploy, paid
+    /// for by the subscriber, or check an `is_subscription_active` deploy for that account.
+    pub fn with_beneficiary(mut self, beneficiary: AccountHash) -> SubscriptionDeploy {
+        self.args.push(account_hash_arg("beneficiary", beneficiary));
+        self
+    }
+
+    pub fn get_subscription_hash(contract_hash: ContractHash, subscription_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_subscription_hash", vec![subscription_id_arg(subscription_id)])
+    }
+
+    pub fn execute_subscription(contract_hash: ContractHash, signature: &str, subscription_id: u64) -> Result<SubscriptionDeploy> {
+        Ok(SubscriptionDeploy::call(contract_hash, "execute_subscription", vec![
+            signature_arg(signature)?,
+            subscription_id_arg(subscription_id),
+        ]))
+    }
+
+    pub fn cancel_subscription(contract_hash: ContractHash, signature: &str, subscription_id: u64) -> Result<SubscriptionDeploy> {
+        Ok(SubscriptionDeploy::call(contract_hash, "cancel_subscription", vec![
+            signature_arg(signature)?,
+            subscription_id_arg(subscription_id),
+        ]))
+    }
+
+    pub fn is_subscription_ready(contract_hash: ContractHash, signature: &str, subscription_id: u64) -> Result<SubscriptionDeploy> {
+        Ok(SubscriptionDeploy::call(contract_hash, "is_subscription_ready", vec![
+            signature_arg(signature)?,
+            subscription_id_arg(subscription_id),
+        ]))
+    }
+
+    pub fn is_subscription_active(contract_hash: ContractHash, subscription_hash: &str, grace_period_seconds: u64) -> Result<SubscriptionDeploy> {
+        check_hex("subscription_hash", subscription_hash, SUBSCRIPTION_HASH_HEX_LENGTH)?;
+
+        Ok(SubscriptionDeploy::call(contract_hash, "is_subscription_active", vec![
+            arg("subscription_hash", "string", subscription_hash),
+            arg("grace_period_seconds", "u64", grace_period_seconds),
+        ]))
+    }
+
+    pub fn get_subscription(contract_hash: ContractHash, subscription_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_subscription", vec![subscription_id_arg(subscription_id)])
+    }
+
+    pub fn get_next_payment_time(contract_hash: ContractHash, subscription_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_next_payment_time", vec![subscription_id_arg(subscription_id)])
+    }
+
+    pub fn get_subscription_status(contract_hash: ContractHash, subscription_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_subscription_status", vec![subscription_id_arg(subscription_id)])
+    }
+
+    pub fn get_active_subscription(contract_hash: ContractHash, account: AccountHash, grace_period_seconds: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_active_subscription", vec![
+            account_hash_arg("account", account),
+            arg("grace_period_seconds", "u64", grace_period_seconds),
+        ])
+    }
+
+    pub fn get_account_subscriptions(contract_hash: ContractHash, from: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_account_subscriptions", vec![account_hash_arg("from", from)])
+    }
+
+    pub fn get_plan(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_plan", vec![])
+    }
+
+    pub fn get_token_prices(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_token_prices", vec![])
+    }
+
+    /// Accept another erc20 token at `token_amount` a period, sent by the publisher.
+    pub fn add_token(contract_hash: ContractHash, erc20_contract_hash: ContractHash, token_amount: U256) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "add_token", vec![
+            erc20_contract_hash_arg(erc20_contract_hash),
+            arg("token_amount", "u256", token_amount),
+        ])
+    }
+
+    pub fn get_pending_prices(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_pending_prices", vec![])
+    }
+
+    /// Change the price of a period in an accepted token to `token_amount` from `effective_at`,
+    /// a blocktime in milliseconds at least a billing period away, sent by the publisher.
+    pub fn schedule_price_change(
+        contract_hash: ContractHash,
+        erc20_contract_hash: ContractHash,
+        token_amount: U256,
+        effective_at: u64,
+    ) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "schedule_price_change", vec![
+            erc20_contract_hash_arg(erc20_contract_hash),
+            arg("token_amount", "u256", token_amount),
+            arg("effective_at", "u64", effective_at),
+        ])
+    }
+
+    pub fn get_unit_prices(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_unit_prices", vec![])
+    }
+
+    /// Charge metered subscriptions `unit_price` a unit of usage in an accepted token, sent by the publisher.
+    pub fn set_unit_price(contract_hash: ContractHash, erc20_contract_hash: ContractHash, unit_price: U256) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "set_unit_price", vec![
+            erc20_contract_hash_arg(erc20_contract_hash),
+            arg("unit_price", "u256", unit_price),
+        ])
+    }
+
+    /// Let `oracle` report usage besides the publisher, sent by the publisher.
+    pub fn set_usage_oracle(contract_hash: ContractHash, oracle: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "set_usage_oracle", vec![account_hash_arg("oracle", oracle)])
+    }
+
+    /// Report units of usage of a metered subscription, sent by the publisher or the usage oracle.
+    pub fn report_usage(contract_hash: ContractHash, subscription_id: u64, usage: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "report_usage", vec![
+            subscription_id_arg(subscription_id),
+            arg("usage", "u64", usage),
+        ])
+    }
+
+    /// Give an account a seat of a subscription to a seat-based plan, sent by its subscriber.
+    pub fn add_member(contract_hash: ContractHash, subscription_id: u64, member: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "add_member", vec![subscription_id_arg(subscription_id), account_hash_arg("member", member)])
+    }
+
+    /// Take the seat of a member of a subscription to a seat-based plan, sent by its subscriber.
+    pub fn remove_member(contract_hash: ContractHash, subscription_id: u64, member: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "remove_member", vec![subscription_id_arg(subscription_id), account_hash_arg("member", member)])
+    }
+
+    pub fn get_members(contract_hash: ContractHash, subscription_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_members", vec![subscription_id_arg(subscription_id)])
+    }
+
+    /// Create a coupon subscribers can redeem, sent by the publisher.
+    pub fn add_coupon(contract_hash: ContractHash, coupon_code: &str, coupon: &Coupon) -> SubscriptionDeploy {
+        let mut args = vec![
+            arg("coupon_code", "string", coupon_code),
+            arg("discount_type", "u8", coupon.discount_type),
+            arg("discount", "u256", coupon.discount),
+            arg("periods", "u64", coupon.periods),
+            arg("max_redemptions", "u64", coupon.max_redemptions),
+            arg("expires_at", "u64", coupon.expires_at),
+        ];
+        if let Some(erc20_contract_hash) = coupon.erc20_contract_hash {
+            args.push(erc20_contract_hash_arg(erc20_contract_hash));
+        }
+
+        SubscriptionDeploy::call(contract_hash, "add_coupon", args)
+    }
+
+    /// Stop a coupon from being redeemed, sent by the publisher.
+    pub fn expire_coupon(contract_hash: ContractHash, coupon_code: &str) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "expire_coupon", vec![arg("coupon_code", "string", coupon_code)])
+    }
+
+    pub fn get_payees(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_payees", vec![])
+    }
+
+    /// Pay `recipient` `basis_points` out of 10000 of each payment, sent by the publisher.
+    /// 0 basis points removes the payee.
+    pub fn set_payee(contract_hash: ContractHash, recipient: AccountHash, basis_points: u32) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "set_payee", vec![
+            account_hash_arg("recipient", recipient),
+            arg("basis_points", "u32", basis_points),
+        ])
+    }
+
+    pub fn get_escrow_balance(contract_hash: ContractHash, account: AccountHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_escrow_balance", vec![account_hash_arg("account", account)])
+    }
+
+    pub fn get_escrow_held(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_escrow_held", vec![])
+    }
+
+    pub fn get_escrow_payment(contract_hash: ContractHash, payment_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_escrow_payment", vec![payment_id_arg(payment_id)])
+    }
+
+    /// Refund a payment held in escrow to its subscriber, sent by the publisher.
+    pub fn refund_payment(contract_hash: ContractHash, payment_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "refund_payment", vec![payment_id_arg(payment_id)])
+    }
+
+    /// Take a payment held in escrow back, sent by its subscriber.
+    pub fn chargeback_payment(contract_hash: ContractHash, payment_id: u64) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "chargeback_payment", vec![payment_id_arg(payment_id)])
+    }
+
+    /// Withdraw the sender's available escrow balance in an erc20 token.
+    pub fn withdraw(contract_hash: ContractHash, erc20_contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "withdraw", vec![erc20_contract_hash_arg(erc20_contract_hash)])
+    }
+
+    pub fn get_subscriber_count(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_subscriber_count", vec![])
+    }
+
+    pub fn get_subscription_count(contract_hash: ContractHash) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "get_subscription_count", vec![])
+    }
+
+    pub fn list_subscribers(contract_hash: ContractHash, offset: u64, limit: u64, status: Option<u8>) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "list_subscribers", page_args(offset, limit, status))
+    }
+
+    pub fn list_subscriptions(contract_hash: ContractHash, offset: u64, limit: u64, status: Option<u8>) -> SubscriptionDeploy {
+        SubscriptionDeploy::call(contract_hash, "list_subscriptions", page_args(offset, limit, status))
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Runtime arguments in the casper-client `name:type='value'` form.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Sign the deploy with the secret key of `params` and write it as JSON to `output_path`,
+    /// or to stdout if `output_path` is empty. An existing file is overwritten.
+    pub fn write(&self, params: &DeployParams, output_path: &str) -> Result<()> {
+        let ttl = params.ttl.as_str();
+        let gas_price = params.gas_price.to_string();
+        let payment_amount = params.payment_amount.to_string();
+
+        let deploy_params = DeployStrParams {
+            secret_key: &params.secret_key,
+            timestamp: "",
+            ttl,
+            gas_price: &gas_price,
+            dependencies: vec![],
+            chain_name: &params.chain_name,
+        };
+
+        let args: Vec<&str> = self.args.iter().map(String::as_str).collect();
+        let contract_hash: String;
+        let session_params = match &self.session {
+            Session::Wasm { path } => SessionStrParams::with_path(path, args, ""),
+            Session::StoredContract { contract_hash: hash, entry_point } => {
+                contract_hash = hex::encode(hash.value());
+                SessionStrParams::with_hash(&contract_hash, entry_point, args, "")
+            }
+        };
+
+        let payment_params = PaymentStrParams::with_amount(&payment_amount);
+
+        casper_client::make_deploy(output_path, deploy_params, session_params, payment_params, true)?;
+
+        Ok(())
+    }
+}
+
+fn arg<T: ToString>(name: &str, cl_type: &str, value: T) -> String {
+    format!("{}:{}='{}'", name, cl_type, value.to_string())
+}
+
+fn account_hash_arg(name: &str, account: AccountHash) -> String {
+    arg(name, "account_hash", account.to_formatted_string())
+}
+
+fn erc20_contract_hash_arg(erc20_contract_hash: ContractHash) -> String {
+    arg("erc20_contract_hash", "key", Key::from(erc20_contract_hash).to_formatted_string())
+}
+
+fn subscription_id_arg(subscription_id: u64) -> String {
+    arg("subscription_id", "u64", subscription_id)
+}
+
+fn payment_id_arg(payment_id: u64) -> String {
+    arg("payment_id", "u64", payment_id)
+}
+
+fn signature_arg(signature: &str) -> Result<String> {
+    check_hex("signature", signature, SIGNATURE_HEX_LENGTH)?;
+
+    Ok(arg("signature", "string", signature))
+}
+
+/// `status` is left out when `None`, the contract reads it as an optional argument.
+fn page_args(offset: u64, limit: u64, status: Option<u8>) -> Vec<String> {
+    let mut args = vec![arg("offset", "u64", offset), arg("limit", "u64", limit)];
+    if let Some(status) = status {
+        args.push(arg("status", "u8", status));
+    }
+    args
+}
+
+/// Reject arguments the contract would revert on, before paying for the deploy.
+fn check_hex(name: &'static str, value: &str, length: usize) -> Result<()> {
+    if value.len() != length {
+        return Err(Error::InvalidArgument {
+            name,
+            reason: format!("expected {} hex characters, got {}", length, value.len()),
+        });
+    }
+
+    hex::decode(value).map_err(|error| Error::InvalidArgument {
+        name,
+        reason: error.to_string(),
+    })?;
+
+    Ok(())
+}
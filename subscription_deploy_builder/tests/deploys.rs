@@ -0,0 +1,338 @@
+use std::fs;
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use subscription_deploy_builder::{Coupon, DeployParams, Error, SubscriptionDeploy};
+
+use types::{account::AccountHash, ContractHash, ContractPackageHash, U256};
+
+const CHAIN_NAME: &str = "casper-test";
+const PAYMENT_AMOUNT: u64 = 10_000_000_000;
+
+const CONTRACT_HASH: ContractHash = ContractHash::new([7u8; 32]);
+const SUBSCRIPTION_HASH: &str = "0707070707070707070707070707070707070707070707070707070707070707";
+
+struct Sender {
+    dir: TempDir,
+    params: DeployParams,
+    public_key_hex: String,
+}
+
+impl Sender {
+    /// A fresh Ed25519 key pair written by `casper-client keygen`.
+    fn new() -> Sender {
+        let dir = tempfile::tempdir().unwrap();
+        casper_client::keygen::generate_files(dir.path().to_str().unwrap(), casper_client::keygen::ED25519, true).unwrap();
+
+        let secret_key = dir.path().join("secret_key.pem");
+        let public_key_hex = fs::read_to_string(dir.path().join("public_key_hex")).unwrap();
+
+        Sender {
+            params: DeployParams::new(CHAIN_NAME, secret_key.to_str().unwrap(), PAYMENT_AMOUNT),
+            public_key_hex: public_key_hex.trim().to_string(),
+            dir,
+        }
+    }
+
+    /// Write the deploy and parse the JSON back.
+    fn write(&self, deploy: &SubscriptionDeploy) -> Value {
+        let path = self.dir.path().join("deploy.json");
+        deploy.write(&self.params, path.to_str().unwrap()).unwrap();
+
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+}
+
+/// The `[name, {cl_type, bytes, parsed}]` runtime arguments of a session or payment.
+fn args(item: &Value) -> Vec<(String, Value)> {
+    item["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|arg| (arg[0].as_str().unwrap().to_string(), arg[1].clone()))
+        .collect()
+}
+
+fn arg_names(item: &Value) -> Vec<String> {
+    args(item).into_iter().map(|(name, _value)| name).collect()
+}
+
+fn signature() -> String {
+    "ab".repeat(64)
+}
+
+#[test]
+fn test_execute_subscription_deploy() {
+    let sender = Sender::new();
+    let deploy = sender.write(&SubscriptionDeploy::execute_subscription(CONTRACT_HASH, &signature(), 7).unwrap());
+
+    // Signed by the sender for the chain
+    assert_eq!(deploy["header"]["chain_name"], CHAIN_NAME);
+    assert_eq!(deploy["header"]["account"], sender.public_key_hex.as_str());
+    assert_eq!(deploy["approvals"].as_array().unwrap().len(), 1);
+    assert_eq!(deploy["approvals"][0]["signer"], sender.public_key_hex.as_str());
+
+    let payment = &deploy["payment"]["ModuleBytes"];
+    assert_eq!(args(payment)[0].1["parsed"], PAYMENT_AMOUNT.to_string());
+
+    let session = &deploy["session"]["StoredContractByHash"];
+    assert_eq!(session["hash"], hex::encode(CONTRACT_HASH.value()));
+    assert_eq!(session["entry_point"], "execute_subscription");
+
+    let session_args = args(session);
+    assert_eq!(arg_names(session), vec!["signature", "subscription_id"]);
+    assert_eq!(session_args[0].1["cl_type"], "String");
+    assert_eq!(session_args[0].1["parsed"], signature());
+    assert_eq!(session_args[1].1["cl_type"], "U64");
+    assert_eq!(session_args[1].1["parsed"], 7);
+}
+
+#[test]
+fn test_install_deploy() {
+    let sender = Sender::new();
+    let wasm_path = sender.dir.path().join("casper-contract-eip-1337.wasm");
+    let wasm_bytes = b"\0asm\x01\0\0\0".to_vec();
+    fs::write(&wasm_path, &wasm_bytes).unwrap();
+
+    let deploy = sender.write(&SubscriptionDeploy::install(
+        wasm_path.to_str().unwrap(),
+        AccountHash::new([5u8; 32]),
+        U256::from(10),
+        3600,
+        ContractHash::new([9u8; 32]),
+    ));
+
+    let session = &deploy["session"]["ModuleBytes"];
+    assert_eq!(session["module_bytes"], hex::encode(&wasm_bytes));
+
+    let session_args = args(session);
+    assert_eq!(arg_names(session), vec!["to", "token_amount", "period_seconds", "erc20_contract_hash"]);
+    assert_eq!(session_args[0].1["cl_type"], json!({ "ByteArray": 32 }));
+    assert_eq!(session_args[1].1["cl_type"], "U256");
+    assert_eq!(session_args[1].1["parsed"], "10");
+    assert_eq!(session_args[2].1["parsed"], 3600);
+    assert_eq!(session_args[3].1["cl_type"], "Key");
+}
+
+#[test]
+fn test_install_with_escrow_deploy() {
+    let sender = Sender::new();
+    let wasm_path = sender.dir.path().join("casper-contract-eip-1337.wasm");
+    fs::write(&wasm_path, b"\0asm\x01\0\0\0").unwrap();
+
+    let deploy = sender.write(&SubscriptionDeploy::install_with_escrow(
+        wasm_path.to_str().unwrap(),
+        AccountHash::new([5u8; 32]),
+        U256::from(10),
+        3600,
+        ContractHash::new([9u8; 32]),
+        86400,
+    ));
+
+    let session = &deploy["session"]["ModuleBytes"];
+    assert_eq!(
+        arg_names(session),
+        vec!["to", "token_amount", "period_seconds", "erc20_contract_hash", "dispute_window_seconds"]
+    );
+    assert_eq!(args(session)[4].1["parsed"], 86400);
+}
+
+#[test]
+fn test_install_with_billing_interval_deploy() {
+    let sender = Sender::new();
+    let wasm_path = sender.dir.path().join("casper-contract-eip-1337.wasm");
+    fs::write(&wasm_path, b"\0asm\x01\0\0\0").unwrap();
+
+    let deploy = sender.write(
+        &SubscriptionDeploy::install(
+            wasm_path.to_str().unwrap(),
+            AccountHash::new([5u8; 32]),
+            U256::from(10),
+            2_592_000,
+            ContractHash::new([9u8; 32]),
+        )
+        .with_billing_interval(3, 31),
+    );
+
+    let session = &deploy["session"]["ModuleBytes"];
+    assert_eq!(
+        arg_names(session),
+        vec!["to", "token_amount", "period_seconds", "erc20_contract_hash", "interval_type", "billing_day"]
+    );
+    assert_eq!(args(session)[4].1["cl_type"], "U8");
+    assert_eq!(args(session)[4].1["parsed"], 3);
+    assert_eq!(args(session)[5].1["parsed"], 31);
+}
+
+#[test]
+fn test_install_with_seats_deploy() {
+    let sender = Sender::new();
+    let wasm_path = sender.dir.path().join("casper-contract-eip-1337.wasm");
+    fs::write(&wasm_path, b"\0asm\x01\0\0\0").unwrap();
+
+    let deploy = sender.write(
+        &SubscriptionDeploy::install(
+            wasm_path.to_str().unwrap(),
+            AccountHash::new([5u8; 32]),
+            U256::from(10),
+            2_592_000,
+            ContractHash::new([9u8; 32]),
+        )
+        .with_seats(),
+    );
+
+    let session = &deploy["session"]["ModuleBytes"];
+    assert_eq!(arg_names(session), vec!["to", "token_amount", "period_seconds", "erc20_contract_hash", "seat_based"]);
+    assert_eq!(args(session)[4].1["cl_type"], "Bool");
+    assert_eq!(args(session)[4].1["parsed"], true);
+}
+
+#[test]
+fn test_entry_point_deploys() {
+    let sender = Sender::new();
+    let from = AccountHash::new([4u8; 32]);
+    let public_key = types::PublicKey::ed25519_from_bytes([1u8; 32]).unwrap();
+
+    let deploys = vec![
+        ("approve", SubscriptionDeploy::approve(CONTRACT_HASH, ContractPackageHash::new([8u8; 32]), U256::from(1000))),
+        ("create_subscription_hash", SubscriptionDeploy::create_subscription_hash(CONTRACT_HASH, &public_key, from)),
+        (
+            "create_subscription_hash",
+            SubscriptionDeploy::create_subscription_hash_with_token(CONTRACT_HASH, &public_key, from, ContractHash::new([9u8; 32])),
+        ),
+        (
+            "create_subscription_hash",
+            SubscriptionDeploy::create_subscription_hash(CONTRACT_HASH, &public_key, from).with_coupon_code("LAUNCH"),
+        ),
+        (
+            "create_subscription_hash",
+            SubscriptionDeploy::create_subscription_hash(CONTRACT_HASH, &public_key, from)
+                .with_max_amount_per_period(U256::from(50))
+                .with_lifetime_cap(U256::from(500)),
+        ),
+        (
+            "create_subscription_hash",
+            SubscriptionDeploy::create_subscription_hash(CONTRACT_HASH, &public_key, from)
+                .with_max_amount_per_period(U256::from(50))
+                .with_metered(),
+        ),
+        (
+            "create_subscription_hash",
+            SubscriptionDeploy::create_subscription_hash(CONTRACT_HASH, &public_key, from).with_beneficiary(AccountHash::new([5u8; 32])),
+        ),
+        ("get_subscription_hash", SubscriptionDeploy::get_subscription_hash(CONTRACT_HASH, 0)),
+        ("cancel_subscription", SubscriptionDeploy::cancel_subscription(CONTRACT_HASH, &signature(), 0).unwrap()),
+        ("is_subscription_ready", SubscriptionDeploy::is_subscription_ready(CONTRACT_HASH, &signature(), 0).unwrap()),
+        ("is_subscription_active", SubscriptionDeploy::is_subscription_active(CONTRACT_HASH, SUBSCRIPTION_HASH, 60).unwrap()),
+        (
+            "is_subscription_active",
+            SubscriptionDeploy::is_subscription_active(CONTRACT_HASH, SUBSCRIPTION_HASH, 60).unwrap().with_beneficiary(AccountHash::new([5u8; 32])),
+        ),
+        ("get_subscription", SubscriptionDeploy::get_subscription(CONTRACT_HASH, 0)),
+        ("get_next_payment_time", SubscriptionDeploy::get_next_payment_time(CONTRACT_HASH, 0)),
+        ("get_subscription_status", SubscriptionDeploy::get_subscription_status(CONTRACT_HASH, 0)),
+        ("get_active_subscription", SubscriptionDeploy::get_active_subscription(CONTRACT_HASH, from, 60)),
+        ("get_account_subscriptions", SubscriptionDeploy::get_account_subscriptions(CONTRACT_HASH, from)),
+        ("get_plan", SubscriptionDeploy::get_plan(CONTRACT_HASH)),
+        ("get_token_prices", SubscriptionDeploy::get_token_prices(CONTRACT_HASH)),
+        ("add_token", SubscriptionDeploy::add_token(CONTRACT_HASH, ContractHash::new([9u8; 32]), U256::from(25))),
+        ("get_pending_prices", SubscriptionDeploy::get_pending_prices(CONTRACT_HASH)),
+        (
+            "schedule_price_change",
+            SubscriptionDeploy::schedule_price_change(CONTRACT_HASH, ContractHash::new([9u8; 32]), U256::from(30), 1_700_000_000_000),
+        ),
+        ("get_payees", SubscriptionDeploy::get_payees(CONTRACT_HASH)),
+        ("set_payee", SubscriptionDeploy::set_payee(CONTRACT_HASH, from, 2000)),
+        ("get_escrow_balance", SubscriptionDeploy::get_escrow_balance(CONTRACT_HASH, from)),
+        ("get_escrow_held", SubscriptionDeploy::get_escrow_held(CONTRACT_HASH)),
+        ("get_escrow_payment", SubscriptionDeploy::get_escrow_payment(CONTRACT_HASH, 0)),
+        ("refund_payment", SubscriptionDeploy::refund_payment(CONTRACT_HASH, 0)),
+        ("chargeback_payment", SubscriptionDeploy::chargeback_payment(CONTRACT_HASH, 0)),
+        ("withdraw", SubscriptionDeploy::withdraw(CONTRACT_HASH, ContractHash::new([9u8; 32]))),
+        ("get_unit_prices", SubscriptionDeploy::get_unit_prices(CONTRACT_HASH)),
+        ("set_unit_price", SubscriptionDeploy::set_unit_price(CONTRACT_HASH, ContractHash::new([9u8; 32]), U256::from(2))),
+        ("set_usage_oracle", SubscriptionDeploy::set_usage_oracle(CONTRACT_HASH, from)),
+        ("report_usage", SubscriptionDeploy::report_usage(CONTRACT_HASH, 0, 7)),
+        ("add_member", SubscriptionDeploy::add_member(CONTRACT_HASH, 0, from)),
+        ("remove_member", SubscriptionDeploy::remove_member(CONTRACT_HASH, 0, from)),
+        ("get_members", SubscriptionDeploy::get_members(CONTRACT_HASH, 0)),
+        ("add_coupon", SubscriptionDeploy::add_coupon(CONTRACT_HASH, "LAUNCH", &coupon(None))),
+        ("expire_coupon", SubscriptionDeploy::expire_coupon(CONTRACT_HASH, "LAUNCH")),
+        ("get_subscriber_count", SubscriptionDeploy::get_subscriber_count(CONTRACT_HASH)),
+        ("get_subscription_count", SubscriptionDeploy::get_subscription_count(CONTRACT_HASH)),
+        ("list_subscribers", SubscriptionDeploy::list_subscribers(CONTRACT_HASH, 0, 10, None)),
+        ("list_subscriptions", SubscriptionDeploy::list_subscriptions(CONTRACT_HASH, 0, 10, Some(2))),
+    ];
+
+    for (entry_point, subscription_deploy) in deploys {
+        let deploy = sender.write(&subscription_deploy);
+        let session = &deploy["session"]["StoredContractByHash"];
+
+        assert_eq!(session["entry_point"], entry_point);
+
+        let expected_names: Vec<String> = subscription_deploy
+            .args()
+            .iter()
+            .map(|arg| arg.split(':').next().unwrap().to_string())
+            .collect();
+        assert_eq!(arg_names(session), expected_names, "{}", entry_point);
+    }
+}
+
+#[test]
+fn test_add_coupon_deploy() {
+    let sender = Sender::new();
+
+    let deploy = sender.write(&SubscriptionDeploy::add_coupon(CONTRACT_HASH, "LAUNCH", &coupon(None)));
+    assert_eq!(
+        arg_names(&deploy["session"]["StoredContractByHash"]),
+        vec!["coupon_code", "discount_type", "discount", "periods", "max_redemptions", "expires_at"]
+    );
+
+    let deploy = sender.write(&SubscriptionDeploy::add_coupon(
+        CONTRACT_HASH,
+        "LAUNCH",
+        &coupon(Some(ContractHash::new([9u8; 32]))),
+    ));
+    let session_args = args(&deploy["session"]["StoredContractByHash"]);
+    assert_eq!(session_args[2].1["cl_type"], "U256");
+    assert_eq!(session_args[6].0, "erc20_contract_hash");
+}
+
+#[test]
+fn test_optional_status_arg() {
+    let sender = Sender::new();
+
+    let deploy = sender.write(&SubscriptionDeploy::list_subscribers(CONTRACT_HASH, 0, 10, None));
+    assert_eq!(arg_names(&deploy["session"]["StoredContractByHash"]), vec!["offset", "limit"]);
+
+    let deploy = sender.write(&SubscriptionDeploy::list_subscribers(CONTRACT_HASH, 0, 10, Some(2)));
+    let session_args = args(&deploy["session"]["StoredContractByHash"]);
+    assert_eq!(session_args[2].0, "status");
+    assert_eq!(session_args[2].1["cl_type"], "U8");
+    assert_eq!(session_args[2].1["parsed"], 2);
+}
+
+#[test]
+fn test_invalid_arguments_are_rejected() {
+    let too_short = SubscriptionDeploy::execute_subscription(CONTRACT_HASH, "abcd", 0);
+    assert!(matches!(too_short, Err(Error::InvalidArgument { name: "signature", .. })));
+
+    let not_hex = SubscriptionDeploy::cancel_subscription(CONTRACT_HASH, &"zz".repeat(64), 0);
+    assert!(matches!(not_hex, Err(Error::InvalidArgument { name: "signature", .. })));
+
+    let bad_hash = SubscriptionDeploy::is_subscription_active(CONTRACT_HASH, "07", 0);
+    assert!(matches!(bad_hash, Err(Error::InvalidArgument { name: "subscription_hash", .. })));
+}
+
+fn coupon(erc20_contract_hash: Option<ContractHash>) -> Coupon {
+    Coupon {
+        discount_type: 1,
+        discount: U256::from(5),
+        erc20_contract_hash,
+        periods: 3,
+        max_redemptions: 100,
+        expires_at: 0,
+    }
+}
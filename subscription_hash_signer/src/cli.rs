@@ -0,0 +1,311 @@
+use clap::{App, AppSettings, Arg, SubCommand};
+
+pub mod commands;
+mod errors;
+pub mod output;
+
+pub const JSON: &str = "json";
+
+pub const HASH: &str = "hash";
+pub const SIGN: &str = "sign";
+pub const VERIFY: &str = "verify";
+pub const ACCOUNT_HASH: &str = "account-hash";
+pub const ACCOUNT_KEY: &str = "account-key";
+pub const KEYGEN: &str = "keygen";
+pub const MAKE_DEPLOY: &str = "make-deploy";
+pub const EXPLAIN_ERROR: &str = "explain-error";
+
+pub const TO: &str = "to";
+pub const FROM: &str = "from";
+pub const TOKEN_AMOUNT: &str = "token-amount";
+pub const PERIOD_SECONDS: &str = "period-seconds";
+pub const SUBSCRIPTION_ID: &str = "subscription-id";
+pub const SUBSCRIPTION_HASH: &str = "subscription-hash";
+pub const SECRET_KEY: &str = "secret-key";
+pub const PASSWORD: &str = "password";
+pub const EXTERNAL_SIGNER: &str = "external-signer";
+pub const EXTERNAL_SIGNER_ARG: &str = "external-signer-arg";
+pub const PUBLIC_KEY: &str = "public-key";
+pub const SIGNATURE: &str = "signature";
+pub const OUTPUT_DIR: &str = "output-dir";
+pub const FORCE: &str = "force";
+pub const ALGORITHM: &str = "algorithm";
+pub const ED25519: &str = "ed25519";
+pub const SECP256K1: &str = "secp256k1";
+pub const ERROR_MESSAGE: &str = "error-message";
+
+pub const ENTRY_POINT: &str = "entry-point";
+pub const CHAIN_NAME: &str = "chain-name";
+pub const PAYMENT_AMOUNT: &str = "payment-amount";
+pub const TTL: &str = "ttl";
+pub const GAS_PRICE: &str = "gas-price";
+pub const OUTPUT: &str = "output";
+pub const CONTRACT_HASH: &str = "contract-hash";
+pub const WASM: &str = "wasm";
+pub const ERC20_CONTRACT_HASH: &str = "erc20-contract-hash";
+pub const SPENDER: &str = "spender";
+pub const AMOUNT: &str = "amount";
+pub const GRACE_PERIOD_SECONDS: &str = "grace-period-seconds";
+pub const OFFSET: &str = "offset";
+pub const LIMIT: &str = "limit";
+pub const STATUS: &str = "status";
+pub const RECIPIENT: &str = "recipient";
+pub const BASIS_POINTS: &str = "basis-points";
+pub const DISPUTE_WINDOW_SECONDS: &str = "dispute-window-seconds";
+pub const INTERVAL_TYPE: &str = "interval-type";
+pub const BILLING_DAY: &str = "billing-day";
+pub const SEAT_BASED: &str = "seat-based";
+pub const PAYMENT_ID: &str = "payment-id";
+pub const ACCOUNT: &str = "account";
+pub const COUPON_CODE: &str = "coupon-code";
+pub const DISCOUNT_TYPE: &str = "discount-type";
+pub const DISCOUNT: &str = "discount";
+pub const PERIODS: &str = "periods";
+pub const MAX_REDEMPTIONS: &str = "max-redemptions";
+pub const EXPIRES_AT: &str = "expires-at";
+pub const MAX_AMOUNT_PER_PERIOD: &str = "max-amount-per-period";
+pub const LIFETIME_CAP: &str = "lifetime-cap";
+pub const METERED: &str = "metered";
+pub const BENEFICIARY: &str = "beneficiary";
+pub const MEMBER: &str = "member";
+pub const UNIT_PRICE: &str = "unit-price";
+pub const ORACLE: &str = "oracle";
+pub const USAGE: &str = "usage";
+pub const EFFECTIVE_AT: &str = "effective-at";
+
+/// Entry points `make-deploy` can build a deploy for, `install` being the contract installation.
+pub const ENTRY_POINTS: &[&str] = &[
+  "install",
+  "approve",
+  "create_subscription_hash",
+  "get_subscription_hash",
+  "execute_subscription",
+  "cancel_subscription",
+  "is_subscription_ready",
+  "is_subscription_active",
+  "get_subscription",
+  "get_next_payment_time",
+  "get_subscription_status",
+  "get_active_subscription",
+  "get_account_subscriptions",
+  "get_plan",
+  "get_token_prices",
+  "add_token",
+  "get_pending_prices",
+  "schedule_price_change",
+  "get_payees",
+  "set_payee",
+  "get_escrow_balance",
+  "get_escrow_held",
+  "get_escrow_payment",
+  "refund_payment",
+  "chargeback_payment",
+  "withdraw",
+  "get_unit_prices",
+  "set_unit_price",
+  "set_usage_oracle",
+  "report_usage",
+  "add_member",
+  "remove_member",
+  "get_members",
+  "add_coupon",
+  "expire_coupon",
+  "get_subscriber_count",
+  "get_subscription_count",
+  "list_subscribers",
+  "list_subscriptions",
+];
+
+fn value<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+  Arg::with_name(name).long(name).value_name(name).takes_value(true).help(help)
+}
+
+fn required<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+  value(name, help).required(true)
+}
+
+/// The subscription terms, required to compute a hash or an alternative to `--subscription-hash`.
+/// `--from` defaults to the account of the key when the terms are optional.
+fn terms(subcommand: App<'static, 'static>, required: bool) -> App<'static, 'static> {
+  let term = |name: &'static str, help: &'static str| {
+    let arg = value(name, help);
+    if required {
+      arg.required(true)
+    } else {
+      arg.conflicts_with(SUBSCRIPTION_HASH)
+    }
+  };
+
+  subcommand
+    .arg(term(TO, "Account hash receiving the payments"))
+    .arg(term(FROM, "Account hash of the subscriber"))
+    .arg(term(TOKEN_AMOUNT, "Price of a period in the chosen erc20 token"))
+    .arg(term(PERIOD_SECONDS, "Seconds between two payments"))
+    .arg(term(SUBSCRIPTION_ID, "Id of the subscription"))
+    .arg(term(ERC20_CONTRACT_HASH, "Hash of the erc20 contract the subscriber pays with"))
+    .arg(value(MAX_AMOUNT_PER_PERIOD, "Most a payment can charge, usage and every seat included, left out for no cap"))
+    .arg(value(LIFETIME_CAP, "Most the subscription can charge over its lifetime, left out for no cap"))
+    .arg(value(BENEFICIARY, "Account hash given access when the subscriber pays for another account, left out for the subscriber"))
+    .arg(
+      Arg::with_name(METERED)
+        .long(METERED)
+        .help("Charge the usage reported on top of the price, within the max amount per period"),
+    )
+}
+
+pub fn app() -> App<'static, 'static> {
+  App::new("subscription_hash_signer")
+    .about("Hashes, signs and verifies EIP-1337 subscriptions and builds deploys for the contract")
+    .setting(AppSettings::SubcommandRequiredElseHelp)
+    .setting(AppSettings::VersionlessSubcommands)
+    .arg(
+      Arg::with_name(JSON)
+        .long(JSON)
+        .global(true)
+        .help("Print the output as a JSON object"),
+    )
+    .subcommand(terms(
+      SubCommand::with_name(HASH).about("Compute the subscription hash from the subscription terms, as the contract does"),
+      true,
+    ))
+    .subcommand(terms(
+      SubCommand::with_name(SIGN)
+        .about("Sign a subscription hash, given or computed from the terms, with an Ed25519 secret key")
+        .arg(
+          value(SECRET_KEY, "Path to the secret key of the subscriber: PEM, or raw hex or base64")
+            .required_unless(EXTERNAL_SIGNER),
+        )
+        .arg(value(PASSWORD, "Password of an encrypted PEM secret key").requires(SECRET_KEY))
+        .arg(
+          value(EXTERNAL_SIGNER, "Command signing the hex subscription hash on its stdin instead of a secret key file")
+            .conflicts_with(SECRET_KEY)
+            .requires(PUBLIC_KEY),
+        )
+        .arg(
+          value(EXTERNAL_SIGNER_ARG, "Argument of the external signer command, may be repeated")
+            .multiple(true)
+            .number_of_values(1)
+            .requires(EXTERNAL_SIGNER),
+        )
+        .arg(value(PUBLIC_KEY, "Hex encoded public key of the external signer").requires(EXTERNAL_SIGNER))
+        .arg(value(SUBSCRIPTION_HASH, "Hex encoded subscription hash").required_unless(TO)),
+      false,
+    ))
+    .subcommand(terms(
+      SubCommand::with_name(VERIFY)
+        .about("Verify a signature of a subscription hash, given or computed from the terms, as the contract does")
+        .arg(required(PUBLIC_KEY, "Hex encoded public key of the subscriber"))
+        .arg(value(SUBSCRIPTION_HASH, "Hex encoded subscription hash").required_unless(TO))
+        .arg(required(SIGNATURE, "Hex encoded signature")),
+      false,
+    ))
+    .subcommand(
+      SubCommand::with_name(ACCOUNT_HASH)
+        .about("Print the account hash of a public key or of a secret key file")
+        .arg(value(PUBLIC_KEY, "Hex encoded public key").required_unless(SECRET_KEY))
+        .arg(value(SECRET_KEY, "Path to a secret key: PEM, or raw hex or base64").conflicts_with(PUBLIC_KEY))
+        .arg(value(PASSWORD, "Password of an encrypted PEM secret key").requires(SECRET_KEY)),
+    )
+    .subcommand(
+      SubCommand::with_name(ACCOUNT_KEY)
+        .about("Print the account hash of a public key and its item keys in the account_subscriptions, account_access and balances dictionaries")
+        .arg(required(PUBLIC_KEY, "Public key as hex or PEM, or the path of a public_key_hex or public_key.pem file")),
+    )
+    .subcommand(
+      SubCommand::with_name(KEYGEN)
+        .about("Generate a key pair in the casper-client layout")
+        .arg(required(OUTPUT_DIR, "Directory to write the key files to"))
+        .arg(
+          value(ALGORITHM, "Key algorithm, only Ed25519 keys can sign subscriptions")
+            .possible_values(&[ED25519, SECP256K1])
+            .default_value(ED25519),
+        )
+        .arg(value(PASSWORD, "Encrypt the secret key with this password"))
+        .arg(Arg::with_name(FORCE).long(FORCE).help("Overwrite existing key files")),
+    )
+    .subcommand(
+      SubCommand::with_name(MAKE_DEPLOY)
+        .about("Write a signed deploy calling an entry point of the contract")
+        .arg(
+          Arg::with_name(ENTRY_POINT)
+            .required(true)
+            .possible_values(ENTRY_POINTS)
+            .help("Entry point to call"),
+        )
+        .arg(required(CHAIN_NAME, "Name of the chain, e.g. casper-test"))
+        .arg(required(SECRET_KEY, "Path to the PEM secret key of the account sending the deploy"))
+        .arg(required(PAYMENT_AMOUNT, "Payment amount in motes"))
+        .arg(value(TTL, "Time to live of the deploy").default_value("30min"))
+        .arg(value(GAS_PRICE, "Gas price in motes per unit of gas").default_value("1"))
+        .arg(value(OUTPUT, "File to write the deploy to, stdout if left out"))
+        .arg(value(CONTRACT_HASH, "Hash of the EIP-1337 contract, or of the erc20 contract for approve"))
+        .arg(value(WASM, "Path to casper-contract-eip-1337.wasm, for install"))
+        .arg(value(TO, "Account hash receiving the payments, for install"))
+        .arg(value(TOKEN_AMOUNT, "Erc20 tokens paid every period, for install, add_token and schedule_price_change"))
+        .arg(value(PERIOD_SECONDS, "Seconds between two payments, for install"))
+        .arg(value(
+          DISPUTE_WINDOW_SECONDS,
+          "Seconds payments are held in escrow, for install, payments are not held if left out",
+        ))
+        .arg(value(
+          INTERVAL_TYPE,
+          "1 daily, 2 weekly, 3 monthly or 4 yearly calendar periods in UTC, for install, every period-seconds if left out",
+        ))
+        .arg(value(
+          BILLING_DAY,
+          "Weekday (1 for Monday to 7) of a weekly plan, or day of the month (1 to 31) of a monthly or yearly plan, for install",
+        ).default_value("0"))
+        .arg(
+          Arg::with_name(SEAT_BASED)
+            .long(SEAT_BASED)
+            .help("Charge subscriptions for every member their subscriber adds, for install"),
+        )
+        .arg(value(
+          ERC20_CONTRACT_HASH,
+          "Hash of the erc20 contract, for install, add_token, schedule_price_change, set_unit_price and withdraw, the token to pay with for create_subscription_hash, or the token of a fixed amount for add_coupon",
+        ))
+        .arg(value(SPENDER, "Contract package hash of the EIP-1337 contract, for approve"))
+        .arg(value(AMOUNT, "Erc20 tokens to approve, for approve"))
+        .arg(value(PUBLIC_KEY, "Hex encoded public key of the subscriber"))
+        .arg(value(FROM, "Account hash of the subscriber"))
+        .arg(value(SUBSCRIPTION_ID, "Id of the subscription"))
+        .arg(value(SIGNATURE, "Hex encoded signature of the subscription hash"))
+        .arg(value(SUBSCRIPTION_HASH, "Hex encoded subscription hash"))
+        .arg(value(GRACE_PERIOD_SECONDS, "Seconds a payment may be late"))
+        .arg(value(OFFSET, "Index of the first item of the page"))
+        .arg(value(LIMIT, "Number of items of the page"))
+        .arg(value(STATUS, "Only list items with this status"))
+        .arg(value(RECIPIENT, "Account hash of the payee, for set_payee"))
+        .arg(value(BASIS_POINTS, "Share of each payment out of 10000, 0 removes the payee, for set_payee"))
+        .arg(value(PAYMENT_ID, "Id of a payment held in escrow"))
+        .arg(value(ACCOUNT, "Account hash of the publisher or a payee, for get_escrow_balance, or of the account to check, for get_active_subscription"))
+        .arg(value(COUPON_CODE, "Code of the coupon, for add_coupon and expire_coupon, or to redeem for create_subscription_hash"))
+        .arg(value(DISCOUNT_TYPE, "0 for a percentage in basis points, 1 for a fixed amount, for add_coupon"))
+        .arg(value(DISCOUNT, "Basis points out of 10000 or erc20 tokens taken off a payment, for add_coupon"))
+        .arg(value(PERIODS, "Number of payments discounted, 0 for every payment, for add_coupon").default_value("0"))
+        .arg(value(MAX_REDEMPTIONS, "Number of subscriptions that can redeem the coupon, 0 for no limit, for add_coupon").default_value("0"))
+        .arg(value(EXPIRES_AT, "Blocktime in milliseconds the coupon expires at, 0 for never, for add_coupon").default_value("0"))
+        .arg(value(MAX_AMOUNT_PER_PERIOD, "Most a payment can charge, usage and every seat included, for create_subscription_hash"))
+        .arg(
+          Arg::with_name(METERED)
+            .long(METERED)
+            .help("Charge the usage reported on top of the price, within the max amount per period, for create_subscription_hash"),
+        )
+        .arg(value(LIFETIME_CAP, "Most the subscription can charge over its lifetime, for create_subscription_hash"))
+        .arg(value(BENEFICIARY, "Account hash given access when the subscriber pays for another account, for create_subscription_hash and is_subscription_active"))
+        .arg(value(UNIT_PRICE, "Price of a unit of usage, 0 stops charging usage, for set_unit_price"))
+        .arg(value(ORACLE, "Account hash allowed to report usage besides the publisher, for set_usage_oracle"))
+        .arg(value(USAGE, "Units of usage to add to the next payment, for report_usage"))
+        .arg(value(MEMBER, "Account hash holding a seat of the subscription, for add_member and remove_member"))
+        .arg(value(EFFECTIVE_AT, "Blocktime in milliseconds a new price applies from, for schedule_price_change")),
+    )
+    .subcommand(
+      SubCommand::with_name(EXPLAIN_ERROR)
+        .about("Translate the error_message of a failed deploy into the contract error")
+        .arg(
+          Arg::with_name(ERROR_MESSAGE)
+            .required(true)
+            .help("The error_message, e.g. \"User error: 26\""),
+        ),
+    )
+}
@@ -0,0 +1,548 @@
+use std::{fs, path::Path, str::FromStr};
+
+use clap::ArgMatches;
+
+use subscription_deploy_builder::{Coupon, DeployParams, SubscriptionDeploy};
+
+use subscription_hash_signer::{
+  deploy_errors::{self, ContractError},
+  account_dictionary_key, balances_key, der, load_public_key, load_secret_key, subscription_hash, subscription_hash_from_hex, verify, ExternalSigner, LocalKeySigner,
+  SubscriptionSigner, SubscriptionTerms,
+};
+
+use types::{
+  account::AccountHash,
+  crypto::PublicKey,
+  AsymmetricType, ContractHash, ContractPackageHash, SecretKey, U256,
+};
+
+use crate::cli::{
+  self,
+  errors::{Error, Result},
+  output::Output,
+};
+
+const HASH_PREFIXES: &[&str] = &["contract-package-wasm", "contract-", "hash-"];
+
+pub fn hash(matches: &ArgMatches) -> Result<Output> {
+  let terms = Args::new(matches, cli::HASH).terms(None)?;
+
+  Ok(Output::new()
+    .field("subscription_data", terms.data())
+    .field("subscription_hash", hex::encode(subscription_hash(&terms))))
+}
+
+pub fn sign_hash(matches: &ArgMatches) -> Result<Output> {
+  let signer: Box<dyn SubscriptionSigner> = match matches.value_of(cli::EXTERNAL_SIGNER) {
+    Some(program) => {
+      let args = matches.values_of(cli::EXTERNAL_SIGNER_ARG).into_iter().flatten().map(String::from).collect();
+      Box::new(ExternalSigner::new(program, args, public_key(matches, cli::PUBLIC_KEY)?))
+    }
+    None => Box::new(LocalKeySigner::new(secret_key(matches)?)),
+  };
+
+  let public_key = signer.public_key().clone();
+  let message_bytes = Args::new(matches, cli::SIGN).message_bytes(&public_key)?;
+  let signature_hex = signer.sign(message_bytes)?;
+
+  let verified = verify(&public_key, message_bytes, &signature_hex).is_ok();
+
+  let output = Output::new()
+    .field("account_hash", public_key.to_account_hash().to_formatted_string())
+    .field("public_key", public_key.to_hex())
+    .field("subscription_hash", hex::encode(message_bytes))
+    .field("signature", signature_hex)
+    .field("verified", verified);
+
+  Ok(if verified { output } else { output.failed() })
+}
+
+pub fn verify_signature(matches: &ArgMatches) -> Result<Output> {
+  let public_key = public_key(matches, cli::PUBLIC_KEY)?;
+  let message_bytes = Args::new(matches, cli::VERIFY).message_bytes(&public_key)?;
+
+  let verified = match verify(&public_key, message_bytes, value(matches, cli::SIGNATURE)) {
+    Ok(()) => true,
+    Err(subscription_hash_signer::Error::SignatureVerificationFailed) => false,
+    Err(error) => return Err(error.into()),
+  };
+
+  let output = Output::new()
+    .field("subscription_hash", hex::encode(message_bytes))
+    .field("verified", verified);
+
+  Ok(if verified { output } else { output.failed() })
+}
+
+pub fn account_hash_of(matches: &ArgMatches) -> Result<Output> {
+  let public_key = match matches.value_of(cli::SECRET_KEY) {
+    Some(_) => PublicKey::from(&secret_key(matches)?),
+    None => public_key(matches, cli::PUBLIC_KEY)?,
+  };
+
+  Ok(Output::new()
+    .field("account_hash", public_key.to_account_hash().to_formatted_string())
+    .field("public_key", public_key.to_hex()))
+}
+
+pub fn account_key(matches: &ArgMatches) -> Result<Output> {
+  let public_key = load_public_key(value(matches, cli::PUBLIC_KEY))?;
+  let account_hash = public_key.to_account_hash();
+
+  Ok(Output::new()
+    .field("account_hash", account_hash.to_formatted_string())
+    .field("account_hash_hex", hex::encode(account_hash.value()))
+    .field("account_hash_base64", base64::encode(account_hash.value()))
+    .field("account_subscriptions_key", account_dictionary_key(account_hash))
+    .field("account_access_key", account_dictionary_key(account_hash))
+    .field("balances_key", balances_key(account_hash)))
+}
+
+pub fn keygen(matches: &ArgMatches) -> Result<Output> {
+  let output_dir = Path::new(value(matches, cli::OUTPUT_DIR));
+  let password = matches.value_of(cli::PASSWORD);
+
+  let secret_key = generate_secret_key(value(matches, cli::ALGORITHM))?;
+  let public_key = PublicKey::from(&secret_key);
+
+  let secret_key_pem = match password {
+    Some(password) => der::to_encrypted_pem(&secret_key, password.as_bytes())?,
+    None => der::to_pem(&secret_key)?,
+  };
+
+  // The casper-client keygen layout
+  let files = [
+    (output_dir.join("secret_key.pem"), secret_key_pem),
+    (output_dir.join("public_key.pem"), der::public_key_to_pem(&public_key)?),
+    (output_dir.join("public_key_hex"), public_key.to_hex()),
+  ];
+
+  if !matches.is_present(cli::FORCE) {
+    if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+      return Err(Error::FileExists(path.display().to_string()));
+    }
+  }
+
+  fs::create_dir_all(output_dir).map_err(|error| Error::Write {
+    path: output_dir.display().to_string(),
+    error,
+  })?;
+
+  for (path, contents) in &files {
+    fs::write(path, contents).map_err(|error| Error::Write {
+      path: path.display().to_string(),
+      error,
+    })?;
+  }
+
+  Ok(Output::new()
+    .field("secret_key", files[0].0.display().to_string())
+    .field("account_hash", public_key.to_account_hash().to_formatted_string())
+    .field("public_key", public_key.to_hex()))
+}
+
+pub fn make_deploy(matches: &ArgMatches) -> Result<Output> {
+  let entry_point = value(matches, cli::ENTRY_POINT);
+
+  let params = DeployParams {
+    chain_name: value(matches, cli::CHAIN_NAME).to_string(),
+    secret_key: value(matches, cli::SECRET_KEY).to_string(),
+    payment_amount: number(matches, cli::PAYMENT_AMOUNT)?,
+    ttl: value(matches, cli::TTL).to_string(),
+    gas_price: number(matches, cli::GAS_PRICE)?,
+  };
+
+  let deploy = Args::new(matches, entry_point).deploy()?;
+
+  let output_path = matches.value_of(cli::OUTPUT).unwrap_or("");
+  deploy.write(&params, output_path)?;
+
+  // Without a file the deploy itself was printed to stdout
+  if output_path.is_empty() {
+    return Ok(Output::new());
+  }
+
+  Ok(Output::new()
+    .field("entry_point", entry_point)
+    .field("deploy", output_path))
+}
+
+pub fn explain_error(matches: &ArgMatches) -> Result<Output> {
+  let error_message = value(matches, cli::ERROR_MESSAGE);
+
+  let mut output = Output::new().field("explanation", deploy_errors::explain(error_message));
+
+  match deploy_errors::api_error(error_message).and_then(ContractError::from_api_error) {
+    Some(contract_error) => {
+      output = output
+        .field("error", format!("{:?}", contract_error))
+        .field("code", contract_error.code())
+        .field("description", contract_error.description());
+    }
+    None => output = output.failed(),
+  }
+
+  Ok(output)
+}
+
+/// The arguments of a command, each read only when the command or entry point needs it.
+struct Args<'a> {
+  matches: &'a ArgMatches<'a>,
+  command: &'a str,
+}
+
+impl<'a> Args<'a> {
+  fn new(matches: &'a ArgMatches<'a>, command: &'a str) -> Args<'a> {
+    Args { matches, command }
+  }
+
+  /// The subscription terms, `--from` defaulting to `default_from`.
+  fn terms(&self, default_from: Option<AccountHash>) -> Result<SubscriptionTerms> {
+    let from = match (self.matches.value_of(cli::FROM), default_from) {
+      (None, Some(from)) => from,
+      _ => self.account_hash(cli::FROM)?,
+    };
+
+    Ok(SubscriptionTerms {
+      to: self.account_hash(cli::TO)?,
+      from,
+      token_amount: self.u256(cli::TOKEN_AMOUNT)?,
+      period_seconds: self.number(cli::PERIOD_SECONDS)?,
+      subscription_id: self.subscription_id()?,
+      erc20_contract_hash: ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?),
+      max_amount_per_period: self.optional_u256(cli::MAX_AMOUNT_PER_PERIOD)?,
+      lifetime_cap: self.optional_u256(cli::LIFETIME_CAP)?,
+      beneficiary: self.optional_account_hash(cli::BENEFICIARY)?,
+      metered: self.matches.is_present(cli::METERED),
+    })
+  }
+
+  /// The `--subscription-hash` to sign or verify, or the hash of the terms, which by default
+  /// are the terms of the account of `public_key`.
+  fn message_bytes(&self, public_key: &PublicKey) -> Result<[u8; 32]> {
+    if let Some(subscription_hash) = self.matches.value_of(cli::SUBSCRIPTION_HASH) {
+      return subscription_hash_from_hex(subscription_hash).map_err(Error::invalid_argument(cli::SUBSCRIPTION_HASH));
+    }
+
+    let terms = self.terms(Some(public_key.to_account_hash()))?;
+    Ok(subscription_hash(&terms))
+  }
+
+  fn deploy(&self) -> Result<SubscriptionDeploy> {
+    if self.command == "install" {
+      let wasm_path = self.required(cli::WASM)?;
+      let to = self.account_hash(cli::TO)?;
+      let token_amount = self.u256(cli::TOKEN_AMOUNT)?;
+      let period_seconds = self.number(cli::PERIOD_SECONDS)?;
+      let erc20_contract_hash = ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?);
+
+      let deploy = match self.matches.value_of(cli::DISPUTE_WINDOW_SECONDS) {
+        Some(_) => SubscriptionDeploy::install_with_escrow(
+          wasm_path,
+          to,
+          token_amount,
+          period_seconds,
+          erc20_contract_hash,
+          self.number(cli::DISPUTE_WINDOW_SECONDS)?,
+        ),
+        None => SubscriptionDeploy::install(wasm_path, to, token_amount, period_seconds, erc20_contract_hash),
+      };
+
+      let deploy = match self.matches.value_of(cli::INTERVAL_TYPE) {
+        Some(_) => deploy.with_billing_interval(self.number(cli::INTERVAL_TYPE)?, self.number(cli::BILLING_DAY)?),
+        None => deploy,
+      };
+
+      return Ok(if self.matches.is_present(cli::SEAT_BASED) { deploy.with_seats() } else { deploy });
+    }
+
+    let contract_hash = ContractHash::new(self.hash(cli::CONTRACT_HASH)?);
+
+    let deploy = match self.command {
+      "approve" => SubscriptionDeploy::approve(
+        contract_hash,
+        ContractPackageHash::new(self.hash(cli::SPENDER)?),
+        self.u256(cli::AMOUNT)?,
+      ),
+      "create_subscription_hash" => {
+        let public_key = public_key_from_hex(cli::PUBLIC_KEY, self.required(cli::PUBLIC_KEY)?)?;
+        let from = self.account_hash(cli::FROM)?;
+
+        let deploy = match self.matches.value_of(cli::ERC20_CONTRACT_HASH) {
+          Some(_) => SubscriptionDeploy::create_subscription_hash_with_token(
+            contract_hash,
+            &public_key,
+            from,
+            ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?),
+          ),
+          None => SubscriptionDeploy::create_subscription_hash(contract_hash, &public_key, from),
+        };
+
+        let deploy = match self.matches.value_of(cli::COUPON_CODE) {
+          Some(coupon_code) => deploy.with_coupon_code(coupon_code),
+          None => deploy,
+        };
+
+        let deploy = match self.optional_u256(cli::MAX_AMOUNT_PER_PERIOD)? {
+          Some(max_amount_per_period) => deploy.with_max_amount_per_period(max_amount_per_period),
+          None => deploy,
+        };
+
+        let deploy = match self.optional_u256(cli::LIFETIME_CAP)? {
+          Some(lifetime_cap) => deploy.with_lifetime_cap(lifetime_cap),
+          None => deploy,
+        };
+
+        let deploy = if self.matches.is_present(cli::METERED) { deploy.with_metered() } else { deploy };
+
+        match self.optional_account_hash(cli::BENEFICIARY)? {
+          Some(beneficiary) => deploy.with_beneficiary(beneficiary),
+          None => deploy,
+        }
+      }
+      "get_subscription_hash" => SubscriptionDeploy::get_subscription_hash(contract_hash, self.subscription_id()?),
+      "execute_subscription" => SubscriptionDeploy::execute_subscription(
+        contract_hash,
+        self.required(cli::SIGNATURE)?,
+        self.subscription_id()?,
+      )?,
+      "cancel_subscription" => SubscriptionDeploy::cancel_subscription(
+        contract_hash,
+        self.required(cli::SIGNATURE)?,
+        self.subscription_id()?,
+      )?,
+      "is_subscription_ready" => SubscriptionDeploy::is_subscription_ready(
+        contract_hash,
+        self.required(cli::SIGNATURE)?,
+        self.subscription_id()?,
+      )?,
+      "is_subscription_active" => {
+        let deploy = SubscriptionDeploy::is_subscription_active(
+          contract_hash,
+          self.required(cli::SUBSCRIPTION_HASH)?,
+          self.number(cli::GRACE_PERIOD_SECONDS)?,
+        )?;
+
+        match self.optional_account_hash(cli::BENEFICIARY)? {
+          Some(beneficiary) => deploy.with_beneficiary(beneficiary),
+          None => deploy,
+        }
+      }
+      "get_subscription" => SubscriptionDeploy::get_subscription(contract_hash, self.subscription_id()?),
+      "get_next_payment_time" => SubscriptionDeploy::get_next_payment_time(contract_hash, self.subscription_id()?),
+      "get_subscription_status" => SubscriptionDeploy::get_subscription_status(contract_hash, self.subscription_id()?),
+      "get_active_subscription" => SubscriptionDeploy::get_active_subscription(
+        contract_hash,
+        self.account_hash(cli::ACCOUNT)?,
+        self.number(cli::GRACE_PERIOD_SECONDS)?,
+      ),
+      "get_account_subscriptions" => {
+        SubscriptionDeploy::get_account_subscriptions(contract_hash, self.account_hash(cli::FROM)?)
+      }
+      "get_plan" => SubscriptionDeploy::get_plan(contract_hash),
+      "get_token_prices" => SubscriptionDeploy::get_token_prices(contract_hash),
+      "add_token" => SubscriptionDeploy::add_token(
+        contract_hash,
+        ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?),
+        self.u256(cli::TOKEN_AMOUNT)?,
+      ),
+      "get_pending_prices" => SubscriptionDeploy::get_pending_prices(contract_hash),
+      "schedule_price_change" => SubscriptionDeploy::schedule_price_change(
+        contract_hash,
+        ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?),
+        self.u256(cli::TOKEN_AMOUNT)?,
+        self.number(cli::EFFECTIVE_AT)?,
+      ),
+      "get_payees" => SubscriptionDeploy::get_payees(contract_hash),
+      "set_payee" => SubscriptionDeploy::set_payee(
+        contract_hash,
+        self.account_hash(cli::RECIPIENT)?,
+        self.number(cli::BASIS_POINTS)?,
+      ),
+      "get_escrow_balance" => SubscriptionDeploy::get_escrow_balance(contract_hash, self.account_hash(cli::ACCOUNT)?),
+      "get_escrow_held" => SubscriptionDeploy::get_escrow_held(contract_hash),
+      "get_escrow_payment" => SubscriptionDeploy::get_escrow_payment(contract_hash, self.number(cli::PAYMENT_ID)?),
+      "refund_payment" => SubscriptionDeploy::refund_payment(contract_hash, self.number(cli::PAYMENT_ID)?),
+      "chargeback_payment" => SubscriptionDeploy::chargeback_payment(contract_hash, self.number(cli::PAYMENT_ID)?),
+      "withdraw" => SubscriptionDeploy::withdraw(contract_hash, ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?)),
+      "get_unit_prices" => SubscriptionDeploy::get_unit_prices(contract_hash),
+      "set_unit_price" => SubscriptionDeploy::set_unit_price(
+        contract_hash,
+        ContractHash::new(self.hash(cli::ERC20_CONTRACT_HASH)?),
+        self.u256(cli::UNIT_PRICE)?,
+      ),
+      "set_usage_oracle" => SubscriptionDeploy::set_usage_oracle(contract_hash, self.account_hash(cli::ORACLE)?),
+      "report_usage" => {
+        SubscriptionDeploy::report_usage(contract_hash, self.subscription_id()?, self.number(cli::USAGE)?)
+      }
+      "add_member" => {
+        SubscriptionDeploy::add_member(contract_hash, self.subscription_id()?, self.account_hash(cli::MEMBER)?)
+      }
+      "remove_member" => {
+        SubscriptionDeploy::remove_member(contract_hash, self.subscription_id()?, self.account_hash(cli::MEMBER)?)
+      }
+      "get_members" => SubscriptionDeploy::get_members(contract_hash, self.subscription_id()?),
+      "add_coupon" => {
+        let coupon = Coupon {
+          discount_type: self.number(cli::DISCOUNT_TYPE)?,
+          discount: self.u256(cli::DISCOUNT)?,
+          erc20_contract_hash: self
+            .matches
+            .value_of(cli::ERC20_CONTRACT_HASH)
+            .map(|hash| parse_hash(cli::ERC20_CONTRACT_HASH, hash).map(ContractHash::new))
+            .transpose()?,
+          periods: self.number(cli::PERIODS)?,
+          max_redemptions: self.number(cli::MAX_REDEMPTIONS)?,
+          expires_at: self.number(cli::EXPIRES_AT)?,
+        };
+
+        SubscriptionDeploy::add_coupon(contract_hash, self.required(cli::COUPON_CODE)?, &coupon)
+      }
+      "expire_coupon" => SubscriptionDeploy::expire_coupon(contract_hash, self.required(cli::COUPON_CODE)?),
+      "get_subscriber_count" => SubscriptionDeploy::get_subscriber_count(contract_hash),
+      "get_subscription_count" => SubscriptionDeploy::get_subscription_count(contract_hash),
+      "list_subscribers" => SubscriptionDeploy::list_subscribers(
+        contract_hash,
+        self.number(cli::OFFSET)?,
+        self.number(cli::LIMIT)?,
+        self.status()?,
+      ),
+      "list_subscriptions" => SubscriptionDeploy::list_subscriptions(
+        contract_hash,
+        self.number(cli::OFFSET)?,
+        self.number(cli::LIMIT)?,
+        self.status()?,
+      ),
+      _ => unreachable!("clap only accepts the entry points of cli::ENTRY_POINTS"),
+    };
+
+    Ok(deploy)
+  }
+
+  fn required(&self, name: &'static str) -> Result<&'a str> {
+    self.matches.value_of(name).ok_or_else(|| Error::MissingArgument {
+      command: self.command.to_string(),
+      name,
+    })
+  }
+
+  fn number<T: FromStr>(&self, name: &'static str) -> Result<T>
+  where
+    T::Err: ToString,
+  {
+    parse_number(name, self.required(name)?)
+  }
+
+  fn u256(&self, name: &'static str) -> Result<U256> {
+    parse_u256(name, self.required(name)?)
+  }
+
+  fn optional_u256(&self, name: &'static str) -> Result<Option<U256>> {
+    self.matches.value_of(name).map(|value| parse_u256(name, value)).transpose()
+  }
+
+  fn account_hash(&self, name: &'static str) -> Result<AccountHash> {
+    parse_account_hash(name, self.required(name)?)
+  }
+
+  fn optional_account_hash(&self, name: &'static str) -> Result<Option<AccountHash>> {
+    self.matches.value_of(name).map(|value| parse_account_hash(name, value)).transpose()
+  }
+
+  fn hash(&self, name: &'static str) -> Result<[u8; 32]> {
+    parse_hash(name, self.required(name)?)
+  }
+
+  fn subscription_id(&self) -> Result<u64> {
+    self.number(cli::SUBSCRIPTION_ID)
+  }
+
+  fn status(&self) -> Result<Option<u8>> {
+    self.matches
+      .value_of(cli::STATUS)
+      .map(|status| parse_number(cli::STATUS, status))
+      .transpose()
+  }
+}
+
+/// A value clap already made sure is present.
+fn value<'a>(matches: &'a ArgMatches, name: &str) -> &'a str {
+  matches.value_of(name).unwrap()
+}
+
+fn number<T: FromStr>(matches: &ArgMatches, name: &'static str) -> Result<T>
+where
+  T::Err: ToString,
+{
+  parse_number(name, value(matches, name))
+}
+
+fn public_key(matches: &ArgMatches, name: &'static str) -> Result<PublicKey> {
+  public_key_from_hex(name, value(matches, name))
+}
+
+/// The `--secret-key` file, decrypted with `--password` if given.
+fn secret_key(matches: &ArgMatches) -> Result<SecretKey> {
+  let password = matches.value_of(cli::PASSWORD).map(str::as_bytes);
+  Ok(load_secret_key(value(matches, cli::SECRET_KEY), password)?)
+}
+
+fn public_key_from_hex(name: &'static str, public_key_hex: &str) -> Result<PublicKey> {
+  PublicKey::from_hex(public_key_hex).map_err(Error::invalid_argument(name))
+}
+
+fn parse_number<T: FromStr>(name: &'static str, value: &str) -> Result<T>
+where
+  T::Err: ToString,
+{
+  value.parse::<T>().map_err(Error::invalid_argument(name))
+}
+
+fn parse_u256(name: &'static str, value: &str) -> Result<U256> {
+  U256::from_dec_str(value).map_err(|error| Error::InvalidArgument {
+    name,
+    reason: format!("{:?}", error),
+  })
+}
+
+/// An account hash, either formatted as `account-hash-<hex>` or as bare hex.
+fn parse_account_hash(name: &'static str, value: &str) -> Result<AccountHash> {
+  if value.starts_with("account-hash-") {
+    return AccountHash::from_formatted_str(value).map_err(|error| Error::InvalidArgument {
+      name,
+      reason: format!("{:?}", error),
+    });
+  }
+
+  parse_hash(name, value).map(AccountHash::new)
+}
+
+/// A contract or package hash, as bare hex or with one of the `hash-`, `contract-` and
+/// `contract-package-wasm` prefixes.
+fn parse_hash(name: &'static str, value: &str) -> Result<[u8; 32]> {
+  let hex_value = HASH_PREFIXES
+    .iter()
+    .find_map(|prefix| value.strip_prefix(prefix))
+    .unwrap_or(value);
+
+  let mut bytes = [0u8; 32];
+  hex::decode_to_slice(hex_value, &mut bytes as &mut [u8]).map_err(Error::invalid_argument(name))?;
+  Ok(bytes)
+}
+
+/// A secret key from random bytes, drawn again in the rare case they are not a secp256k1 scalar.
+fn generate_secret_key(algorithm: &str) -> Result<SecretKey> {
+  loop {
+    let mut bytes = [0u8; 32];
+    openssl::rand::rand_bytes(&mut bytes)?;
+
+    let secret_key = match algorithm {
+      cli::SECP256K1 => SecretKey::secp256k1_from_bytes(bytes),
+      _ => SecretKey::ed25519_from_bytes(bytes),
+    };
+
+    if let Ok(secret_key) = secret_key {
+      return Ok(secret_key);
+    }
+  }
+}
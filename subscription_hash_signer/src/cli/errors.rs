@@ -0,0 +1,68 @@
+use std::{io, result};
+
+use thiserror::Error;
+
+use subscription_hash_signer::der;
+
+/// A specialized `std::result::Result` type for command errors.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Command errors.
+#[derive(Debug, Error)]
+pub enum Error {
+  /// Error loading a key, signing or verifying.
+  #[error("{0}")]
+  Signer(#[from] subscription_hash_signer::Error),
+
+  /// Error encoding a generated key.
+  #[error("encoding key: {0}")]
+  Key(#[from] der::errors::Error),
+
+  /// Error writing a key file.
+  #[error("writing {path}: {error}")]
+  Write {
+    /// The file that could not be written.
+    path: String,
+    /// The underlying error.
+    error: io::Error,
+  },
+
+  /// Error resulting when `keygen` would overwrite a key file without `--force`.
+  #[error("{0} already exists, use --force to overwrite it")]
+  FileExists(String),
+
+  /// Error generating random key bytes.
+  #[error("generating keys: {0}")]
+  Keygen(#[from] openssl::error::ErrorStack),
+
+  /// Error building or writing a deploy.
+  #[error("{0}")]
+  Deploy(#[from] subscription_deploy_builder::Error),
+
+  /// Error resulting when a command line argument is not valid.
+  #[error("invalid argument --{name}: {reason}")]
+  InvalidArgument {
+    /// The name of the argument.
+    name: &'static str,
+    /// Why it was rejected.
+    reason: String,
+  },
+
+  /// Error resulting when a command or entry point needs an argument that was not given.
+  #[error("{command} requires --{name}")]
+  MissingArgument {
+    /// The command, or the entry point of the deploy.
+    command: String,
+    /// The name of the argument.
+    name: &'static str,
+  },
+}
+
+impl Error {
+  pub fn invalid_argument<E: ToString>(name: &'static str) -> impl Fn(E) -> Error {
+    move |error| Error::InvalidArgument {
+      name,
+      reason: error.to_string(),
+    }
+  }
+}
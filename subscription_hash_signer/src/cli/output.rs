@@ -0,0 +1,52 @@
+use serde_json::{Map, Value};
+
+/// What a command prints: named fields, as `name: value` lines or as a JSON object.
+#[derive(Debug, Default)]
+pub struct Output {
+  fields: Vec<(&'static str, Value)>,
+  failed: bool,
+}
+
+impl Output {
+  pub fn new() -> Output {
+    Output::default()
+  }
+
+  pub fn field<T: Into<Value>>(mut self, name: &'static str, value: T) -> Output {
+    self.fields.push((name, value.into()));
+    self
+  }
+
+  /// Mark the command as failed, the process exits with status 1 after printing.
+  pub fn failed(mut self) -> Output {
+    self.failed = true;
+    self
+  }
+
+  pub fn is_failed(&self) -> bool {
+    self.failed
+  }
+
+  pub fn print(&self, json: bool) {
+    if self.fields.is_empty() {
+      return;
+    }
+
+    if json {
+      let object: Map<String, Value> = self
+        .fields
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+      println!("{}", Value::Object(object));
+      return;
+    }
+
+    for (name, value) in &self.fields {
+      match value {
+        Value::String(string) => println!("{}: {}", name, string),
+        _ => println!("{}: {}", name, value),
+      }
+    }
+  }
+}
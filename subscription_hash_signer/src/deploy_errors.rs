@@ -0,0 +1,46 @@
+use types::ApiError;
+
+#[path = "../../casper-contract-eip-1337/src/errors.rs"]
+mod contract_errors;
+
+pub use contract_errors::ContractError;
+
+/// How the execution engine reports a user error in a deploy result's `error_message`.
+const USER_ERROR_PREFIX: &str = "User error: ";
+
+/// Pulls the `ApiError` out of a deploy result's `error_message`, e.g. `User error: 26`.
+/// A bare number is accepted too: below 65536 it is read as the user error code,
+/// otherwise as the raw `u32` an `ApiError` encodes to.
+pub fn api_error(error_message: &str) -> Option<ApiError> {
+  let message = error_message.trim();
+
+  if let Some(index) = message.find(USER_ERROR_PREFIX) {
+    let code: String = message[index + USER_ERROR_PREFIX.len()..]
+      .chars()
+      .take_while(|c| c.is_ascii_digit())
+      .collect();
+    return code.parse::<u16>().ok().map(ApiError::User);
+  }
+
+  match message.parse::<u32>() {
+    Ok(code) if code <= u16::MAX as u32 => Some(ApiError::User(code as u16)),
+    Ok(code) => Some(ApiError::from(code)),
+    Err(_) => None,
+  }
+}
+
+/// Translates a deploy result's `error_message` into a readable message.
+pub fn explain(error_message: &str) -> String {
+  match api_error(error_message) {
+    Some(error) => match ContractError::from_api_error(error) {
+      Some(contract_error) => format!(
+        "{:?} (User error: {}): {}",
+        contract_error,
+        contract_error.code(),
+        contract_error.description()
+      ),
+      None => format!("{:?} is not an EIP-1337 contract error", error),
+    },
+    None => format!("no error code found in {:?}", error_message),
+  }
+}
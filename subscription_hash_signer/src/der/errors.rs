@@ -2,6 +2,7 @@ use std::result;
 
 use base64::DecodeError;
 use hex::FromHexError;
+use openssl::error::ErrorStack;
 use pem::PemError;
 use signature::Error as SignatureError;
 use thiserror::Error;
@@ -30,6 +31,10 @@ pub enum Error {
     #[error("pem error: {0}")]
     FromPem(String),
 
+    /// Error decrypting a password protected key, e.g. because of a wrong password.
+    #[error("decryption error: {0}")]
+    Decryption(String),
+
     /// DER format error.
     #[error("der error: {0}")]
     FromDer(#[from] derp::Error),
@@ -49,6 +54,12 @@ impl From<PemError> for Error {
     }
 }
 
+impl From<ErrorStack> for Error {
+    fn from(error: ErrorStack) -> Self {
+        Error::Decryption(error.to_string())
+    }
+}
+
 impl From<crypto::Error> for Error {
     fn from(error: crypto::Error) -> Self {
         match error {
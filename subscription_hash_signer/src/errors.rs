@@ -0,0 +1,55 @@
+use std::{io, result};
+
+use hex::FromHexError;
+use thiserror::Error;
+
+use types::crypto;
+
+use crate::der;
+
+/// A specialized `std::result::Result` type for signer errors.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Signer errors.
+#[derive(Debug, Error)]
+pub enum Error {
+  /// Error reading a key file.
+  #[error("reading {path}: {error}")]
+  Io {
+    /// The file that could not be read.
+    path: String,
+    /// The underlying error.
+    error: io::Error,
+  },
+
+  /// Error parsing or using a key.
+  #[error("{0}")]
+  Key(#[from] der::errors::Error),
+
+  /// Error resulting when decoding a hash or signature from hex.
+  #[error("parsing from hex: {0}")]
+  FromHex(#[from] FromHexError),
+
+  /// Error resulting when the key is not an Ed25519 key, the only type the contract verifies.
+  #[error("only Ed25519 keys can sign subscriptions")]
+  UnsupportedKeyType,
+
+  /// Error resulting when an external signer command fails or returns an invalid signature.
+  #[error("external signer {program}: {reason}")]
+  ExternalSigner {
+    /// The signer command.
+    program: String,
+    /// Why it failed.
+    reason: String,
+  },
+
+  /// Error resulting when the signature was not made by the key for the subscription hash.
+  #[error("signature verification failed")]
+  SignatureVerificationFailed,
+}
+
+impl From<crypto::Error> for Error {
+  fn from(error: crypto::Error) -> Self {
+    Error::Key(error.into())
+  }
+}
@@ -0,0 +1,17 @@
+//! Off-chain counterpart of `runtime::blake2b`. Shared with the test support through `#[path]`,
+//! so the contract parity tests exercise the hash the signer computes.
+
+use blake2::{
+  digest::{Update, VariableOutput},
+  VarBlake2b,
+};
+
+/// The 32 byte blake2b hash of the subscription data, as `runtime::blake2b` computes it.
+pub fn blake2b<T: AsRef<[u8]>>(data: T) -> [u8; 32] {
+  let mut hasher = VarBlake2b::new(32).unwrap();
+  hasher.update(data);
+
+  let mut hash = [0u8; 32];
+  hasher.finalize_variable(|result| hash.copy_from_slice(result));
+  hash
+}
@@ -0,0 +1,252 @@
+//! Off-chain side of the EIP-1337 subscription contract: computes subscription hashes from their
+//! terms, signs them with the subscriber's Ed25519 key and verifies signatures exactly as
+//! `execute_subscription` does.
+//!
+//! Keys can stay off this machine: any [`SubscriptionSigner`] signs, e.g. an [`ExternalSigner`]
+//! running a command that talks to a hardware wallet.
+//!
+//! The `subscription_hash_signer` binary is a command line wrapper around this library.
+
+use std::{fs, path::Path};
+
+use ed25519_dalek::Signer;
+
+use types::{
+  account::AccountHash,
+  bytesrepr::ToBytes,
+  crypto::{PublicKey, SecretKey, Signature},
+  AsymmetricType, Key,
+};
+
+pub mod der;
+pub mod deploy_errors;
+mod errors;
+mod hash;
+#[path = "../../casper-contract-eip-1337/src/keys.rs"]
+mod keys;
+mod signing;
+#[path = "../../casper-contract-eip-1337/src/terms.rs"]
+mod terms;
+
+pub use deploy_errors::ContractError;
+pub use errors::{Error, Result};
+pub use signing::{ExternalSigner, LocalKeySigner, SubscriptionSigner};
+pub use terms::SubscriptionTerms;
+
+/// Length of an Ed25519 signature.
+const SIGNATURE_LENGTH: usize = 64;
+
+/// The subscription hash of the terms, as `_get_subscription_hash` computes it on-chain.
+pub fn subscription_hash(terms: &SubscriptionTerms) -> [u8; 32] {
+  hash::blake2b(terms.data())
+}
+
+/// Decode a hex encoded subscription hash, as stored in the `hashes` dictionary.
+pub fn subscription_hash_from_hex(subscription_hash: &str) -> Result<[u8; 32]> {
+  let mut bytes = [0u8; 32];
+  hex::decode_to_slice(subscription_hash, &mut bytes as &mut [u8])?;
+  Ok(bytes)
+}
+
+/// Sign a subscription hash, returning the hex encoded signature the contract takes.
+/// Only Ed25519 keys are supported, the only type the contract verifies.
+pub fn sign(secret_key: &SecretKey, subscription_hash: [u8; 32]) -> Result<String> {
+  match secret_key {
+    SecretKey::Ed25519(secret_key) => {
+      let secret = ed25519_dalek::SecretKey::from_bytes(secret_key.as_bytes())
+        .map_err(|error| Error::Key(der::errors::Error::AsymmetricKey(error.to_string())))?;
+      let public: ed25519_dalek::PublicKey = (&secret).into();
+      let pair = ed25519_dalek::Keypair { secret, public };
+
+      let signature: ed25519_dalek::Signature = pair.sign(&subscription_hash);
+      Ok(hex::encode(signature.to_bytes()))
+    }
+    _ => Err(Error::UnsupportedKeyType),
+  }
+}
+
+/// Verify a hex encoded signature of a subscription hash the way `execute_subscription` does.
+pub fn verify(public_key: &PublicKey, subscription_hash: [u8; 32], signature: &str) -> Result<()> {
+  let mut signature_bytes = [0u8; SIGNATURE_LENGTH];
+  hex::decode_to_slice(signature, &mut signature_bytes as &mut [u8])?;
+
+  match (public_key, Signature::ed25519(signature_bytes)?) {
+    (PublicKey::Ed25519(public_key), Signature::Ed25519(signature)) => public_key
+      .verify_strict(&subscription_hash, &signature)
+      .map_err(|_| Error::SignatureVerificationFailed),
+    _ => Err(Error::UnsupportedKeyType),
+  }
+}
+
+/// Read a secret key file in any format [`der::parse`] reads, decrypted with `password` if given.
+pub fn load_secret_key<P: AsRef<Path>>(path: P, password: Option<&[u8]>) -> Result<SecretKey> {
+  let path = path.as_ref();
+  let contents = fs::read_to_string(path).map_err(|error| Error::Io {
+    path: path.display().to_string(),
+    error,
+  })?;
+
+  Ok(der::parse(&contents, password)?)
+}
+
+/// Read a public key given as hex, as a `PUBLIC KEY` PEM, or as the path of a file holding
+/// either, e.g. `public_key_hex` or `public_key.pem` of casper-client.
+pub fn load_public_key(input: &str) -> Result<PublicKey> {
+  let path = Path::new(input);
+  let contents = if path.is_file() {
+    fs::read_to_string(path).map_err(|error| Error::Io {
+      path: path.display().to_string(),
+      error,
+    })?
+  } else {
+    input.to_string()
+  };
+
+  let contents = contents.trim();
+  if contents.starts_with("-----BEGIN") {
+    return Ok(der::public_key_from_pem(contents)?);
+  }
+  Ok(PublicKey::from_hex(contents)?)
+}
+
+/// Item key of an account in the contract's dictionaries keyed by account, `account_subscriptions`,
+/// `account_access` and the escrow balances: the account hash as lowercase hex. The dictionaries
+/// keyed by subscription, such as `hashes`, take the decimal subscription id listed there instead.
+pub fn account_dictionary_key(account: AccountHash) -> String {
+  keys::account_key(account)
+}
+
+/// Item key of an account in the `balances` dictionary of the ERC-20 contract: its `Key::Account`
+/// bytes as base64.
+pub fn balances_key(account: AccountHash) -> String {
+  let key_bytes = Key::Account(account).to_bytes().expect("a key always serializes");
+  base64::encode(key_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use types::{ContractHash, U256};
+
+  use super::*;
+
+  fn secret_key() -> SecretKey {
+    SecretKey::ed25519_from_bytes([7u8; 32]).unwrap()
+  }
+
+  fn terms(subscription_id: u64) -> SubscriptionTerms {
+    SubscriptionTerms {
+      to: AccountHash::new([1u8; 32]),
+      from: AccountHash::new([2u8; 32]),
+      token_amount: U256::from(10),
+      period_seconds: 3600,
+      subscription_id,
+      erc20_contract_hash: ContractHash::new([9u8; 32]),
+      max_amount_per_period: None,
+      lifetime_cap: None,
+      beneficiary: None,
+      metered: false,
+    }
+  }
+
+  #[test]
+  fn test_sign_and_verify() {
+    let secret_key = secret_key();
+    let public_key = PublicKey::from(&secret_key);
+    let subscription_hash = subscription_hash(&terms(0));
+
+    let signature = sign(&secret_key, subscription_hash).unwrap();
+    assert_eq!(signature.len(), SIGNATURE_LENGTH * 2);
+    verify(&public_key, subscription_hash, &signature).unwrap();
+  }
+
+  #[test]
+  fn test_verify_rejects_other_subscription() {
+    let secret_key = secret_key();
+    let public_key = PublicKey::from(&secret_key);
+
+    let signature = sign(&secret_key, subscription_hash(&terms(0))).unwrap();
+    assert!(matches!(
+      verify(&public_key, subscription_hash(&terms(1)), &signature),
+      Err(Error::SignatureVerificationFailed)
+    ));
+  }
+
+  #[test]
+  fn test_verify_rejects_other_key() {
+    let other_key = PublicKey::from(&SecretKey::ed25519_from_bytes([8u8; 32]).unwrap());
+    let subscription_hash = subscription_hash(&terms(0));
+
+    let signature = sign(&secret_key(), subscription_hash).unwrap();
+    assert!(matches!(
+      verify(&other_key, subscription_hash, &signature),
+      Err(Error::SignatureVerificationFailed)
+    ));
+  }
+
+  #[test]
+  fn test_verify_rejects_malformed_signature() {
+    let public_key = PublicKey::from(&secret_key());
+    let subscription_hash = subscription_hash(&terms(0));
+
+    assert!(matches!(verify(&public_key, subscription_hash, "abcd"), Err(Error::FromHex(_))));
+    assert!(matches!(verify(&public_key, subscription_hash, &"zz".repeat(64)), Err(Error::FromHex(_))));
+  }
+
+  #[test]
+  fn test_sign_rejects_secp256k1() {
+    let secret_key = SecretKey::secp256k1_from_bytes([7u8; 32]).unwrap();
+    assert!(matches!(sign(&secret_key, [0u8; 32]), Err(Error::UnsupportedKeyType)));
+  }
+
+  #[test]
+  fn test_every_term_changes_the_hash() {
+    let base = terms(0);
+    let hash = subscription_hash(&base);
+
+    let changed = vec![
+      SubscriptionTerms { to: AccountHash::new([3u8; 32]), ..base.clone() },
+      SubscriptionTerms { from: AccountHash::new([3u8; 32]), ..base.clone() },
+      SubscriptionTerms { token_amount: U256::from(11), ..base.clone() },
+      SubscriptionTerms { period_seconds: 3601, ..base.clone() },
+      SubscriptionTerms { erc20_contract_hash: ContractHash::new([3u8; 32]), ..base.clone() },
+      SubscriptionTerms { max_amount_per_period: Some(U256::from(10)), ..base.clone() },
+      SubscriptionTerms { lifetime_cap: Some(U256::from(10)), ..base.clone() },
+      SubscriptionTerms { beneficiary: Some(AccountHash::new([3u8; 32])), ..base.clone() },
+      SubscriptionTerms { max_amount_per_period: Some(U256::from(10)), metered: true, ..base.clone() },
+      terms(1),
+    ];
+    for terms in changed {
+      assert_ne!(subscription_hash(&terms), hash);
+    }
+  }
+
+  #[test]
+  fn test_account_keys() {
+    let account = AccountHash::new([0xabu8; 32]);
+
+    assert_eq!(account_dictionary_key(account), "ab".repeat(32));
+    // `00` is the tag of `Key::Account`, as scripts/base64_key.sh used to prepend
+    assert_eq!(balances_key(account), base64::encode([&[0u8][..], &[0xabu8; 32][..]].concat()));
+  }
+
+  #[test]
+  fn test_load_public_key() {
+    let public_key = PublicKey::from(&secret_key());
+    let pem = der::public_key_to_pem(&public_key).unwrap();
+
+    assert_eq!(load_public_key(&public_key.to_hex()).unwrap(), public_key);
+    assert_eq!(load_public_key(&pem).unwrap(), public_key);
+
+    let path = format!("{}/src/der/fixtures/ed25519_public.pem", env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(load_public_key(&path).unwrap(), public_key);
+
+    assert!(load_public_key("not a key").is_err());
+  }
+
+  #[test]
+  fn test_subscription_hash_from_hex() {
+    let subscription_hash = subscription_hash(&terms(0));
+    assert_eq!(subscription_hash_from_hex(&hex::encode(subscription_hash)).unwrap(), subscription_hash);
+    assert!(subscription_hash_from_hex("07").is_err());
+  }
+}
@@ -1,73 +1,41 @@
-use ed25519_dalek::{
-  Signer,
-};
+use std::process;
 
-use types::{
-  AsymmetricType, 
-  SecretKey, 
-  crypto:: {
-    PublicKey,
-    Signature,
-  },
-};
-
-use hex;
-
-use std::env;
-use std::fs;
-
-mod der;
-
-pub fn sign(secret_key: SecretKey, message_bytes: [u8;32]) -> String {
-  match secret_key {
-    SecretKey::Ed25519(secret_key) => {
-        let pub_key: ed25519_dalek::PublicKey = (&secret_key).into();
-        let pair = ed25519_dalek::Keypair{
-            secret: secret_key,
-            public: pub_key,
-        };
-
-        let signature: ed25519_dalek::Signature = pair.sign(&message_bytes);
-        hex::encode(signature.to_bytes())
-    },
-    _ => panic!("secret key should be a Ed25519"),
-  }
-}
+mod cli;
+use cli::commands;
 
 fn main() {
-  let args: Vec<String> = env::args().collect();
-  assert_eq!(args.len(), 3);
-
-  let message_key_str = &args[2];
-  let mut message_bytes =  [0u8;32];
-  hex::decode_to_slice(message_key_str, &mut message_bytes as &mut [u8]).unwrap();
-
-  let secret_key_file = &args[1];
-  let secret_key_str = fs::read_to_string(secret_key_file).expect("Secret key file could not be read.");
-  let secret_key = der::from_pem(secret_key_str.clone().as_bytes()).unwrap();
-
-  let public_key: PublicKey = (&secret_key).into();
-
-  let signature_hex = sign(secret_key, message_bytes);
-
-  println!("Account Hash {:?}", public_key.to_account_hash().to_formatted_string());
-  println!("Public Key Hex {:?}", public_key.to_hex());
-  println!("Signature {:?}", signature_hex);
-
-  if let PublicKey::Ed25519(ed_public_key) = public_key {
-    let mut signature_bytes = [0u8;64];
-    hex::decode_to_slice(signature_hex, &mut signature_bytes as &mut [u8]).unwrap();
-
-    let signature = Signature::ed25519(signature_bytes).unwrap();
-    if let Signature::Ed25519(ed_signature) = signature 
-    {
-      ed_public_key.verify_strict(&message_bytes, &ed_signature).unwrap();
-    
-      println!("Signature Verification Success");
-    } else {
-      println!("Signature Verification Failed");
+  let matches = cli::app().get_matches();
+
+  let (command, command_matches) = matches.subcommand();
+  let command_matches = command_matches.expect("clap requires a subcommand");
+  let json = command_matches.is_present(cli::JSON);
+
+  let result = match command {
+    cli::HASH => commands::hash(command_matches),
+    cli::SIGN => commands::sign_hash(command_matches),
+    cli::VERIFY => commands::verify_signature(command_matches),
+    cli::ACCOUNT_HASH => commands::account_hash_of(command_matches),
+    cli::ACCOUNT_KEY => commands::account_key(command_matches),
+    cli::KEYGEN => commands::keygen(command_matches),
+    cli::MAKE_DEPLOY => commands::make_deploy(command_matches),
+    cli::EXPLAIN_ERROR => commands::explain_error(command_matches),
+    _ => unreachable!("clap only accepts the subcommands of cli::app"),
+  };
+
+  match result {
+    Ok(output) => {
+      output.print(json);
+      if output.is_failed() {
+        process::exit(1);
+      }
+    }
+    Err(error) => {
+      if json {
+        println!("{}", serde_json::json!({ "error": error.to_string() }));
+      } else {
+        eprintln!("error: {}", error);
+      }
+      process::exit(1);
     }
-  } else {
-    println!("Signature Verification Failed");
   }
 }
@@ -0,0 +1,188 @@
+use std::{
+  io::Write,
+  path::Path,
+  process::{Command, Stdio},
+};
+
+use types::crypto::{PublicKey, SecretKey};
+
+use crate::{errors::Error, load_secret_key, sign, verify, Result};
+
+/// Signs subscription hashes for one subscriber account, wherever its secret key is kept.
+pub trait SubscriptionSigner {
+  /// The public key registered with `create_subscription_hash`, signatures are verified against it.
+  fn public_key(&self) -> &PublicKey;
+
+  /// Sign the subscription hash, returning the hex encoded signature the contract takes.
+  fn sign(&self, subscription_hash: [u8; 32]) -> Result<String>;
+}
+
+/// Signs with a secret key held in memory, e.g. read from a key file.
+pub struct LocalKeySigner {
+  secret_key: SecretKey,
+  public_key: PublicKey,
+}
+
+impl LocalKeySigner {
+  pub fn new(secret_key: SecretKey) -> LocalKeySigner {
+    LocalKeySigner {
+      public_key: PublicKey::from(&secret_key),
+      secret_key,
+    }
+  }
+
+  /// A signer for the secret key file at `path`, see [`load_secret_key`].
+  pub fn from_file<P: AsRef<Path>>(path: P, password: Option<&[u8]>) -> Result<LocalKeySigner> {
+    Ok(LocalKeySigner::new(load_secret_key(path, password)?))
+  }
+}
+
+impl SubscriptionSigner for LocalKeySigner {
+  fn public_key(&self) -> &PublicKey {
+    &self.public_key
+  }
+
+  fn sign(&self, subscription_hash: [u8; 32]) -> Result<String> {
+    sign(&self.secret_key, subscription_hash)
+  }
+}
+
+/// Signs by running a command that holds the key, e.g. a wrapper around a hardware wallet or a
+/// key management service, so the secret key never touches the disk of this machine.
+///
+/// The command is run once per signature. It gets the hex encoded subscription hash and a
+/// newline on stdin, and must print the hex encoded Ed25519 signature on stdout and exit with
+/// status 0. The signature is verified against the configured public key before it is returned.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+  program: String,
+  args: Vec<String>,
+  public_key: PublicKey,
+}
+
+impl ExternalSigner {
+  pub fn new(program: &str, args: Vec<String>, public_key: PublicKey) -> ExternalSigner {
+    ExternalSigner {
+      program: program.to_string(),
+      args,
+      public_key,
+    }
+  }
+
+  fn error(&self, reason: String) -> Error {
+    Error::ExternalSigner {
+      program: self.program.clone(),
+      reason,
+    }
+  }
+}
+
+impl SubscriptionSigner for ExternalSigner {
+  fn public_key(&self) -> &PublicKey {
+    &self.public_key
+  }
+
+  fn sign(&self, subscription_hash: [u8; 32]) -> Result<String> {
+    let mut child = Command::new(&self.program)
+      .args(&self.args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(|error| self.error(error.to_string()))?;
+
+    // Dropping stdin at the end of the statement closes it, so the command sees the end of its input
+    child
+      .stdin
+      .take()
+      .expect("stdin is piped")
+      .write_all(format!("{}\n", hex::encode(subscription_hash)).as_bytes())
+      .map_err(|error| self.error(error.to_string()))?;
+
+    let output = child.wait_with_output().map_err(|error| self.error(error.to_string()))?;
+
+    if !output.status.success() {
+      return Err(self.error(format!(
+        "{}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+      )));
+    }
+
+    let signature = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
+
+    verify(&self.public_key, subscription_hash, &signature)
+      .map_err(|error| self.error(format!("invalid signature: {}", error)))?;
+
+    Ok(signature)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SUBSCRIPTION_HASH: [u8; 32] = [0x42u8; 32];
+  const SIGNATURE: &str = "643b1d83c8ced52329c69e6164d705eb912930d926c8dbb1cb4ac8c2ee298c9f310be954b009a601ead652ad447988fc323a35517185367c5ff99c67cf47bb0e";
+
+  fn secret_key() -> SecretKey {
+    SecretKey::ed25519_from_bytes([7u8; 32]).unwrap()
+  }
+
+  fn mock(script: &str) -> ExternalSigner {
+    let path = format!("{}/src/signing/fixtures/{}", env!("CARGO_MANIFEST_DIR"), script);
+    ExternalSigner::new("sh", vec![path], PublicKey::from(&secret_key()))
+  }
+
+  #[test]
+  fn test_local_key_signer() {
+    let signer = LocalKeySigner::new(secret_key());
+
+    assert_eq!(signer.public_key(), &PublicKey::from(&secret_key()));
+    assert_eq!(signer.sign(SUBSCRIPTION_HASH).unwrap(), SIGNATURE);
+  }
+
+  #[test]
+  fn test_external_signer() {
+    let signer = mock("mock_signer.sh");
+
+    assert_eq!(signer.sign(SUBSCRIPTION_HASH).unwrap(), SIGNATURE);
+  }
+
+  #[test]
+  fn test_external_signer_failure() {
+    let signer = mock("mock_signer.sh");
+
+    match signer.sign([0u8; 32]) {
+      Err(Error::ExternalSigner { reason, .. }) => assert!(reason.contains("refusing to sign")),
+      other => panic!("expected an external signer error, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_external_signer_wrong_signature() {
+    let signer = mock("wrong_signature.sh");
+
+    match signer.sign(SUBSCRIPTION_HASH) {
+      Err(Error::ExternalSigner { reason, .. }) => assert_eq!(reason, "invalid signature: signature verification failed"),
+      other => panic!("expected an external signer error, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_external_signer_missing_program() {
+    let signer = ExternalSigner::new("/nonexistent/signer", vec![], PublicKey::from(&secret_key()));
+
+    assert!(matches!(signer.sign(SUBSCRIPTION_HASH), Err(Error::ExternalSigner { .. })));
+  }
+
+  #[test]
+  fn test_signers_are_interchangeable() {
+    let signers: Vec<Box<dyn SubscriptionSigner>> = vec![Box::new(LocalKeySigner::new(secret_key())), Box::new(mock("mock_signer.sh"))];
+
+    for signer in signers {
+      let signature = signer.sign(SUBSCRIPTION_HASH).unwrap();
+      verify(signer.public_key(), SUBSCRIPTION_HASH, &signature).unwrap();
+    }
+  }
+}
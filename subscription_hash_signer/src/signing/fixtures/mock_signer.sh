@@ -0,0 +1,11 @@
+#!/bin/sh
+# Mock external signer holding the Ed25519 key [7u8; 32]. It only knows the signature of the
+# subscription hash [0x42u8; 32] and refuses to sign anything else.
+read -r subscription_hash
+
+if [ "$subscription_hash" = "4242424242424242424242424242424242424242424242424242424242424242" ]; then
+  echo "643b1d83c8ced52329c69e6164d705eb912930d926c8dbb1cb4ac8c2ee298c9f310be954b009a601ead652ad447988fc323a35517185367c5ff99c67cf47bb0e"
+else
+  echo "refusing to sign $subscription_hash" >&2
+  exit 3
+fi
@@ -0,0 +1,5 @@
+#!/bin/sh
+# Mock external signer holding another Ed25519 key, [8u8; 32]. It answers with its valid signature
+# of the subscription hash [0x42u8; 32], which does not verify against the key [7u8; 32].
+read -r subscription_hash
+echo "4181209336d29c0feff319f4bd2b2a2cb22f5be4871e8cea4845de1af6c3432f244530c568e6013d93f58f227e2e1888152f9badfbcbaea3a8c822aa319b4a00"
@@ -1,17 +1,8 @@
-mod utils;
-
 #[cfg(test)]
 mod tests {
-    use engine_test_support::{
-        Code, Hash, SessionBuilder, TestContext, TestContextBuilder
-    };
-    
-    use types::{CLTyped, ContractHash, ContractPackageHash, Key, PublicKey, RuntimeArgs, SecretKey, U256, U512, account::AccountHash, bytesrepr::{
-            FromBytes, 
-            ToBytes
-        }, 
-        runtime_args
-    };
+    use std::collections::BTreeMap;
+
+    use types::{Key, PublicKey, SecretKey, U256, account::AccountHash, bytesrepr::ToBytes, runtime_args};
 
     use erc20::{
         constants::{
@@ -22,389 +13,18 @@ mod tests {
         Address,
     };
 
-    use blake2::{VarBlake2b};
-    use blake2::digest::{Update, VariableOutput};
-
-    use crate::utils::{sign};
-
-    const TOKEN_NAME: &str = "TEST";
-    const TOKEN_SYMBOL: &str = "TST";
-    const TOKEN_DECIMALS: u8 = 100;
-    const TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000;
-
-    const ARG_NAME: &str = "name";
-    const ARG_SYMBOL: &str = "symbol";
-    const ARG_DECIMALS: &str = "decimals";
-    const ARG_TOTAL_SUPPLY: &str = "total_supply";
-
-    const MINT_ENTRY_POINT_NAME: &str = "mint";
-    const TRANSFER_ENTRY_POINT_NAME: &str = "transfer";
-    const APPROVE_ENTRY_POINT_NAME: &str = "approve";
-
-    pub const HASHES_DICT: &str = "hashes";
-    pub const ALLOWANCES_KEY_NAME: &str = "allowances";
-    pub const BALANCES_KEY_NAME: &str = "balances";
-
-    const CONTRACT_NAME: &str = "casper-contract-eip-1337"; //contract name
-    const ERC20_CONTRACT_NAME: &str = "erc20_token_contract"; //erc20 contract hash
-    const ERC20_CONTRACT_HASH: &str = "erc20_contract_hash"; //erc20 contract hash2
-
-    const TO: &str = "to"; //the publisher
-    const FROM: &str = "from_"; //the subscriber
-    const TOKEN_AMOUNT: &str = "token_amount"; //the token amount paid to the publisher
-    const TOKEN_AMOUNT_VALUE: u64 = 10;
-    const PERIOD_SECONDS: &str = "period_seconds"; //the period in seconds between payments
-    const GRACE_PERIOD_SECONDS: &str = "grace_period_seconds"; //the grace_period in seconds for is_subscription_active
-  
-    const PUBLIC: &str = "public"; //the Publickey
-    const SIGNATURE: &str = "signature"; //the Signature
-    const NEXT_VALID_TIMESTAMP: &str= "next_valid_timestamp"; 
-
-    pub fn generate_eip_1337_secret_key() -> SecretKey {
-        SecretKey::ed25519_from_bytes([1u8; 32]).unwrap()
-    }
-
-    pub fn get_subscription_data(
-        from: AccountHash,
-        to: AccountHash,
-        token_amount:U256,
-        period_seconds:u64,
-    ) -> String {
-        format!("{}_{}_{}_{}",to,from,token_amount,period_seconds)
-    }
-
-    pub fn get_hash_bytes(data:String) -> [u8; 32]
-    {
-        // create a Blake2b object
-        let mut hasher = VarBlake2b::new(32).unwrap();
-        hasher.update(data);
-
-        let mut real_res = [0u8;32];
-        let res = hasher.finalize_variable(|res| {
-            real_res.copy_from_slice(&res);    
-        });
-        
-        real_res
-    }
-
-    pub fn get_hex(bytes:[u8;32]) -> String {
-        hex::encode(bytes)
-    }
-
-    pub struct Subscription {
-        context: TestContext,
-        pub eip_1337_admin: AccountHash,
-        pub eip_1337_admin_pk: PublicKey,
-        pub eip_1337_contract_hash: ContractPackageHash,
-        pub erc_20_admin: AccountHash,
-        pub erc_20_admin_pk: PublicKey,
-        pub erc_20_contract_hash: ContractHash,
-        pub user_to: AccountHash,
-        pub user_to_pk: PublicKey,
-        pub user_from: AccountHash,
-        pub user_from_pk: PublicKey,
-    }
-
-    impl Subscription {
-
-        pub fn deployment(period_seconds: u64) -> Subscription {
-
-            // Create EIP 1337 contract admin.
-            let admin_secret = generate_eip_1337_secret_key();
-            let admin_key: PublicKey = (&admin_secret).into();
-            let admin_addr = AccountHash::from(&admin_key);
-
-            // Create ERC 20 contract admin.
-            let erc_20_admin_secret = SecretKey::ed25519_from_bytes([2u8; 32]).unwrap();
-            let erc_20_admin_key: PublicKey = (&erc_20_admin_secret).into();
-            let erc_20_admin_addr = AccountHash::from(&erc_20_admin_key);
-
-            // Create user.
-            let user_secret = SecretKey::ed25519_from_bytes([4u8; 32]).unwrap();
-            let user_key: PublicKey = (&user_secret).into();
-            let user_addr = AccountHash::from(&user_key);
-
-            // Create user.
-            let user_secret_2 = SecretKey::ed25519_from_bytes([5u8; 32]).unwrap();
-            let user_key_2: PublicKey = (&user_secret_2).into();
-            let user_addr_2 = AccountHash::from(&user_key_2);
-
-            // Create context.
-            let mut context = TestContextBuilder::new()
-                .with_public_key(admin_key.clone(), U512::from(500_000_000_000_000_000u64))
-                .with_public_key(
-                    erc_20_admin_key.clone(),
-                    U512::from(500_000_000_000_000_000u64),
-                )
-                .with_public_key(user_key.clone(), U512::from(500_000_000_000_000_000u64))
-                .with_public_key(user_key_2.clone(), U512::from(500_000_000_000_000_000u64))
-                .build();
-
-            // Deploy the EIP 1337 contract onto the context.
-            let erc_20_session_code = Code::from("erc-20.wasm");
-
-            let erc_20_session_args = runtime_args! {
-                ARG_NAME => TOKEN_NAME,
-                ARG_SYMBOL => TOKEN_SYMBOL,
-                ARG_DECIMALS => TOKEN_DECIMALS,
-                ARG_TOTAL_SUPPLY => U256::from(TOKEN_TOTAL_SUPPLY),
-            };
-
-            let erc_20_session = SessionBuilder::new(erc_20_session_code, erc_20_session_args)
-                .with_address(erc_20_admin_addr)
-                .with_authorization_keys(&[erc_20_admin_addr])
-                .build();
-
-            context.run(erc_20_session);
-
-            let erc_20_contract_hash: ContractHash = context
-                .get_account(erc_20_admin_addr)
-                .unwrap()
-                .named_keys()
-                .get(ERC20_CONTRACT_NAME)
-                .unwrap()
-                .normalize()
-                .into_hash()
-                .unwrap()
-                .into();
-
-            // Deploy the EIP 1337 contract onto the context.
-            let session_code = Code::from("casper-contract-eip-1337.wasm");
-            
-            let session_args = runtime_args! {
-                TO => user_addr_2,
-                TOKEN_AMOUNT => U256::from(TOKEN_AMOUNT_VALUE),
-                PERIOD_SECONDS => period_seconds,
-                ERC20_CONTRACT_HASH => Key::Hash(erc_20_contract_hash.value()),
-            };
-
-            let session = SessionBuilder::new(session_code, session_args)
-                .with_address(admin_addr)
-                .with_authorization_keys(&[admin_addr])
-                .build();
-
-            context.run(session);
-
-            let contract_package_hash: Hash = context
-                .query(
-                    admin_addr,
-                    &["casper-contract-eip-1337-latest-version-contract-hash".to_string()],
-                )
-                .unwrap()
-                .into_t()
-                .unwrap();
-
-            Subscription {
-                context,
-                eip_1337_admin: admin_addr,
-                eip_1337_admin_pk: admin_key.clone(),
-                eip_1337_contract_hash: ContractPackageHash::from(contract_package_hash),
-                erc_20_admin: erc_20_admin_addr,
-                erc_20_admin_pk: erc_20_admin_key,
-                erc_20_contract_hash,
-                user_from: user_addr,
-                user_from_pk: user_key,
-                user_to: user_addr_2,
-                user_to_pk: user_key_2,
-            }
-        }
-
-        fn call(&mut self, caller: &AccountHash, function: &str, args: RuntimeArgs) {
-            let code = Code::Hash(self.eip_1337_contract_hash.value(), function.to_string());
-            let session = SessionBuilder::new(code, args)
-                .with_address(*caller)
-                .with_authorization_keys(&[*caller])
-                .build();
-            self.context.run(session);
-        }
-
-        fn call_erc_20(&mut self, caller: &AccountHash, function: &str, args: RuntimeArgs) {
-            let code = Code::Hash(self.erc_20_contract_hash.value(), function.to_string());
-            let session = SessionBuilder::new(code, args)
-                .with_address(*caller)
-                .with_authorization_keys(&[*caller])
-                .build();
-            self.context.run(session);
-        }
-
-        fn query_contract<T: CLTyped + FromBytes>(&self, name: &str) -> Option<T> {
-            match self.context.query(
-                self.eip_1337_admin,
-                &["casper-contract-eip-1337-latest-version-contract".to_string(), name.to_string()],
-            ) {
-                Err(_) => None,
-                Ok(maybe_value) => {
-                    let value = maybe_value
-                        .into_t()
-                        .unwrap();
-                    Some(value)
-                }
-            }
-        }
-
-        fn query_contract_erc20<T: CLTyped + FromBytes>(&self, name: &str) -> Option<T> {
-            match self.context.query(
-                self.erc_20_admin,
-                &[ERC20_CONTRACT_NAME.to_string(), name.to_string()],
-            ) {
-                Err(_) => None,
-                Ok(maybe_value) => {
-                    let value = maybe_value
-                        .into_t()
-                        .unwrap();
-                    Some(value)
-                }
-            }
-        }
-
-        fn query_dictionary_value<T: CLTyped + FromBytes>(
-            &self,
-            dict_name: &str,
-            key: &str,
-        ) -> Option<T> {
-            match self.context.query_dictionary_item(
-                Key::from(self.eip_1337_contract_hash),
-                Some(dict_name.to_string()),
-                key.to_string(),
-            ) {
-                Err(_) => None,
-                Ok(maybe_value) => {
-                    let value: T = maybe_value
-                        .into_t()
-                        .unwrap();
-                    Option::Some(value)
-                }
-            }
-        }
-
-        fn query_dictionary_value_erc20<T: CLTyped + FromBytes>(
-            &self,
-            dict_name: &str,
-            key: &str,
-        ) -> Option<T> {
-            match self.context.query_dictionary_item(
-                Key::from(self.erc_20_contract_hash),
-                Some(dict_name.to_string()),
-                key.to_string(),
-            ) {
-                Err(_) => None,
-                Ok(maybe_value) => {
-                    let value: T = maybe_value
-                        .into_t()
-                        .unwrap();
-                    Option::Some(value)
-                }
-            }
-        }
-
-        pub fn to(&self) -> AccountHash {
-            self.query_contract(TO).unwrap()
-        }
-
-        pub fn token_amount(&self) -> U256 {
-            self.query_contract(TOKEN_AMOUNT).unwrap()
-        }
-
-        pub fn period_seconds(&self) -> u64 {
-            self.query_contract(PERIOD_SECONDS).unwrap()
-        }
-
-        pub fn from(&self) -> AccountHash {
-            self.query_contract(FROM).unwrap()
-        }
-
-        pub fn next_valid_timestamp(&self) -> u64 {
-            self.query_contract(NEXT_VALID_TIMESTAMP).unwrap()
-        }
-
-        pub fn get_subscription_hash(
-            &mut self,        
-            caller: AccountHash,   
-            public: PublicKey,
-            from: AccountHash,
-        ) -> String {
-            
-            self.call(
-                &caller,
-                "create_subscription_hash",
-                runtime_args! {
-                    "public" => public,
-                    "from" => from,
-                },
-            );
-
-            let subscription_hash: String = self.query_dictionary_value(
-                HASHES_DICT, 
-                &from.to_string(),
-            ).unwrap();
-            
-            subscription_hash
-        }
-        
-        pub fn execute_subscription(
-            &mut self,
-            caller: AccountHash,
-            signature: String,
-            from: AccountHash,
-        ) {
-            self.call(
-                &caller,
-                "execute_subscription",
-                runtime_args! {
-                    "signature" => signature,
-                    "from" => from
-                },
-            );
-        }
-
-        pub fn cancel_subscription(
-            &mut self,
-            caller: AccountHash,
-            signature: String,
-            from: AccountHash,
-        ) {
-            self.call(
-                &caller,
-                "cancel_subscription",
-                runtime_args! {
-                    "signature" => signature,
-                    "from" => from
-                },
-            );
-        }
+    use eip1337_test_support::{
+        CallError, ContractError, Coupon, DiscountType, IntervalType, PaymentState, Subscription,
+        SubscriptionStatus, SubscriptionTerms, days_from_civil, discounted_price, is_valid_discount,
+        metered_price, prorated_price, seats_price, MILLIS_PER_DAY,
+        generate_eip_1337_secret_key, get_hash_bytes, get_hex, get_subscription_data, is_valid_payout,
+        split_payment, ACCOUNT_SUBSCRIPTIONS_DICT, APPROVE_ENTRY_POINT_NAME, BALANCES_KEY_NAME, HASHES_DICT,
+        TOKEN_AMOUNT_VALUE, TOTAL_BASIS_POINTS, TRANSFER_ENTRY_POINT_NAME,
+    };
 
-        pub fn is_subscription_ready(
-            &mut self,
-            caller: AccountHash,
-            signature: String,
-            from: AccountHash,
-        ) {
-            self.call(
-                &caller,
-                "is_subscription_ready",
-                runtime_args! {
-                    "signature" => signature,
-                    "from" => from
-                },
-            );
-        }
+    use eip1337_access::AccessError;
 
-        pub fn is_subscription_active(
-            &mut self,
-            caller: AccountHash,
-            subscription_hash: String,
-            grace_period_seconds: u64,
-        ) {
-            self.call(
-                &caller,
-                "is_subscription_active",
-                runtime_args! {
-                    "subscription_hash_" => subscription_hash,
-                    "grace_period_seconds" => grace_period_seconds
-                },
-            );
-        }
-    }
+    use subscription_hash_signer::sign;
 
     // Deploy Test
     #[test]
@@ -435,7 +55,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Check that the owner has sent 1000 tokens
         let admin_bytes = Key::Account(erc_20_admin).to_bytes().unwrap();
@@ -463,7 +83,7 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
     /*     // Check the approval of the spender contract
         let mut preimage = Vec::new();
@@ -480,17 +100,17 @@ mod tests {
         assert_eq!(allowance, U256::from(1000)); */
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
         hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
 
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0);
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
@@ -500,9 +120,9 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // TODO: This won't work well unless we can get the contract hash into the contract scope
 
@@ -518,8 +138,8 @@ mod tests {
         s.execute_subscription(
             eip_1337_admin,
             signature, 
-            user_from,
-        );
+            subscription_id,
+        ).unwrap();
 
         // Check that the mint is okay
         let admin_balance2: U256 = s.query_dictionary_value_erc20(
@@ -546,7 +166,6 @@ mod tests {
 
     // Fail without approval
     #[test]
-    #[should_panic]
     fn test_execute_subscription_no_approval() {
         let mut s = Subscription::deployment(0);
         let user_from = s.user_from;
@@ -564,20 +183,20 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
         hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
 
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0);
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
@@ -587,21 +206,23 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
-        s.execute_subscription(
-            eip_1337_admin,
-            signature, 
-            user_from,
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InsufficientAllowance)),
         );
     }
 
     // Insufficient allowance
     #[test]
-    #[should_panic]
     fn test_execute_subscription_insufficient_allowance() {
         let mut s = Subscription::deployment(0);
         let user_from = s.user_from;
@@ -619,7 +240,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Give the spender contract permission to spend 1000 tokens
         s.call_erc_20(
@@ -629,7 +250,7 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1),
             },
-        );
+        ).unwrap();
 
     /*     // Check the approval of the spender contract
         let mut preimage = Vec::new();
@@ -646,17 +267,17 @@ mod tests {
         assert_eq!(allowance, U256::from(1000)); */
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
         hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
 
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0);
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
@@ -666,22 +287,24 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
-        s.execute_subscription(
-            eip_1337_admin,
-            signature, 
-            user_from,
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InsufficientAllowance)),
         );
 
     }
 
     // Bad Hash
     #[test]
-    #[should_panic]
     fn test_execute_subscription_bad_hash() {
         let mut s = Subscription::deployment(0);
         let user_from = s.user_from;
@@ -699,7 +322,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Give the spender contract permission to spend 1000 tokens
         s.call_erc_20(
@@ -709,31 +332,34 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
-        // Generate a subscription hash in contract
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE*10), 0);
+        // Generate a subscription hash in contract, no subscription is registered under the id
+        let subscription_id = 0;
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE*10), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             sub_bytes,
-        );
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
-        s.execute_subscription(
-            eip_1337_admin,
-            signature, 
-            user_from,
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::MissingPublicKey)),
         );
 
     }
 
     // Not yet ready
     #[test]
-    #[should_panic]
     fn test_execute_subscription_not_ready() {
         let mut s = Subscription::deployment(100000);
         let user_from = s.user_from;
@@ -751,7 +377,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Give the spender contract permission to spend 1000 tokens
         s.call_erc_20(
@@ -761,20 +387,20 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
         hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
 
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0);
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
@@ -784,15 +410,18 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
-        s.execute_subscription(
-            eip_1337_admin,
-            signature, 
-            user_from,
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InvalidBlockTime)),
         );
 
     }
@@ -816,7 +445,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Give the spender contract permission to spend 1000 tokens
         s.call_erc_20(
@@ -826,20 +455,20 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
         hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
 
-        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0);
+        let sub_data = get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, s.erc_20_contract_hash);
         let sub_bytes = get_hash_bytes(sub_data);
         let sub_hex = get_hex(sub_bytes);
 
@@ -849,9 +478,9 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // TODO: This won't work well unless we can get the contract hash into the contract scope
 
@@ -867,15 +496,15 @@ mod tests {
         s.execute_subscription(
             eip_1337_admin,
             signature.clone(), 
-            user_from,
-        );
+            subscription_id,
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
         s.execute_subscription(
             eip_1337_admin,
             signature, 
-            user_from,
-        );
+            subscription_id,
+        ).unwrap();
 
         // Check that the mint is okay
         let admin_bytes = Key::Account(erc_20_admin).to_bytes().unwrap();
@@ -908,7 +537,6 @@ mod tests {
 
     // Cancellation Test
     #[test]
-    #[should_panic]
     fn test_execute_subscription_cancellation() {
         let mut s = Subscription::deployment(0);
         let user_from = s.user_from;
@@ -926,7 +554,7 @@ mod tests {
                 RECIPIENT_RUNTIME_ARG_NAME => Address::Account(user_from.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Give the spender contract permission to spend 1000 tokens
         s.call_erc_20(
@@ -936,14 +564,14 @@ mod tests {
                 SPENDER_RUNTIME_ARG_NAME => Address::Contract(eip_1337_contract_hash.clone()),
                 AMOUNT_RUNTIME_ARG_NAME => U256::from(1000),
             },
-        );
+        ).unwrap();
 
         // Generate a subscription hash in contract
-        let subscription_hash = s.get_subscription_hash(
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(
             eip_1337_admin,
             eip_1337_admin_pk,
             user_from,
-        );
+        ).unwrap();
 
         // Generate a subscription hash in test
         let mut subscription_bytes = [0u8;32];
@@ -951,35 +579,1527 @@ mod tests {
 
         // Sign the subscription hash 
         let signature = sign(
-            generate_eip_1337_secret_key(),
+            &generate_eip_1337_secret_key(),
             subscription_bytes,
-        );
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
         s.execute_subscription(
             eip_1337_admin,
             signature.clone(), 
-            user_from,
-        );
+            subscription_id,
+        ).unwrap();
 
         // Cancel subscription
         s.cancel_subscription(
             eip_1337_admin,
             signature.clone(), 
-            user_from,
-        );
+            subscription_id,
+        ).unwrap();
 
         // Use the signed subscription hash to execute a payment of 1 token
-        s.execute_subscription(
-            eip_1337_admin,
-            signature, 
-            user_from,
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InvalidBlockTime)),
+        );
+
+    }
+
+    // Malformed signature hex
+    #[test]
+    fn test_execute_subscription_malformed_signature_hex() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        let (subscription_id, _subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        // 128 characters, but not hex
+        let signature = "zz".repeat(64);
+
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::MalformedSignatureHex)),
+        );
+    }
+
+    // Signature of the wrong length
+    #[test]
+    fn test_execute_subscription_wrong_signature_length() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+
+        // Drop the last byte of an otherwise valid signature
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature[..126].to_string(), 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InvalidSignatureLength)),
+        );
+
+        // or its last hex digit, leaving an odd number of them
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature[..127].to_string(), 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InvalidSignatureLength)),
+        );
+    }
+
+    // Registered public key is not Ed25519
+    #[test]
+    fn test_execute_subscription_wrong_key_type() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        let secp256k1_secret = SecretKey::secp256k1_from_bytes([3u8; 32]).unwrap();
+        let secp256k1_key: PublicKey = (&secp256k1_secret).into();
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, secp256k1_key, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::UnsupportedKeyType)),
+        );
+    }
+
+    // Well formed signature from the wrong signer
+    #[test]
+    fn test_execute_subscription_bad_signature() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+
+        let signature = sign(
+            &SecretKey::ed25519_from_bytes([9u8; 32]).unwrap(),
+            subscription_bytes,
+        ).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::SignatureVerificationFailed)),
+        );
+    }
+
+    // Approved, but not enough tokens to pay
+    #[test]
+    fn test_execute_subscription_insufficient_balance() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(TOKEN_AMOUNT_VALUE - 1, 1000).unwrap();
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(
+                eip_1337_admin,
+                signature, 
+                subscription_id,
+            ),
+            Err(CallError::Contract(ContractError::InsufficientBalance)),
+        );
+    }
+
+    // Subscription hash that was never registered
+    #[test]
+    fn test_is_subscription_active_unknown_hash() {
+        let mut s = Subscription::deployment(0);
+        let eip_1337_admin = s.eip_1337_admin;
+
+        assert_eq!(
+            s.is_subscription_active(
+                eip_1337_admin,
+                get_hex([7u8;32]),
+                0,
+            ),
+            Err(CallError::Contract(ContractError::UnknownSubscription)),
+        );
+    }
+
+    // Query entry points and the documented dictionary keys
+    #[test]
+    fn test_query_subscription_state() {
+        let mut s = Subscription::deployment(1000);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        // hashes[<subscription id>], next_valid_timestamps[<subscription id>] and account_subscriptions[<account hash hex>]
+        let stored_hash: String = s.query_dictionary_value(HASHES_DICT, &subscription_id.to_string()).unwrap();
+        assert_eq!(stored_hash, subscription_hash);
+        let next_valid_timestamp = s.next_valid_timestamp(subscription_id).unwrap();
+        assert!(next_valid_timestamp >= 1000 * 1000);
+        let subscription_ids: Vec<u64> = s.query_dictionary_value(ACCOUNT_SUBSCRIPTIONS_DICT, &hex::encode(user_from.value())).unwrap();
+        assert_eq!(subscription_ids, vec![subscription_id]);
+
+        // The views return the same state
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        let mut expected: BTreeMap<String, String> = BTreeMap::new();
+        expected.insert("from".to_string(), user_from.to_formatted_string());
+        expected.insert("beneficiary".to_string(), user_from.to_formatted_string());
+        expected.insert("subscription_hash".to_string(), subscription_hash.clone());
+        expected.insert("public".to_string(), hex::encode(eip_1337_admin_pk.to_bytes().unwrap()));
+        expected.insert("next_valid_timestamp".to_string(), next_valid_timestamp.to_string());
+        expected.insert("status".to_string(), (SubscriptionStatus::Active as u8).to_string());
+        expected.insert("erc20_contract_hash".to_string(), s.erc_20_contract_hash.to_formatted_string());
+        expected.insert("token_amount".to_string(), TOKEN_AMOUNT_VALUE.to_string());
+        expected.insert("payment_amount".to_string(), TOKEN_AMOUNT_VALUE.to_string());
+        expected.insert("amount_paid".to_string(), "0".to_string());
+        assert_eq!(subscription, expected);
+
+        assert_eq!(s.get_next_payment_time(eip_1337_admin, subscription_id), Ok(next_valid_timestamp));
+        assert_eq!(s.get_subscription_status(eip_1337_admin, subscription_id), Ok(SubscriptionStatus::Active as u8));
+        s.get_subscription_hash(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, user_from), Ok(vec![subscription_id]));
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, s.user_to), Ok(vec![]));
+        assert_eq!(s.get_subscription_count(eip_1337_admin), Ok(1));
+        assert_eq!(s.get_subscriber_count(eip_1337_admin), Ok(1));
+
+        let plan = s.get_plan(eip_1337_admin).unwrap();
+        let mut expected: BTreeMap<String, String> = BTreeMap::new();
+        expected.insert("to".to_string(), s.user_to.to_formatted_string());
+        expected.insert("token_amount".to_string(), TOKEN_AMOUNT_VALUE.to_string());
+        expected.insert("period_seconds".to_string(), "1000".to_string());
+        expected.insert("erc20_contract_hash".to_string(), s.erc_20_contract_hash.to_formatted_string());
+        expected.insert("dispute_window_seconds".to_string(), "0".to_string());
+        expected.insert("usage_oracle".to_string(), s.user_to.to_formatted_string());
+        expected.insert("interval_type".to_string(), (IntervalType::Seconds as u8).to_string());
+        expected.insert("billing_day".to_string(), "0".to_string());
+        expected.insert("seat_based".to_string(), "false".to_string());
+        assert_eq!(plan, expected);
+
+        // Due once the next valid timestamp is reached
+        s.set_block_time(next_valid_timestamp);
+        assert_eq!(s.get_subscription_status(eip_1337_admin, subscription_id), Ok(SubscriptionStatus::Due as u8));
+        assert_eq!(s.get_subscription_status(eip_1337_admin, subscription_id + 1), Ok(SubscriptionStatus::NotFound as u8));
+
+        // Paid up until the next valid timestamp
+        s.is_subscription_active(eip_1337_admin, subscription_hash, 0).unwrap();
+    }
+
+    // Next payment time of a subscription id that was never registered
+    #[test]
+    fn test_get_next_payment_time_unknown_subscription() {
+        let mut s = Subscription::deployment(1000);
+        let eip_1337_admin = s.eip_1337_admin;
+
+        assert_eq!(
+            s.get_next_payment_time(eip_1337_admin, 0),
+            Err(CallError::Contract(ContractError::UnknownSubscription)),
+        );
+    }
+
+    // Cancelled subscriptions are not active
+    #[test]
+    fn test_is_subscription_active_cancelled() {
+        let mut s = Subscription::deployment(1000);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
+
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.cancel_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(
+            s.is_subscription_active(eip_1337_admin, subscription_hash, 0),
+            Err(CallError::Contract(ContractError::SubscriptionNotActive)),
         );
+    }
+
+    // Subscriber index and pagination
+    #[test]
+    fn test_list_subscribers() {
+        let mut s = Subscription::deployment(1000);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        assert_eq!(s.subscriber_count(), 0);
 
+        s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_to).unwrap();
+
+        // A second subscription does not index the account twice
+        s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        assert_eq!(s.subscriber_count(), 2);
+        assert_eq!(s.subscription_count(), 3);
+        assert_eq!(s.subscriber(0), Some(user_from));
+        assert_eq!(s.subscriber(1), Some(user_to));
+        assert_eq!(s.subscriber(2), None);
+
+        s.list_subscribers(eip_1337_admin, 0, 10, None).unwrap();
+        s.list_subscribers(eip_1337_admin, 1, 1, Some(1)).unwrap();
+        s.list_subscribers(eip_1337_admin, 5, 10, Some(2)).unwrap();
+        s.list_subscriptions(eip_1337_admin, 0, 10, None).unwrap();
+        s.list_subscriptions(eip_1337_admin, 2, 1, Some(1)).unwrap();
+    }
+
+    // Concurrent subscriptions of one account are paid and cancelled independently
+    #[test]
+    fn test_multiple_subscriptions_per_account() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        let (first_id, first_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        let (second_id, second_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        // Every subscription signs a different hash
+        assert_ne!(first_id, second_id);
+        assert_ne!(first_hash, second_hash);
+        assert_eq!(second_hash, get_hex(get_hash_bytes(get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, second_id, s.erc_20_contract_hash))));
+        assert_eq!(s.account_subscriptions(user_from), vec![first_id, second_id]);
+        assert_eq!(s.subscriber_count(), 1);
+
+        let mut first_bytes = [0u8;32];
+        hex::decode_to_slice(first_hash.clone(), &mut first_bytes as &mut [u8]).unwrap();
+        let first_signature = sign(&generate_eip_1337_secret_key(), first_bytes).unwrap();
+
+        let mut second_bytes = [0u8;32];
+        hex::decode_to_slice(second_hash.clone(), &mut second_bytes as &mut [u8]).unwrap();
+        let second_signature = sign(&generate_eip_1337_secret_key(), second_bytes).unwrap();
+
+        s.execute_subscription(eip_1337_admin, first_signature.clone(), first_id).unwrap();
+        s.execute_subscription(eip_1337_admin, second_signature, second_id).unwrap();
+
+        let bytes_from = Key::Account(user_from).to_bytes().unwrap();
+        let balance_from: U256 = s.query_dictionary_value_erc20(
+            &BALANCES_KEY_NAME.to_string(), 
+            &base64::encode(&bytes_from),
+        ).unwrap();
+        assert_eq!(balance_from, U256::from(1000 - TOKEN_AMOUNT_VALUE * 2));
+
+        // Cancelling one subscription leaves the other one untouched
+        s.cancel_subscription(eip_1337_admin, first_signature, first_id).unwrap();
+        assert_eq!(s.next_valid_timestamp(first_id), Some(99999999999 * 1000));
+        s.is_subscription_active(eip_1337_admin, second_hash, 1000).unwrap();
+    }
+
+    // The hash computed off-chain from the shared terms, as the signer does, matches the one
+    // `_get_subscription_hash` stores for every plan and subscription
+    #[test]
+    fn test_offline_subscription_hash_parity() {
+        for period_seconds in vec![0u64, 1000, 2592000] {
+            let mut s = Subscription::deployment(period_seconds);
+            let user_from = s.user_from;
+            let eip_1337_admin = s.eip_1337_admin;
+            let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+            for from in vec![user_from, eip_1337_admin] {
+                let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), from).unwrap();
+
+                let terms = SubscriptionTerms {
+                    to: s.to(),
+                    from,
+                    token_amount: s.token_amount(),
+                    period_seconds,
+                    subscription_id,
+                    erc20_contract_hash: s.erc_20_contract_hash,
+                    max_amount_per_period: None,
+                    lifetime_cap: None,
+                    beneficiary: None,
+                    metered: false,
+                };
+
+                assert_eq!(subscription_hash, get_hex(get_hash_bytes(terms.data())));
+                assert_eq!(s.subscription_hash(subscription_id), Some(subscription_hash));
+            }
+        }
+    }
+
+    // The signature of one subscription does not authorize another
+    #[test]
+    fn test_execute_subscription_signature_of_other_subscription() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        let (_first_id, first_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        let (second_id, _second_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut first_bytes = [0u8;32];
+        hex::decode_to_slice(first_hash, &mut first_bytes as &mut [u8]).unwrap();
+        let first_signature = sign(&generate_eip_1337_secret_key(), first_bytes).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(eip_1337_admin, first_signature, second_id),
+            Err(CallError::Contract(ContractError::SignatureVerificationFailed)),
+        );
+    }
+
+    // The publisher accepts another token at its own price, once
+    #[test]
+    fn test_add_token() {
+        let mut s = Subscription::deployment(0);
+        let user_to = s.user_to;
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+
+        let other_token = s.install_token(eip_1337_admin);
+
+        // Only the publisher can change the plan
+        assert_eq!(
+            s.add_token(user_from, other_token, U256::from(25)),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+
+        s.add_token(user_to, other_token, U256::from(25)).unwrap();
+
+        let token_prices = s.token_prices();
+        assert_eq!(token_prices.len(), 2);
+        assert_eq!(token_prices[&erc_20_contract_hash], U256::from(TOKEN_AMOUNT_VALUE));
+        assert_eq!(token_prices[&other_token], U256::from(25));
+        s.get_token_prices(eip_1337_admin).unwrap();
+
+        // Signed hashes commit to the price, so it cannot change
+        assert_eq!(
+            s.add_token(user_to, other_token, U256::from(30)),
+            Err(CallError::Contract(ContractError::TokenExists)),
+        );
+        assert_eq!(
+            s.add_token(user_to, erc_20_contract_hash, U256::from(30)),
+            Err(CallError::Contract(ContractError::TokenExists)),
+        );
+    }
+
+    // A subscription is paid in the token chosen at signup, at that token's price
+    #[test]
+    fn test_execute_subscription_in_chosen_token() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+
+        let other_token = s.install_token(eip_1337_admin);
+        s.add_token(user_to, other_token, U256::from(25)).unwrap();
+        s.fund_and_approve_token(other_token, eip_1337_admin, 1000, 1000).unwrap();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash_with_token(eip_1337_admin, eip_1337_admin_pk, user_from, other_token).unwrap();
+
+        // The token and its price are bound into the signed terms
+        assert_eq!(subscription_hash, get_hex(get_hash_bytes(get_subscription_data(user_from, user_to, U256::from(25), 0, subscription_id, other_token))));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+
+        assert_eq!(s.token_balance_of(other_token, user_from), U256::from(1000 - 25));
+        assert_eq!(s.token_balance_of(other_token, user_to), U256::from(25));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::zero());
+    }
+
+    // Subscribers can only choose a token the plan accepts
+    #[test]
+    fn test_create_subscription_hash_unsupported_token() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        let other_token = s.install_token(eip_1337_admin);
+
+        assert_eq!(
+            s.create_subscription_hash_with_token(eip_1337_admin, eip_1337_admin_pk, user_from, other_token),
+            Err(CallError::Contract(ContractError::UnsupportedToken)),
+        );
+        assert_eq!(s.subscription_count(), 0);
+    }
+
+    // The publisher sets the payees of the plan, within a whole payment
+    #[test]
+    fn test_set_payee() {
+        let mut s = Subscription::deployment(0);
+        let user_to = s.user_to;
+        let user_from = s.user_from;
+        let erc_20_admin = s.erc_20_admin;
+        let eip_1337_admin = s.eip_1337_admin;
+
+        assert!(s.payees().is_empty());
+
+        // Only the publisher can change the plan
+        assert_eq!(
+            s.set_payee(user_from, erc_20_admin, 2000),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+
+        s.set_payee(user_to, erc_20_admin, 2000).unwrap();
+        s.set_payee(user_to, eip_1337_admin, 1500).unwrap();
+        assert_eq!(s.payees(), vec![(erc_20_admin, 2000), (eip_1337_admin, 1500)].into_iter().collect());
+        s.get_payees(user_from).unwrap();
+
+        // The publisher gets the rest, it is not a payee
+        assert_eq!(
+            s.set_payee(user_to, user_to, 1000),
+            Err(CallError::Contract(ContractError::InvalidPayout)),
+        );
+
+        // Payees cannot get more than a whole payment
+        assert_eq!(
+            s.set_payee(user_to, user_from, TOTAL_BASIS_POINTS - 3500 + 1),
+            Err(CallError::Contract(ContractError::InvalidPayout)),
+        );
+        s.set_payee(user_to, user_from, TOTAL_BASIS_POINTS - 3500).unwrap();
+
+        // Setting a payee again replaces its share, 0 removes it
+        s.set_payee(user_to, user_from, 500).unwrap();
+        s.set_payee(user_to, eip_1337_admin, 0).unwrap();
+        assert_eq!(s.payees(), vec![(erc_20_admin, 2000), (user_from, 500)].into_iter().collect());
+    }
+
+    // Each payment is split between the payees, rounded down, and the publisher, who gets the rest
+    #[test]
+    fn test_execute_subscription_split() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_admin = s.erc_20_admin;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        s.set_payee(user_to, erc_20_admin, 2000).unwrap();
+        s.set_payee(user_to, eip_1337_admin, 1500).unwrap();
+
+        let erc_20_admin_balance = s.token_balance_of(erc_20_contract_hash, erc_20_admin);
+        let eip_1337_admin_balance = s.token_balance_of(erc_20_contract_hash, eip_1337_admin);
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+
+        // 20% and 15% of 10 tokens, rounded down, the publisher gets the rest
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, erc_20_admin), erc_20_admin_balance + 2);
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, eip_1337_admin), eip_1337_admin_balance + 1);
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(7));
+    }
+
+    // The split of a payment always adds up to the token amount, whatever the payees
+    #[test]
+    fn test_split_payment_sums_to_token_amount() {
+        let to = AccountHash::new([1u8; 32]);
+        let payee = |n: u8| AccountHash::new([n; 32]);
+
+        let tables: Vec<BTreeMap<AccountHash, u32>> = vec![
+            BTreeMap::new(),
+            vec![(payee(2), 2000), (payee(3), 1500)].into_iter().collect(),
+            vec![(payee(2), 3333), (payee(3), 3333), (payee(4), 3333)].into_iter().collect(),
+            vec![(payee(2), 1), (payee(3), 9999)].into_iter().collect(),
+            vec![(payee(2), TOTAL_BASIS_POINTS)].into_iter().collect(),
+        ];
+        let amounts = (0u64..=1000).map(U256::from)
+            .chain(vec![U256::from(u64::MAX), U256::MAX - 1, U256::MAX]);
+
+        for token_amount in amounts {
+            for payees in &tables {
+                assert!(is_valid_payout(payees));
+
+                let payments = split_payment(token_amount, to, payees);
+                assert_eq!(payments.len(), payees.len() + 1);
+                assert_eq!(payments[0].0, to);
+
+                let total = payments.iter().fold(U256::zero(), |total, (_, amount)| total + amount);
+                assert_eq!(total, token_amount);
+
+                // Payees are rounded down, the publisher gets the remainders
+                for (recipient, amount) in &payments[1..] {
+                    if let Some(product) = token_amount.checked_mul(U256::from(payees[recipient])) {
+                        assert_eq!(*amount, product / U256::from(TOTAL_BASIS_POINTS));
+                    }
+                }
+            }
+        }
+
+        assert!(!is_valid_payout(&vec![(payee(2), 5000), (payee(3), 5001)].into_iter().collect()));
+        assert!(!is_valid_payout(&vec![(payee(2), u32::MAX), (payee(3), u32::MAX)].into_iter().collect()));
+    }
+
+    // In escrow mode payments are held by the contract until the dispute window ends, then the
+    // publisher and payees withdraw their shares
+    #[test]
+    fn test_escrow_withdraw() {
+        let mut s = Subscription::deployment_with_escrow(0, 60);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_admin = s.erc_20_admin;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        s.set_payee(user_to, erc_20_admin, 2000).unwrap();
+        let erc_20_admin_balance = s.token_balance_of(erc_20_contract_hash, erc_20_admin);
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.set_block_time(1_000);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+
+        // The whole payment is held, its split is pending
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::zero());
+        assert_eq!(s.package_token_balance(erc_20_contract_hash), U256::from(TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.escrow_held()[&erc_20_contract_hash], U256::from(TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.escrow_payment_count(), 1);
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Held as u8));
+        assert_eq!(s.escrow_pending(user_to)[&erc_20_contract_hash], U256::from(8));
+        assert_eq!(s.escrow_pending(erc_20_admin)[&erc_20_contract_hash], U256::from(2));
+        s.get_escrow_payment(user_from, 0).unwrap();
+
+        // Nothing can be withdrawn within the dispute window
+        assert_eq!(
+            s.withdraw(user_to, erc_20_contract_hash),
+            Err(CallError::Contract(ContractError::NothingToWithdraw)),
+        );
+
+        // The window ends 60 seconds after the payment, the balance view does not release it
+        s.set_block_time(61_000);
+        s.get_escrow_balance(user_from, user_to).unwrap();
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Held as u8));
+        assert_eq!(s.escrow_pending(user_to)[&erc_20_contract_hash], U256::from(8));
+        assert!(s.escrow_available(user_to).is_empty());
+
+        // withdrawing does
+        s.withdraw(user_to, erc_20_contract_hash).unwrap();
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Released as u8));
+        assert!(s.escrow_pending(user_to).is_empty());
+        assert_eq!(s.escrow_available(erc_20_admin)[&erc_20_contract_hash], U256::from(2));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(8));
+        assert_eq!(s.escrow_held()[&erc_20_contract_hash], U256::from(2));
+        assert_eq!(
+            s.withdraw(user_to, erc_20_contract_hash),
+            Err(CallError::Contract(ContractError::NothingToWithdraw)),
+        );
+
+        s.withdraw(erc_20_admin, erc_20_contract_hash).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, erc_20_admin), erc_20_admin_balance + 2);
+        assert_eq!(s.escrow_held()[&erc_20_contract_hash], U256::zero());
+        assert_eq!(s.package_token_balance(erc_20_contract_hash), U256::zero());
+        s.get_escrow_held(user_from).unwrap();
+    }
+
+    // The longest dispute window holds payments until the release time saturates instead of
+    // wrapping around to a time already past
+    #[test]
+    fn test_escrow_long_dispute_window() {
+        let mut s = Subscription::deployment_with_escrow(0, u64::MAX / 1000);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.set_block_time(1_000_000);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Held as u8));
+
+        s.set_block_time(u64::MAX - 1);
+        assert_eq!(
+            s.withdraw(user_to, erc_20_contract_hash),
+            Err(CallError::Contract(ContractError::NothingToWithdraw)),
+        );
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Held as u8));
+        assert_eq!(s.escrow_pending(user_to)[&erc_20_contract_hash], U256::from(TOKEN_AMOUNT_VALUE));
+    }
+
+    // A dispute window whose milliseconds overflow a u64 cannot be installed
+    #[test]
+    #[should_panic]
+    fn test_escrow_dispute_window_overflow() {
+        Subscription::deployment_with_escrow(0, u64::MAX / 1000 + 1);
+    }
+
+    // Within the dispute window the publisher can refund a payment and the subscriber can charge it back
+    #[test]
+    fn test_escrow_refund_and_chargeback() {
+        let mut s = Subscription::deployment_with_escrow(0, 60);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        // Three payments, the period is 0
+        s.set_block_time(1_000);
+        for _ in 0..3 {
+            s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        }
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - 3 * TOKEN_AMOUNT_VALUE));
+
+        // Only the publisher refunds
+        assert_eq!(
+            s.refund_payment(user_from, 0),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+        s.refund_payment(user_to, 0).unwrap();
+        assert_eq!(s.escrow_payment_state(0), Some(PaymentState::Refunded as u8));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - 2 * TOKEN_AMOUNT_VALUE));
+        assert_eq!(
+            s.refund_payment(user_to, 0),
+            Err(CallError::Contract(ContractError::PaymentReversed)),
+        );
+
+        // Only the subscriber charges back
+        assert_eq!(
+            s.chargeback_payment(user_to, 1),
+            Err(CallError::Contract(ContractError::NotSubscriber)),
+        );
+        s.chargeback_payment(user_from, 1).unwrap();
+        assert_eq!(s.escrow_payment_state(1), Some(PaymentState::ChargedBack as u8));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.escrow_pending(user_to)[&erc_20_contract_hash], U256::from(TOKEN_AMOUNT_VALUE));
+
+        assert_eq!(
+            s.chargeback_payment(user_from, 3),
+            Err(CallError::Contract(ContractError::UnknownPayment)),
+        );
+
+        // Once the window ended the payment belongs to the publisher
+        s.set_block_time(61_000);
+        assert_eq!(
+            s.chargeback_payment(user_from, 2),
+            Err(CallError::Contract(ContractError::DisputeWindowClosed)),
+        );
+        s.withdraw(user_to, erc_20_contract_hash).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.escrow_payment_state(2), Some(PaymentState::Released as u8));
+        assert_eq!(s.package_token_balance(erc_20_contract_hash), U256::zero());
+    }
+
+    // The publisher creates coupons, once per code and within 100%
+    #[test]
+    fn test_add_coupon() {
+        let mut s = Subscription::deployment(0);
+        let user_to = s.user_to;
+        let user_from = s.user_from;
+
+        // Only the publisher can change the plan
+        assert_eq!(
+            s.add_coupon(user_from, "LAUNCH", Coupon::percentage(5000)),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+
+        s.add_coupon(user_to, "LAUNCH", Coupon::percentage(5000)).unwrap();
+        assert_eq!(s.coupon_redemptions("LAUNCH"), Some(0));
+        assert_eq!(
+            s.add_coupon(user_to, "LAUNCH", Coupon::fixed_amount(U256::from(3))),
+            Err(CallError::Contract(ContractError::CouponExists)),
+        );
+
+        // Malformed coupons
+        assert_eq!(
+            s.add_coupon(user_to, "MORE", Coupon::percentage(TOTAL_BASIS_POINTS + 1)),
+            Err(CallError::Contract(ContractError::InvalidCoupon)),
+        );
+        assert_eq!(
+            s.add_coupon(user_to, "", Coupon::percentage(1000)),
+            Err(CallError::Contract(ContractError::InvalidCoupon)),
+        );
+        assert_eq!(
+            s.add_coupon(user_to, &"A".repeat(65), Coupon::percentage(1000)),
+            Err(CallError::Contract(ContractError::InvalidCoupon)),
+        );
+
+        assert_eq!(
+            s.expire_coupon(user_from, "LAUNCH"),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+        assert_eq!(
+            s.expire_coupon(user_to, "UNKNOWN"),
+            Err(CallError::Contract(ContractError::UnknownCoupon)),
+        );
+    }
+
+    // A coupon discounts the first payments of a subscription, then the full price is charged
+    #[test]
+    fn test_execute_subscription_with_coupon() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        s.add_coupon(user_to, "HALF", Coupon { periods: 2, ..Coupon::percentage(5000) }).unwrap();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk, user_from, "HALF").unwrap();
+        assert_eq!(s.coupon_redemptions("HALF"), Some(1));
+        assert_eq!(s.discounted_periods(subscription_id), Some(2));
+
+        // The signed terms keep the full price, the coupon only lowers what is charged
+        assert_eq!(subscription_hash, get_hex(get_hash_bytes(get_subscription_data(user_from, user_to, U256::from(TOKEN_AMOUNT_VALUE), 0, subscription_id, erc_20_contract_hash))));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        // Two payments at half price, then the full price, the period is 0
+        for _ in 0..3 {
+            s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        }
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(subscription["coupon_code"], "HALF");
+        assert_eq!(subscription["discounted_periods"], "0");
+        assert_eq!(subscription["payment_amount"], TOKEN_AMOUNT_VALUE.to_string());
+        assert_eq!(subscription["amount_paid"], (5 + 5 + 10).to_string());
+
+        assert_eq!(s.discounted_periods(subscription_id), Some(0));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - 5 - 5 - 10));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(5 + 5 + 10));
+    }
+
+    // Coupons can only be redeemed before they expire, as often as they allow and in their token
+    #[test]
+    fn test_coupon_limits() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        let other_token = s.install_token(eip_1337_admin);
+        s.add_token(user_to, other_token, U256::from(25)).unwrap();
+
+        s.add_coupon(user_to, "ONCE", Coupon { max_redemptions: 1, ..Coupon::percentage(1000) }).unwrap();
+        s.add_coupon(user_to, "SOON", Coupon { expires_at: 5_000, ..Coupon::percentage(1000) }).unwrap();
+        s.add_coupon(user_to, "ENDED", Coupon::percentage(1000)).unwrap();
+        s.add_coupon(user_to, "OTHER", Coupon { erc20_contract_hash: Some(other_token), ..Coupon::fixed_amount(U256::from(5)) }).unwrap();
+
+        assert_eq!(
+            s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "UNKNOWN"),
+            Err(CallError::Contract(ContractError::UnknownCoupon)),
+        );
+
+        s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "ONCE").unwrap();
+        assert_eq!(
+            s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "ONCE"),
+            Err(CallError::Contract(ContractError::CouponExhausted)),
+        );
+
+        s.set_block_time(4_000);
+        s.expire_coupon(user_to, "ENDED").unwrap();
+        assert_eq!(
+            s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "ENDED"),
+            Err(CallError::Contract(ContractError::CouponExpired)),
+        );
+        s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "SOON").unwrap();
+        s.set_block_time(5_000);
+        assert_eq!(
+            s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, "SOON"),
+            Err(CallError::Contract(ContractError::CouponExpired)),
+        );
+
+        // A fixed amount is in the coupon's token
+        assert_eq!(
+            s.create_subscription_hash_with_coupon(eip_1337_admin, eip_1337_admin_pk, user_from, "OTHER"),
+            Err(CallError::Contract(ContractError::InvalidCoupon)),
+        );
+        assert_eq!(s.subscription_count(), 2);
+        assert_eq!(s.coupon_redemptions("ONCE"), Some(1));
+        assert_eq!(s.coupon_redemptions("OTHER"), Some(0));
+    }
+
+    // Discounts never take the price below 0 and percentages round in the publisher's favour
+    #[test]
+    fn test_discounted_price() {
+        let percentage = DiscountType::Percentage;
+        let fixed_amount = DiscountType::FixedAmount;
+
+        assert_eq!(discounted_price(U256::from(10), percentage, U256::from(5000)), U256::from(5));
+        assert_eq!(discounted_price(U256::from(10), percentage, U256::from(1500)), U256::from(9));
+        assert_eq!(discounted_price(U256::from(10), percentage, U256::from(TOTAL_BASIS_POINTS)), U256::zero());
+        assert_eq!(discounted_price(U256::from(10), percentage, U256::zero()), U256::from(10));
+        assert_eq!(discounted_price(U256::MAX, percentage, U256::from(5000)), U256::MAX - U256::MAX / 2);
+
+        assert_eq!(discounted_price(U256::from(10), fixed_amount, U256::from(3)), U256::from(7));
+        assert_eq!(discounted_price(U256::from(10), fixed_amount, U256::from(30)), U256::zero());
+
+        assert!(is_valid_discount(percentage, U256::from(TOTAL_BASIS_POINTS)));
+        assert!(!is_valid_discount(percentage, U256::from(TOTAL_BASIS_POINTS + 1)));
+        assert!(is_valid_discount(fixed_amount, U256::MAX));
+    }
+
+    // A metered subscription pays the price of a period plus the usage reported since the last
+    // payment, and a payment above the max amount the subscriber signed reverts
+    #[test]
+    fn test_execute_metered_subscription() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+        s.set_unit_price(user_to, erc_20_contract_hash, U256::from(2)).unwrap();
+        assert_eq!(s.unit_prices().get(&erc_20_contract_hash), Some(&U256::from(2)));
+
+        let (subscription_id, subscription_hash) = s.create_metered_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, U256::from(30)).unwrap();
+
+        // The max amount and metering are part of the signed terms
+        let terms = SubscriptionTerms {
+            to: user_to,
+            from: user_from,
+            token_amount: U256::from(TOKEN_AMOUNT_VALUE),
+            period_seconds: 0,
+            subscription_id,
+            erc20_contract_hash: erc_20_contract_hash,
+            max_amount_per_period: Some(U256::from(30)),
+            lifetime_cap: None,
+            beneficiary: None,
+            metered: true,
+        };
+        assert_eq!(subscription_hash, get_hex(get_hash_bytes(terms.data())));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        // 10 + 5 units at 2
+        s.report_usage(user_to, subscription_id, 5).unwrap();
+        assert_eq!(s.usage(subscription_id), Some(5));
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        assert_eq!(s.usage(subscription_id), Some(0));
+
+        // 10 + 10 units at 2, the max amount
+        s.report_usage(user_to, subscription_id, 10).unwrap();
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+
+        // 10 + 11 units at 2 is above it, the payment reverts instead of charging less
+        s.report_usage(user_to, subscription_id, 11).unwrap();
+        assert_eq!(
+            s.execute_subscription(eip_1337_admin, signature, subscription_id),
+            Err(CallError::Contract(ContractError::PeriodCapReached)),
+        );
+        assert_eq!(s.usage(subscription_id), Some(11));
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(subscription["metered"], "true");
+        assert_eq!(subscription["usage"], "11");
+        assert_eq!(subscription["max_amount_per_period"], "30");
+        assert_eq!(subscription["payment_amount"], (10 + 11 * 2).to_string());
+        assert_eq!(subscription["amount_paid"], (20 + 30).to_string());
+        assert!(!subscription.contains_key("lifetime_cap"));
+
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - 20 - 30));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(20 + 30));
+
+        // a spending cap alone does not charge usage
+        let (capped_id, capped_hash) = s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk, user_from, Some(U256::from(30)), None).unwrap();
+        assert_eq!(s.report_usage(user_to, capped_id, 5), Err(CallError::Contract(ContractError::NotMetered)));
+
+        hex::decode_to_slice(capped_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let capped_signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+        s.execute_subscription(eip_1337_admin, capped_signature, capped_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(20 + 30 + TOKEN_AMOUNT_VALUE));
+    }
+
+    // Only the publisher or the usage oracle reports usage, and only of metered subscriptions
+    #[test]
+    fn test_report_usage() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        assert_eq!(s.usage_oracle(), user_to);
+
+        assert_eq!(
+            s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, Some(U256::from(TOKEN_AMOUNT_VALUE - 1)), None),
+            Err(CallError::Contract(ContractError::InvalidMaxAmount)),
+        );
+
+        // metering needs a max amount per period
+        assert_eq!(
+            s.call(
+                &eip_1337_admin,
+                "create_subscription_hash",
+                runtime_args! {
+                    "public" => eip_1337_admin_pk.clone(),
+                    "from" => user_from,
+                    "metered" => true
+                },
+            ),
+            Err(CallError::Contract(ContractError::InvalidMaxAmount)),
+        );
+
+        let (fixed_id, _) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        let (metered_id, _) = s.create_metered_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from, U256::from(50)).unwrap();
+
+        assert_eq!(s.report_usage(user_to, fixed_id, 1), Err(CallError::Contract(ContractError::NotMetered)));
+        assert_eq!(s.report_usage(user_to, 99, 1), Err(CallError::Contract(ContractError::UnknownSubscription)));
+        assert_eq!(s.report_usage(eip_1337_admin, metered_id, 1), Err(CallError::Contract(ContractError::NotUsageReporter)));
+
+        assert_eq!(s.set_usage_oracle(eip_1337_admin, eip_1337_admin), Err(CallError::Contract(ContractError::NotPublisher)));
+        s.set_usage_oracle(user_to, eip_1337_admin).unwrap();
+        assert_eq!(s.usage_oracle(), eip_1337_admin);
+
+        // usage adds up until the next payment
+        s.report_usage(eip_1337_admin, metered_id, 3).unwrap();
+        s.report_usage(user_to, metered_id, 4).unwrap();
+        assert_eq!(s.usage(metered_id), Some(7));
+
+        let other_token = s.install_token(eip_1337_admin);
+        assert_eq!(
+            s.set_unit_price(user_to, other_token, U256::from(1)),
+            Err(CallError::Contract(ContractError::UnsupportedToken)),
+        );
+        assert_eq!(
+            s.set_unit_price(eip_1337_admin, erc_20_contract_hash, U256::from(1)),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+    }
+
+    // The metered charge adds the usage to the base price, saturating instead of overflowing
+    #[test]
+    fn test_metered_price() {
+        assert_eq!(metered_price(U256::from(10), 5, U256::from(2)), U256::from(20));
+        assert_eq!(metered_price(U256::from(10), 50, U256::from(2)), U256::from(110));
+        assert_eq!(metered_price(U256::from(10), 50, U256::zero()), U256::from(10));
+        assert_eq!(metered_price(U256::from(10), u64::MAX, U256::MAX), U256::MAX);
+    }
+
+    // The lifetime cap stops payments once the next one would go past it
+    #[test]
+    fn test_lifetime_cap() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        assert_eq!(
+            s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, None, Some(U256::from(TOKEN_AMOUNT_VALUE - 1))),
+            Err(CallError::Contract(ContractError::InvalidMaxAmount)),
+        );
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk, user_from, None, Some(U256::from(25))).unwrap();
+
+        // The lifetime cap is part of the signed terms
+        let terms = SubscriptionTerms {
+            to: user_to,
+            from: user_from,
+            token_amount: U256::from(TOKEN_AMOUNT_VALUE),
+            period_seconds: 0,
+            subscription_id,
+            erc20_contract_hash: erc_20_contract_hash,
+            max_amount_per_period: None,
+            lifetime_cap: Some(U256::from(25)),
+            beneficiary: None,
+            metered: false,
+        };
+        assert_eq!(subscription_hash, get_hex(get_hash_bytes(terms.data())));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        assert_eq!(s.amount_paid(subscription_id), Some(U256::from(20)));
+
+        // a third payment would make 30
+        assert_eq!(
+            s.execute_subscription(eip_1337_admin, signature, subscription_id),
+            Err(CallError::Contract(ContractError::SpendingCapReached)),
+        );
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(20));
+    }
+
+    // A gift subscription debits the payer and gives the beneficiary access
+    #[test]
+    fn test_gift_subscription() {
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        let beneficiary = AccountHash::new([7u8; 32]);
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        let (subscription_id, subscription_hash) = s.create_subscription_hash_with_beneficiary(eip_1337_admin, eip_1337_admin_pk, user_from, beneficiary).unwrap();
+        assert_eq!(s.beneficiary(subscription_id), Some(beneficiary));
+        assert_eq!(s.account_subscriptions(user_from), vec![subscription_id]);
+
+        // the beneficiary is given access without being counted as a subscriber
+        assert_eq!(s.account_access(beneficiary), vec![subscription_id]);
+        assert_eq!(s.account_subscriptions(beneficiary), Vec::<u64>::new());
+        assert_eq!(s.subscriber_count(), 1);
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, beneficiary), Ok(vec![subscription_id]));
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, user_from), Ok(vec![subscription_id]));
+        assert_eq!(s.get_subscriber_count(eip_1337_admin), Ok(1));
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(subscription["from"], user_from.to_formatted_string());
+        assert_eq!(subscription["beneficiary"], beneficiary.to_formatted_string());
+        s.get_active_subscription(eip_1337_admin, beneficiary, 0).unwrap();
+
+        // The beneficiary is part of the signed terms
+        let terms = SubscriptionTerms {
+            to: user_to,
+            from: user_from,
+            token_amount: U256::from(TOKEN_AMOUNT_VALUE),
+            period_seconds: 0,
+            subscription_id,
+            erc20_contract_hash: erc_20_contract_hash,
+            max_amount_per_period: None,
+            lifetime_cap: None,
+            beneficiary: Some(beneficiary),
+            metered: false,
+        };
+        assert_eq!(subscription_hash, get_hex(get_hash_bytes(terms.data())));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash.clone(), &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        // the payer is debited
+        let balance = s.token_balance_of(erc_20_contract_hash, user_from);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), balance - U256::from(TOKEN_AMOUNT_VALUE));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, beneficiary), U256::zero());
+
+        // and the beneficiary is given access, not the payer
+        s.is_subscription_active_for(eip_1337_admin, subscription_hash.clone(), 60, beneficiary).unwrap();
+        assert_eq!(
+            s.is_subscription_active_for(eip_1337_admin, subscription_hash.clone(), 60, user_from),
+            Err(CallError::Contract(ContractError::NotBeneficiary)),
+        );
+        assert_eq!(
+            s.is_subscription_active_for(eip_1337_admin, subscription_hash, 60, user_to),
+            Err(CallError::Contract(ContractError::NotBeneficiary)),
+        );
+
+        // paying for itself is not a gift, the hash is the one without beneficiary
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        let (subscription_id, _) = s.create_subscription_hash_with_beneficiary(eip_1337_admin, eip_1337_admin_pk, user_from, user_from).unwrap();
+        assert_eq!(s.beneficiary(subscription_id), None);
+    }
+
+    // A seat-based plan charges every member, prorating the seats changed during a period
+    #[test]
+    fn test_seat_subscription() {
+        let mut s = Subscription::deployment_with_seats(3600);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        let member = AccountHash::new([7u8; 32]);
+
+        assert!(s.seat_based());
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        // the subscriber holds the first seat
+        let start = 1_000_000;
+        s.set_block_time(start);
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+        assert_eq!(s.members(subscription_id), vec![user_from]);
+        s.get_members(eip_1337_admin, subscription_id).unwrap();
+
+        // only the subscriber manages the members
+        assert_eq!(
+            s.add_member(eip_1337_admin, subscription_id, member),
+            Err(CallError::Contract(ContractError::NotSubscriber)),
+        );
+
+        // seats added half way through the period are credited the first half
+        s.set_block_time(start + 1_800_000);
+        s.add_member(user_from, subscription_id, member).unwrap();
+        s.add_member(user_from, subscription_id, user_to).unwrap();
+        assert_eq!(
+            s.add_member(user_from, subscription_id, member),
+            Err(CallError::Contract(ContractError::MemberExists)),
+        );
+        assert_eq!(s.members(subscription_id), vec![user_from, member, user_to]);
+        assert_eq!(s.seat_proration(subscription_id), (U256::zero(), U256::from(10)));
+
+        // members are given access without being counted as subscribers
+        assert_eq!(s.account_access(member), vec![subscription_id]);
+        assert_eq!(s.account_subscriptions(member), Vec::<u64>::new());
+        assert_eq!(s.subscriber_count(), 1);
+        assert_eq!(s.subscriber(1), None);
+
+        // a seat removed three quarters through the period is charged for them
+        s.set_block_time(start + 2_700_000);
+        s.remove_member(user_from, subscription_id, user_to).unwrap();
+        assert_eq!(
+            s.remove_member(user_from, subscription_id, user_to),
+            Err(CallError::Contract(ContractError::UnknownMember)),
+        );
+        assert_eq!(s.seat_proration(subscription_id), (U256::from(7), U256::from(10)));
+        assert_eq!(s.account_access(user_to), Vec::<u64>::new());
+        assert_eq!(s.account_access(member), vec![subscription_id]);
+
+        // the subscriber lists its subscription once, although it also holds a seat of it
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, user_from), Ok(vec![subscription_id]));
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, member), Ok(vec![subscription_id]));
+        assert_eq!(s.get_account_subscriptions(eip_1337_admin, user_to), Ok(vec![]));
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(subscription["seats"], "2");
+        assert_eq!(subscription["payment_amount"], "17");
+        assert_eq!(s.get_plan(eip_1337_admin).unwrap()["seat_based"], "true");
+
+        // every member has access, removed ones do not
+        s.is_subscription_active_for(eip_1337_admin, subscription_hash.clone(), 0, member).unwrap();
+        s.is_subscription_active_for(eip_1337_admin, subscription_hash.clone(), 0, user_from).unwrap();
+        assert_eq!(
+            s.is_subscription_active_for(eip_1337_admin, subscription_hash.clone(), 0, user_to),
+            Err(CallError::Contract(ContractError::NotBeneficiary)),
+        );
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        // 2 seats, plus 7 for the removed one, less 2 * 5 for the added ones
+        s.set_block_time(start + 3_600_000);
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(17));
+        assert_eq!(s.seat_proration(subscription_id), (U256::zero(), U256::zero()));
+
+        // then whole seats
+        s.set_block_time(start + 7_200_000);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(37));
+
+        // a subscription keeps at least one seat
+        s.remove_member(user_from, subscription_id, member).unwrap();
+        assert_eq!(
+            s.remove_member(user_from, subscription_id, user_from),
+            Err(CallError::Contract(ContractError::LastSeat)),
+        );
+
+        // a max amount per period must cover every seat
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        let (capped_id, _) = s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk, user_from, Some(U256::from(15)), None).unwrap();
+        assert_eq!(
+            s.add_member(user_from, capped_id, member),
+            Err(CallError::Contract(ContractError::InvalidMaxAmount)),
+        );
+        assert_eq!(s.members(capped_id), vec![user_from]);
+
+        // and members are only managed on seat-based plans
+        let mut s = Subscription::deployment(0);
+        let user_from = s.user_from;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+        assert!(!s.seat_based());
+        let (subscription_id, _) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+        assert_eq!(
+            s.add_member(user_from, subscription_id, member),
+            Err(CallError::Contract(ContractError::NotSeatBased)),
+        );
+    }
+
+    // Seat changes are prorated over the period, rounded down
+    #[test]
+    fn test_seat_prices() {
+        assert_eq!(prorated_price(U256::from(10), 1_800_000, 3_600_000), U256::from(5));
+        assert_eq!(prorated_price(U256::from(10), 2_700_000, 3_600_000), U256::from(7));
+        assert_eq!(prorated_price(U256::from(10), 7_200_000, 3_600_000), U256::from(10));
+        assert_eq!(prorated_price(U256::from(10), 1_000, 0), U256::zero());
+        assert_eq!(prorated_price(U256::MAX, 1, 2), U256::MAX / 2);
+
+        assert_eq!(seats_price(U256::from(10), 3, U256::from(7), U256::from(10)), U256::from(27));
+        assert_eq!(seats_price(U256::from(10), 1, U256::zero(), U256::from(20)), U256::zero());
+        assert_eq!(seats_price(U256::MAX, 2, U256::zero(), U256::zero()), U256::MAX);
+    }
+
+    // Calendar payments are due on the billing day, clamped to the end of shorter months
+    #[test]
+    fn test_execute_monthly_subscription() {
+        let mut s = Subscription::deployment_with_interval(0, IntervalType::Monthly, 31);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        assert_eq!(s.interval_type(), Some(IntervalType::Monthly));
+        assert_eq!(s.billing_day(), 31);
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        // subscribing on January 15th 2024, the first payment is due on the 31st
+        s.set_block_time(days_from_civil(2024, 1, 15) * MILLIS_PER_DAY + 1_000);
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+        assert_eq!(s.next_valid_timestamp(subscription_id), Some(days_from_civil(2024, 1, 31) * MILLIS_PER_DAY));
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        assert_eq!(
+            s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id),
+            Err(CallError::Contract(ContractError::InvalidBlockTime)),
+        );
+
+        // paid late on February 2nd, the next payment is still due at the end of February
+        s.set_block_time(days_from_civil(2024, 2, 2) * MILLIS_PER_DAY);
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        assert_eq!(s.next_valid_timestamp(subscription_id), Some(days_from_civil(2024, 2, 29) * MILLIS_PER_DAY));
+
+        // and the one after on March 31st, not the 29th
+        s.set_block_time(days_from_civil(2024, 2, 29) * MILLIS_PER_DAY);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(s.next_valid_timestamp(subscription_id), Some(days_from_civil(2024, 3, 31) * MILLIS_PER_DAY));
+
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(2 * TOKEN_AMOUNT_VALUE));
+    }
+
+    // A price change takes effect after a billing period of notice, without breaking signatures
+    #[test]
+    fn test_schedule_price_change() {
+        let mut s = Subscription::deployment(60);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let erc_20_contract_hash = s.erc_20_contract_hash;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        // the subscriptions are first due at 60_000
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        let (cancelled_id, cancelled_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk.clone(), user_from).unwrap();
+        let (capped_id, capped_hash) = s.create_subscription_hash_with_caps(eip_1337_admin, eip_1337_admin_pk.clone(), user_from, Some(U256::from(12)), None).unwrap();
+
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        hex::decode_to_slice(cancelled_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let cancelled_signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        hex::decode_to_slice(capped_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let capped_signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+
+        s.set_block_time(1_000);
+        assert_eq!(
+            s.schedule_price_change(user_to, erc_20_contract_hash, U256::from(15), 60_000),
+            Err(CallError::Contract(ContractError::PriceNoticeTooShort)),
+        );
+        assert_eq!(
+            s.schedule_price_change(eip_1337_admin, erc_20_contract_hash, U256::from(15), 61_000),
+            Err(CallError::Contract(ContractError::NotPublisher)),
+        );
+        let other_token = s.install_token(eip_1337_admin);
+        assert_eq!(
+            s.schedule_price_change(user_to, other_token, U256::from(15), 61_000),
+            Err(CallError::Contract(ContractError::UnsupportedToken)),
+        );
+
+        s.schedule_price_change(user_to, erc_20_contract_hash, U256::from(15), 61_000).unwrap();
+        assert_eq!(s.pending_prices().get(&erc_20_contract_hash), Some(&(U256::from(15), 61_000)));
+        s.get_pending_prices(eip_1337_admin).unwrap();
+        let subscription = s.get_subscription(eip_1337_admin, subscription_id).unwrap();
+        assert_eq!(subscription["token_amount"], TOKEN_AMOUNT_VALUE.to_string());
+        assert_eq!(subscription["payment_amount"], TOKEN_AMOUNT_VALUE.to_string());
+        assert_eq!(subscription["pending_token_amount"], "15");
+        assert_eq!(subscription["effective_at"], "61000");
+
+        // a subscriber who does not accept the new price cancels before it takes effect
+        s.cancel_subscription(eip_1337_admin, cancelled_signature, cancelled_id).unwrap();
+        assert_eq!(s.get_subscription_status(eip_1337_admin, cancelled_id), Ok(SubscriptionStatus::Cancelled as u8));
+
+        // the old price until the change takes effect
+        s.set_block_time(60_000);
+        s.execute_subscription(eip_1337_admin, signature.clone(), subscription_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(TOKEN_AMOUNT_VALUE));
+
+        // then the new one, with the signature made for the old one
+        s.set_block_time(120_000);
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(TOKEN_AMOUNT_VALUE + 15));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_from), U256::from(1000 - TOKEN_AMOUNT_VALUE - 15));
+
+        // a period that fell due before the change is charged the old price, however late it is paid
+        s.execute_subscription(eip_1337_admin, capped_signature.clone(), capped_id).unwrap();
+        assert_eq!(s.next_valid_timestamp(capped_id), Some(120_000));
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(TOKEN_AMOUNT_VALUE + 15 + TOKEN_AMOUNT_VALUE));
+
+        // and a subscriber who signed a max amount below the new price is not charged it
+        assert_eq!(
+            s.execute_subscription(eip_1337_admin, capped_signature, capped_id),
+            Err(CallError::Contract(ContractError::PeriodCapReached)),
+        );
+        assert_eq!(s.token_balance_of(erc_20_contract_hash, user_to), U256::from(TOKEN_AMOUNT_VALUE + 15 + TOKEN_AMOUNT_VALUE));
+
+        // the price in effect is written to token_prices when the next change is scheduled
+        s.schedule_price_change(user_to, erc_20_contract_hash, U256::from(20), 200_000).unwrap();
+        assert_eq!(s.token_prices().get(&erc_20_contract_hash), Some(&U256::from(15)));
+        assert_eq!(s.pending_prices().get(&erc_20_contract_hash), Some(&(U256::from(20), 200_000)));
+
+        // and subscribers from now on sign for it
+        let (new_id, new_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+        assert_eq!(new_hash, get_hex(get_hash_bytes(get_subscription_data(user_from, user_to, U256::from(15), 60, new_id, erc_20_contract_hash))));
+    }
+
+    // Another contract gates content on an active subscription, paid for or held as a seat
+    #[test]
+    fn test_gated_content() {
+        let mut s = Subscription::deployment_with_seats(3600);
+        let user_from = s.user_from;
+        let user_to = s.user_to;
+        let eip_1337_admin = s.eip_1337_admin;
+        let eip_1337_admin_pk = s.eip_1337_admin_pk.clone();
+
+        let gated_content = s.install_gated_content(eip_1337_admin);
+        s.fund_and_approve(1000, 1000).unwrap();
+
+        // no access without a subscription
+        let start = 1_000_000;
+        s.set_block_time(start);
+        assert_eq!(
+            s.read_content(user_from, gated_content),
+            Err(CallError::User(AccessError::NoActiveSubscription as u16)),
+        );
+        s.get_active_subscription(eip_1337_admin, user_from, 0).unwrap();
+        assert_eq!(s.gated_content_reads(eip_1337_admin), 0);
+
+        // the subscriber and its members read until the payment is due
+        let (subscription_id, subscription_hash) = s.create_subscription_hash(eip_1337_admin, eip_1337_admin_pk, user_from).unwrap();
+        s.read_content(user_from, gated_content).unwrap();
+        assert_eq!(
+            s.read_content(user_to, gated_content),
+            Err(CallError::User(AccessError::NoActiveSubscription as u16)),
+        );
+        s.add_member(user_from, subscription_id, user_to).unwrap();
+        assert_eq!(s.account_access(user_to), vec![subscription_id]);
+        s.read_content(user_to, gated_content).unwrap();
+        assert_eq!(s.gated_content_reads(eip_1337_admin), 2);
+
+        s.set_block_time(start + 3_600_000);
+        assert_eq!(
+            s.read_content(user_from, gated_content),
+            Err(CallError::User(AccessError::NoActiveSubscription as u16)),
+        );
+        s.get_active_subscription(eip_1337_admin, user_from, 60).unwrap();
+
+        // and again once it is paid
+        let mut subscription_bytes = [0u8;32];
+        hex::decode_to_slice(subscription_hash, &mut subscription_bytes as &mut [u8]).unwrap();
+        let signature = sign(&generate_eip_1337_secret_key(), subscription_bytes).unwrap();
+        s.execute_subscription(eip_1337_admin, signature, subscription_id).unwrap();
+        s.read_content(user_from, gated_content).unwrap();
+        assert_eq!(s.gated_content_reads(eip_1337_admin), 3);
+
+        // removed members lose access
+        s.remove_member(user_from, subscription_id, user_to).unwrap();
+        assert_eq!(
+            s.read_content(user_to, gated_content),
+            Err(CallError::User(AccessError::NoActiveSubscription as u16)),
+        );
+        assert_eq!(s.gated_content_reads(eip_1337_admin), 3);
     }
 
-    // TODO: 1.4 tests once return values are supported:
-    // is_subscription_active tests
+    // TODO: list_subscribers, get_subscription_hash, create_subscription_hash and list_subscriptions
+    // return values through the view probe
     // is_subscription_ready tests
 
 }